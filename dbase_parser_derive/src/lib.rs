@@ -0,0 +1,100 @@
+//! The `#[derive(WritableRecord)]` macro of `dbase_parser`, enabled by its `derive` feature.
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitInt, LitStr};
+
+/// The column a struct field is written to, from its `#[dbase(...)]` attribute.
+struct Column {
+    name: String,
+    length: Option<LitInt>,
+    decimal_count: Option<LitInt>
+}
+
+fn parse_column(field: &syn::Field) -> Result<Column, syn::Error> {
+    let ident = field.ident.as_ref().expect("Only named fields are supported");
+    let mut column = Column {
+        name: ident.to_string().to_uppercase(),
+        length: None,
+        decimal_count: None
+    };
+    let mut renamed = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("dbase")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                column.name = meta.value()?.parse::<LitStr>()?.value();
+                renamed = true;
+            } else if meta.path.is_ident("length") {
+                column.length = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("decimal_count") {
+                column.decimal_count = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `name`, `length` or `decimal_count`"));
+            }
+            Ok(())
+        })?;
+    }
+    // Catch the most common mistake here rather than when the database is created
+    if !renamed && column.name.len() > 10 {
+        return Err(syn::Error::new_spanned(ident, format!("The column name {} is longer than 10 bytes, set a shorter one with #[dbase(name = \"...\")]", column.name)));
+    }
+    Ok(column)
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, syn::Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(&input.ident, "WritableRecord can only be derived for structs with named fields"))
+        },
+        _ => return Err(syn::Error::new_spanned(&input.ident, "WritableRecord can only be derived for structs"))
+    };
+    let mut descriptors = vec![];
+    let mut values = vec![];
+    for field in fields {
+        let column = parse_column(field)?;
+        let ident = &field.ident;
+        let ty = &field.ty;
+        let name = &column.name;
+        let length = match &column.length {
+            Some(length) => quote!(#length),
+            None => quote!(<#ty as ::dbase_parser::FieldData>::LENGTH)
+        };
+        let decimal_count = match &column.decimal_count {
+            Some(decimal_count) => quote!(#decimal_count),
+            None => quote!(<#ty as ::dbase_parser::FieldData>::DECIMAL_COUNT)
+        };
+        descriptors.push(quote! {
+            ::dbase_parser::header::FieldDescriptor::new(#name, <#ty as ::dbase_parser::FieldData>::TYPE_CODE, #length, #decimal_count)?
+        });
+        values.push(quote! {
+            values.insert(#name.to_string(), ::dbase_parser::FieldData::to_field_value(&self.#ident));
+        });
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::dbase_parser::WritableRecord for #ident #ty_generics #where_clause {
+            fn fields() -> ::std::result::Result<::std::vec::Vec<::dbase_parser::header::FieldDescriptor>, ::dbase_parser::DbaseError> {
+                Ok(vec![#(#descriptors),*])
+            }
+            fn values(&self) -> ::std::collections::HashMap<::std::string::String, ::dbase_parser::FieldValue> {
+                let mut values = ::std::collections::HashMap::new();
+                #(#values)*
+                values
+            }
+        }
+    })
+}
+
+/// Implements `dbase_parser::WritableRecord` for a struct, one column per field.
+///
+/// Columns are named after the fields, in upper case, and typed after `FieldData`. The
+/// `#[dbase(name = "...", length = ..., decimal_count = ...)]` attribute overrides any of them.
+#[proc_macro_derive(WritableRecord, attributes(dbase))]
+pub fn derive_writable_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
@@ -0,0 +1,225 @@
+use encoding_rs::{EncoderResult, Encoding};
+
+/// A code page text fields can be written in, see `DbfWriter::code_page`.
+///
+/// Each code page is stored in the header as a language driver byte, which dBASE and FoxPro use to
+/// pick the code page back when reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodePage {
+    /// The original IBM PC code page, for US MS-DOS
+    Dos437,
+    /// The international MS-DOS code page, also known as DOS Latin-1
+    Dos850,
+    /// The Russian MS-DOS code page
+    Dos866,
+    Windows874,
+    Windows1250,
+    Windows1251,
+    Windows1252,
+    Windows1253,
+    Windows1254,
+    Windows1255,
+    Windows1256,
+    Windows1257,
+    ShiftJis,
+    Gbk,
+    EucKr,
+    Big5,
+    Macintosh,
+    MacCyrillic
+}
+
+/// What happens to characters a code page cannot represent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unmappable {
+    /// Refuse the value with `DbaseError::InvalidFieldValue`
+    Error,
+    /// Write a `?` instead of the character
+    Substitute
+}
+
+/// Characters 0x80 to 0xff of code page 437.
+const DOS_437: [char; 128] = [
+    '\u{00c7}', '\u{00fc}', '\u{00e9}', '\u{00e2}', '\u{00e4}', '\u{00e0}', '\u{00e5}', '\u{00e7}',
+    '\u{00ea}', '\u{00eb}', '\u{00e8}', '\u{00ef}', '\u{00ee}', '\u{00ec}', '\u{00c4}', '\u{00c5}',
+    '\u{00c9}', '\u{00e6}', '\u{00c6}', '\u{00f4}', '\u{00f6}', '\u{00f2}', '\u{00fb}', '\u{00f9}',
+    '\u{00ff}', '\u{00d6}', '\u{00dc}', '\u{00a2}', '\u{00a3}', '\u{00a5}', '\u{20a7}', '\u{0192}',
+    '\u{00e1}', '\u{00ed}', '\u{00f3}', '\u{00fa}', '\u{00f1}', '\u{00d1}', '\u{00aa}', '\u{00ba}',
+    '\u{00bf}', '\u{2310}', '\u{00ac}', '\u{00bd}', '\u{00bc}', '\u{00a1}', '\u{00ab}', '\u{00bb}',
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2502}', '\u{2524}', '\u{2561}', '\u{2562}', '\u{2556}',
+    '\u{2555}', '\u{2563}', '\u{2551}', '\u{2557}', '\u{255d}', '\u{255c}', '\u{255b}', '\u{2510}',
+    '\u{2514}', '\u{2534}', '\u{252c}', '\u{251c}', '\u{2500}', '\u{253c}', '\u{255e}', '\u{255f}',
+    '\u{255a}', '\u{2554}', '\u{2569}', '\u{2566}', '\u{2560}', '\u{2550}', '\u{256c}', '\u{2567}',
+    '\u{2568}', '\u{2564}', '\u{2565}', '\u{2559}', '\u{2558}', '\u{2552}', '\u{2553}', '\u{256b}',
+    '\u{256a}', '\u{2518}', '\u{250c}', '\u{2588}', '\u{2584}', '\u{258c}', '\u{2590}', '\u{2580}',
+    '\u{03b1}', '\u{00df}', '\u{0393}', '\u{03c0}', '\u{03a3}', '\u{03c3}', '\u{00b5}', '\u{03c4}',
+    '\u{03a6}', '\u{0398}', '\u{03a9}', '\u{03b4}', '\u{221e}', '\u{03c6}', '\u{03b5}', '\u{2229}',
+    '\u{2261}', '\u{00b1}', '\u{2265}', '\u{2264}', '\u{2320}', '\u{2321}', '\u{00f7}', '\u{2248}',
+    '\u{00b0}', '\u{2219}', '\u{00b7}', '\u{221a}', '\u{207f}', '\u{00b2}', '\u{25a0}', '\u{00a0}'
+];
+
+/// Characters 0x80 to 0xff of code page 850.
+const DOS_850: [char; 128] = [
+    '\u{00c7}', '\u{00fc}', '\u{00e9}', '\u{00e2}', '\u{00e4}', '\u{00e0}', '\u{00e5}', '\u{00e7}',
+    '\u{00ea}', '\u{00eb}', '\u{00e8}', '\u{00ef}', '\u{00ee}', '\u{00ec}', '\u{00c4}', '\u{00c5}',
+    '\u{00c9}', '\u{00e6}', '\u{00c6}', '\u{00f4}', '\u{00f6}', '\u{00f2}', '\u{00fb}', '\u{00f9}',
+    '\u{00ff}', '\u{00d6}', '\u{00dc}', '\u{00f8}', '\u{00a3}', '\u{00d8}', '\u{00d7}', '\u{0192}',
+    '\u{00e1}', '\u{00ed}', '\u{00f3}', '\u{00fa}', '\u{00f1}', '\u{00d1}', '\u{00aa}', '\u{00ba}',
+    '\u{00bf}', '\u{00ae}', '\u{00ac}', '\u{00bd}', '\u{00bc}', '\u{00a1}', '\u{00ab}', '\u{00bb}',
+    '\u{2591}', '\u{2592}', '\u{2593}', '\u{2502}', '\u{2524}', '\u{00c1}', '\u{00c2}', '\u{00c0}',
+    '\u{00a9}', '\u{2563}', '\u{2551}', '\u{2557}', '\u{255d}', '\u{00a2}', '\u{00a5}', '\u{2510}',
+    '\u{2514}', '\u{2534}', '\u{252c}', '\u{251c}', '\u{2500}', '\u{253c}', '\u{00e3}', '\u{00c3}',
+    '\u{255a}', '\u{2554}', '\u{2569}', '\u{2566}', '\u{2560}', '\u{2550}', '\u{256c}', '\u{00a4}',
+    '\u{00f0}', '\u{00d0}', '\u{00ca}', '\u{00cb}', '\u{00c8}', '\u{0131}', '\u{00cd}', '\u{00ce}',
+    '\u{00cf}', '\u{2518}', '\u{250c}', '\u{2588}', '\u{2584}', '\u{00a6}', '\u{00cc}', '\u{2580}',
+    '\u{00d3}', '\u{00df}', '\u{00d4}', '\u{00d2}', '\u{00f5}', '\u{00d5}', '\u{00b5}', '\u{00fe}',
+    '\u{00de}', '\u{00da}', '\u{00db}', '\u{00d9}', '\u{00fd}', '\u{00dd}', '\u{00af}', '\u{00b4}',
+    '\u{00ad}', '\u{00b1}', '\u{2017}', '\u{00be}', '\u{00b6}', '\u{00a7}', '\u{00f7}', '\u{00b8}',
+    '\u{00b0}', '\u{00a8}', '\u{00b7}', '\u{00b9}', '\u{00b3}', '\u{00b2}', '\u{25a0}', '\u{00a0}'
+];
+
+impl CodePage {
+    /// The language driver byte of the code page, stored at offset 29 of the header.
+    pub fn language_driver(self) -> u8 {
+        match self {
+            CodePage::Dos437 => 0x01,
+            CodePage::Dos850 => 0x02,
+            CodePage::Dos866 => 0x65,
+            CodePage::Windows874 => 0x7c,
+            CodePage::Windows1250 => 0xc8,
+            CodePage::Windows1251 => 0xc9,
+            CodePage::Windows1252 => 0x03,
+            CodePage::Windows1253 => 0xcb,
+            CodePage::Windows1254 => 0xca,
+            CodePage::Windows1255 => 0x7d,
+            CodePage::Windows1256 => 0x7e,
+            CodePage::Windows1257 => 0xcc,
+            CodePage::ShiftJis => 0x7b,
+            CodePage::Gbk => 0x7a,
+            CodePage::EucKr => 0x79,
+            CodePage::Big5 => 0x78,
+            CodePage::Macintosh => 0x04,
+            CodePage::MacCyrillic => 0x96
+        }
+    }
+
+    /// The code page a language driver byte stands for. Blank and unknown drivers fall back to
+    /// Windows-1252, as they do when reading.
+    pub fn from_language_driver(language_driver: u8) -> CodePage {
+        match language_driver {
+            0x01 => CodePage::Dos437,
+            0x02 => CodePage::Dos850,
+            0x04 => CodePage::Macintosh,
+            0x13 | 0x7b => CodePage::ShiftJis,
+            0x26 | 0x65 => CodePage::Dos866,
+            0x4d | 0x7a => CodePage::Gbk,
+            0x4e | 0x79 => CodePage::EucKr,
+            0x4f | 0x78 => CodePage::Big5,
+            0x50 | 0x7c => CodePage::Windows874,
+            0x7d => CodePage::Windows1255,
+            0x7e => CodePage::Windows1256,
+            0x96 => CodePage::MacCyrillic,
+            0xc8 => CodePage::Windows1250,
+            0xc9 => CodePage::Windows1251,
+            0xca => CodePage::Windows1254,
+            0xcb => CodePage::Windows1253,
+            0xcc => CodePage::Windows1257,
+            _ => CodePage::Windows1252
+        }
+    }
+
+    /// The name of the code page, for error messages.
+    pub fn name(self) -> &'static str {
+        self.charset().name()
+    }
+
+    pub(crate) fn charset(self) -> Charset {
+        match self {
+            CodePage::Dos437 => Charset::Dos("IBM437", &DOS_437),
+            CodePage::Dos850 => Charset::Dos("IBM850", &DOS_850),
+            CodePage::Dos866 => Charset::Encoding(encoding_rs::IBM866),
+            CodePage::Windows874 => Charset::Encoding(encoding_rs::WINDOWS_874),
+            CodePage::Windows1250 => Charset::Encoding(encoding_rs::WINDOWS_1250),
+            CodePage::Windows1251 => Charset::Encoding(encoding_rs::WINDOWS_1251),
+            CodePage::Windows1252 => Charset::Encoding(encoding_rs::WINDOWS_1252),
+            CodePage::Windows1253 => Charset::Encoding(encoding_rs::WINDOWS_1253),
+            CodePage::Windows1254 => Charset::Encoding(encoding_rs::WINDOWS_1254),
+            CodePage::Windows1255 => Charset::Encoding(encoding_rs::WINDOWS_1255),
+            CodePage::Windows1256 => Charset::Encoding(encoding_rs::WINDOWS_1256),
+            CodePage::Windows1257 => Charset::Encoding(encoding_rs::WINDOWS_1257),
+            CodePage::ShiftJis => Charset::Encoding(encoding_rs::SHIFT_JIS),
+            CodePage::Gbk => Charset::Encoding(encoding_rs::GBK),
+            CodePage::EucKr => Charset::Encoding(encoding_rs::EUC_KR),
+            CodePage::Big5 => Charset::Encoding(encoding_rs::BIG5),
+            CodePage::Macintosh => Charset::Encoding(encoding_rs::MACINTOSH),
+            CodePage::MacCyrillic => Charset::Encoding(encoding_rs::X_MAC_CYRILLIC)
+        }
+    }
+}
+
+/// How text is encoded: a DOS code page `encoding_rs` doesn't know, given by its characters
+/// above 0x7f, or an `encoding_rs` encoding.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Charset {
+    Dos(&'static str, &'static [char; 128]),
+    Encoding(&'static Encoding)
+}
+
+impl Charset {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Charset::Dos(name, _) => name,
+            Charset::Encoding(encoding) => encoding.name()
+        }
+    }
+
+    /// Decodes `data`, or returns `None` if it isn't valid in the charset.
+    pub(crate) fn decode(self, data: &[u8]) -> Option<String> {
+        match self {
+            Charset::Dos(_, table) => Some(data.iter().map(|r| match r.is_ascii() {
+                true => char::from(*r),
+                false => table[(r - 0x80) as usize]
+            }).collect()),
+            Charset::Encoding(encoding) => encoding.decode_without_bom_handling_and_without_replacement(data).map(|r| r.into_owned())
+        }
+    }
+
+    /// Encodes `text`, returning the first character the charset cannot represent as an error
+    /// unless `unmappable` substitutes it.
+    pub(crate) fn encode(self, text: &str, unmappable: Unmappable) -> Result<Vec<u8>, char> {
+        let mut bytes = Vec::with_capacity(text.len());
+        let substitute = |c: char, bytes: &mut Vec<u8>| match unmappable {
+            Unmappable::Error => Err(c),
+            Unmappable::Substitute => {
+                bytes.push(b'?');
+                Ok(())
+            }
+        };
+        let encoding = match self {
+            Charset::Dos(_, table) => {
+                for c in text.chars() {
+                    match (c.is_ascii(), table.iter().position(|r| *r == c)) {
+                        (true, _) => bytes.push(c as u8),
+                        (false, Some(index)) => bytes.push(0x80 + index as u8),
+                        (false, None) => substitute(c, &mut bytes)?
+                    }
+                }
+                return Ok(bytes);
+            },
+            Charset::Encoding(encoding) => encoding
+        };
+        let mut encoder = encoding.new_encoder();
+        let mut buffer = vec![0; encoder.max_buffer_length_from_utf8_without_replacement(text.len()).unwrap_or(text.len() * 4)];
+        let mut rest = text;
+        loop {
+            let (result, read, written) = encoder.encode_from_utf8_without_replacement(rest, &mut buffer, true);
+            bytes.extend_from_slice(&buffer[..written]);
+            rest = &rest[read..];
+            match result {
+                EncoderResult::InputEmpty => return Ok(bytes),
+                EncoderResult::OutputFull => continue,
+                EncoderResult::Unmappable(c) => substitute(c, &mut bytes)?
+            }
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use chrono::NaiveDate;
+use std::collections::HashMap;
+use std::io;
+use std::io::{BufRead, BufReader, Read};
+use super::header::FieldDescriptor;
+use super::fields;
+use super::fields::FieldValue;
+use super::error::DbaseError;
+use super::writer::DbfWriter;
+
+/// Options for `from_csv`, following the same builder pattern as `OpenOptions`.
+///
+/// By default, the first row holds the column names, columns are separated by commas and the
+/// schema is inferred from the values.
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    delimiter: u8,
+    has_headers: bool,
+    fields: Option<Vec<FieldDescriptor>>
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            delimiter: b',',
+            has_headers: true,
+            fields: None
+        }
+    }
+}
+
+impl CsvOptions {
+    pub fn new() -> Self {
+        CsvOptions::default()
+    }
+
+    /// The byte separating columns, such as `b';'` or `b'\t'`.
+    pub fn delimiter(&mut self, delimiter: u8) -> &mut Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Whether the first row holds column names rather than values.
+    pub fn has_headers(&mut self, has_headers: bool) -> &mut Self {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// Write the columns, in order, as `fields` instead of inferring a schema.
+    ///
+    /// Values are read as text for `C` and `M` fields, numbers for `N`, `F` and `I` fields,
+    /// `YYYY-MM-DD` or `YYYYMMDD` dates for `D` fields and `Y`/`N`, `T`/`F`, `yes`/`no` or
+    /// `true`/`false` for `L` fields. Empty values are left blank.
+    pub fn fields(&mut self, fields: Vec<FieldDescriptor>) -> &mut Self {
+        self.fields = Some(fields);
+        self
+    }
+}
+
+/// Writes the CSV content of `reader` as a new database at `path`, overwriting any existing
+/// file, and returns the number of records written.
+///
+/// Without `CsvOptions::fields`, every row is read first to pick a type for each column:
+///
+///  * `L` when every value is a boolean, such as `Y`, `false` or `no`,
+///  * `N` when every value is a decimal number without an exponent or a leading zero,
+///  * `D` when every value is a `YYYY-MM-DD` date,
+///  * `C` otherwise, or `M` when a value doesn't fit in 254 bytes, written to a .dbt memo file.
+///
+/// Empty values are ignored while inferring and left blank. Column names are upper cased, cut
+/// to 10 characters and have anything but letters and digits replaced with underscores.
+///
+///     use dbase_parser::{from_csv, CsvOptions};
+///
+///     let csv = "name,price,sold\nWidget,12.50,2019-09-03\n";
+///     let path = std::env::temp_dir().join("dbase_parser_doc_csv.dbf");
+///     let count = from_csv(csv.as_bytes(), path.to_str().unwrap(), &CsvOptions::new()).unwrap();
+///     assert_eq!(count, 1);
+pub fn from_csv<R: Read>(reader: R, path: &str, options: &CsvOptions) -> Result<u32, DbaseError> {
+    let mut rows = CsvRows {
+        reader: BufReader::new(reader),
+        delimiter: options.delimiter,
+        line: 0
+    };
+    let names = match options.has_headers {
+        true => rows.next_row()?.map(|(_, names)| names),
+        false => None
+    };
+    match &options.fields {
+        Some(fields) => {
+            let mut writer = DbfWriter::create(path, fields.clone())?;
+            let mut count = 0;
+            while let Some((line, row)) = rows.next_row()? {
+                write_row(&mut writer, fields, line, &row)?;
+                count += 1;
+            }
+            writer.finish()?;
+            Ok(count)
+        },
+        None => {
+            let mut values = vec![];
+            while let Some(row) = rows.next_row()? {
+                values.push(row);
+            }
+            let column_count = names.as_ref().or_else(|| values.first().map(|(_, row)| row)).map_or(0, Vec::len);
+            write_inferred(path, names.as_deref(), column_count, &values)
+        }
+    }
+}
+
+/// Writes `rows` of text values, numbered for error messages, as a new database at `path` with
+/// a schema inferred from the values, see `from_csv`.
+pub(crate) fn write_inferred(path: &str, names: Option<&[String]>, column_count: usize, rows: &[(usize, Vec<String>)]) -> Result<u32, DbaseError> {
+    let names = column_names(names, column_count);
+    let mut fields = vec![];
+    for (index, name) in names.iter().enumerate() {
+        let column = rows.iter().filter_map(|(_, row)| row.get(index)).map(String::as_str);
+        fields.push(infer_field(name, column)?);
+    }
+    let mut writer = DbfWriter::create(path, fields.clone())?;
+    for (line, row) in rows.iter() {
+        write_row(&mut writer, &fields, *line, row)?;
+    }
+    writer.finish()?;
+    Ok(rows.len() as u32)
+}
+
+/// Reads the rows of a CSV file as defined by RFC 4180: values may be quoted to hold
+/// delimiters, line breaks or doubled quotes.
+struct CsvRows<R: BufRead> {
+    reader: R,
+    delimiter: u8,
+    line: usize
+}
+
+impl<R: BufRead> CsvRows<R> {
+    /// The next non empty row and the line it starts on, or `None` at the end of the file.
+    fn next_row(&mut self) -> Result<Option<(usize, Vec<String>)>, io::Error> {
+        loop {
+            let start = self.line + 1;
+            let mut bytes = vec![];
+            // A row goes on past line breaks as long as a quote is left open
+            loop {
+                if self.reader.read_until(b'\n', &mut bytes)? == 0 {
+                    break;
+                }
+                self.line += 1;
+                if bytes.iter().filter(|r| **r == b'"').count() % 2 == 0 {
+                    break;
+                }
+            }
+            if bytes.is_empty() {
+                return Ok(None);
+            }
+            if start == 1 && bytes.starts_with(&[0xef, 0xbb, 0xbf]) {
+                bytes.drain(0..3);
+            }
+            while bytes.last() == Some(&b'\n') || bytes.last() == Some(&b'\r') {
+                bytes.pop();
+            }
+            if !bytes.is_empty() {
+                return Ok(Some((start, self.split(start, &bytes)?)));
+            }
+        }
+    }
+
+    fn split(&self, line: usize, bytes: &[u8]) -> Result<Vec<String>, io::Error> {
+        let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("Line {}: {}", line, message));
+        let mut values = vec![];
+        let mut value = vec![];
+        let mut quoted = false;
+        let mut iter = bytes.iter().peekable();
+        while let Some(byte) = iter.next() {
+            match (quoted, *byte) {
+                (true, b'"') if iter.peek() == Some(&&b'"') => {
+                    value.push(b'"');
+                    iter.next();
+                },
+                (true, b'"') => quoted = false,
+                (false, b'"') => quoted = true,
+                (false, r) if r == self.delimiter => values.push(std::mem::take(&mut value)),
+                (_, r) => value.push(r)
+            }
+        }
+        if quoted {
+            return Err(invalid("a quoted value is never closed"));
+        }
+        values.push(value);
+        values.into_iter()
+            .map(|value| String::from_utf8(value).map_err(|_| invalid("the row is not valid UTF-8")))
+            .collect()
+    }
+}
+
+/// Field names made of the CSV column names, or `F1`, `F2` and so on without them.
+pub(crate) fn column_names(names: Option<&[String]>, column_count: usize) -> Vec<String> {
+    let mut result:Vec<String> = vec![];
+    for index in 0..column_count {
+        let mut name:String = names.and_then(|names| names.get(index))
+            .map(|name| name.trim().chars()
+                .map(|r| match r.is_ascii_alphanumeric() {
+                    true => r.to_ascii_uppercase(),
+                    false => '_'
+                })
+                .take(10)
+                .collect())
+            .unwrap_or_default();
+        if name.is_empty() || result.contains(&name) {
+            let suffix = format!("F{}", index + 1);
+            name = match name.is_empty() {
+                true => suffix,
+                false => format!("{}_{}", &name[..name.len().min(9_usize.saturating_sub(suffix.len()))], suffix)
+            };
+        }
+        result.push(name);
+    }
+    result
+}
+
+fn parse_logical(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "y" | "yes" | "t" | "true" => Some(true),
+        "n" | "no" | "f" | "false" => Some(false),
+        _ => None
+    }
+}
+
+/// The integer and decimal digit counts of a plain decimal number, sign included with the
+/// integer digits.
+fn numeric_digits(value: &str) -> Option<(usize, usize)> {
+    let (sign, digits) = match value.strip_prefix('-') {
+        Some(digits) => (1, digits),
+        None => (0, value)
+    };
+    let (integer, decimals) = match digits.find('.') {
+        Some(point) => (&digits[..point], &digits[point + 1..]),
+        None => (digits, "")
+    };
+    let all_digits = |r: &str| r.bytes().all(|b| b.is_ascii_digit());
+    match !integer.is_empty() && all_digits(integer) && all_digits(decimals) {
+        // Leading zeros are kept as text, they usually mark codes rather than amounts
+        true if integer.len() > 1 && integer.starts_with('0') => None,
+        true => Some((sign + integer.len(), decimals.len())),
+        false => None
+    }
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+}
+
+/// The narrowest field able to hold every value of a column.
+fn infer_field<'a, I: Iterator<Item = &'a str>>(name: &str, column: I) -> Result<FieldDescriptor, DbaseError> {
+    let values:Vec<&str> = column.filter(|value| !value.trim().is_empty()).collect();
+    let trimmed = || values.iter().map(|value| value.trim());
+    if values.is_empty() {
+        return FieldDescriptor::new(name, b'C', 1, 0);
+    }
+    if trimmed().all(|value| parse_logical(value).is_some()) {
+        return FieldDescriptor::new(name, b'L', 1, 0);
+    }
+    if let Some(digits) = trimmed().map(numeric_digits).collect::<Option<Vec<(usize, usize)>>>() {
+        let integer = digits.iter().map(|(integer, _)| *integer).max().unwrap_or(1);
+        let decimals = digits.iter().map(|(_, decimals)| *decimals).max().unwrap_or(0);
+        let length = match decimals {
+            0 => integer,
+            r => integer + r + 1
+        };
+        if length <= 20 && decimals <= 15 {
+            return FieldDescriptor::new(name, b'N', length as u16, decimals as u8);
+        }
+    }
+    if trimmed().all(|value| parse_date(value).is_some()) {
+        return FieldDescriptor::new(name, b'D', 8, 0);
+    }
+    let length = values.iter()
+        .map(|value| encoding_rs::WINDOWS_1252.encode(value).0.len())
+        .max()
+        .unwrap_or(1);
+    match length {
+        0..=254 => FieldDescriptor::new(name, b'C', length as u16, 0),
+        _ => FieldDescriptor::new(name, b'M', 10, 0)
+    }
+}
+
+/// Reads `value` as the content of `field`.
+fn field_value(field: &FieldDescriptor, line: usize, value: &str) -> Result<FieldValue, DbaseError> {
+    let trimmed = value.trim();
+    let invalid = || DbaseError::InvalidFieldValue {
+        field: field.name().to_string(),
+        message: format!("line {}: {:?} is not a valid {} value", line, value, field.type_code() as char)
+    };
+    if trimmed.is_empty() {
+        return Ok(FieldValue::Null);
+    }
+    match field.type_code() {
+        b'C' | b'M' => Ok(FieldValue::Text(value.to_string())),
+        b'N' => trimmed.parse().map(FieldValue::Numeric).map_err(|_| invalid()),
+        b'F' => trimmed.parse().map(FieldValue::Float).map_err(|_| invalid()),
+        b'I' => trimmed.parse().map(FieldValue::Integer).map_err(|_| invalid()),
+        b'L' => parse_logical(trimmed).map(|r| FieldValue::Boolean(Some(r))).ok_or_else(invalid),
+        b'D' => parse_date(trimmed)
+            .or_else(|| NaiveDate::parse_from_str(trimmed, "%Y%m%d").ok())
+            .map(|date| FieldValue::Date(fields::utc_date(date)))
+            .ok_or_else(invalid),
+        _ => Err(DbaseError::InvalidFieldValue {
+            field: field.name().to_string(),
+            message: format!("{} fields cannot be read from CSV", field.type_code() as char)
+        })
+    }
+}
+
+fn write_row(writer: &mut DbfWriter, fields: &[FieldDescriptor], line: usize, row: &[String]) -> Result<(), DbaseError> {
+    if row.len() != fields.len() {
+        let message = format!("Line {}: expected {} columns, found {}", line, fields.len(), row.len());
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message).into());
+    }
+    let mut values = HashMap::new();
+    for (field, value) in fields.iter().zip(row.iter()) {
+        values.insert(field.name().to_string(), field_value(field, line, value)?);
+    }
+    writer.write_record(&values)
+}
@@ -0,0 +1,101 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// A limit set through `OpenOptions` to guard against malicious or corrupt headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    FieldCount,
+    RecordSize
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Limit::FieldCount => write!(f, "field count"),
+            Limit::RecordSize => write!(f, "record size")
+        }
+    }
+}
+
+/// Errors raised while opening or reading a database.
+#[derive(Debug)]
+pub enum DbaseError {
+    /// An underlying I/O or parsing error
+    Io(io::Error),
+    /// The database file does not exist
+    NotFound(PathBuf),
+    /// The path given to open a database points to a directory
+    IsADirectory(PathBuf),
+    /// The database file exists but cannot be read
+    PermissionDenied(PathBuf),
+    /// The header flags the records as encrypted (dBASE IV), which this crate cannot decrypt
+    Encrypted,
+    /// The header declares more than an `OpenOptions` limit allows
+    LimitExceeded {
+        limit: Limit,
+        declared: usize,
+        max: usize
+    },
+    /// The database has no field with this name
+    FieldNotFound(String),
+    /// The database was not opened for writing, see `OpenOptions::write`
+    ReadOnly,
+    /// A value cannot be written to a field, because of its type or size
+    InvalidFieldValue {
+        field: String,
+        message: String
+    },
+    /// A field descriptor breaks the limits of its type, see `FieldDescriptorBuilder`
+    InvalidFieldDescriptor {
+        field: String,
+        message: String
+    },
+    /// A record range was requested outside of `0..=record_count`
+    InvalidRange {
+        start: u32,
+        end: u32,
+        record_count: u32
+    },
+    /// A `FieldValue` cannot be converted to the Rust type `target`, see its `TryFrom`
+    /// implementations
+    InvalidConversion {
+        value: String,
+        target: &'static str
+    }
+}
+
+impl fmt::Display for DbaseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DbaseError::Io(e) => write!(f, "{}", e),
+            DbaseError::NotFound(path) => write!(f, "The database {} does not exist", path.display()),
+            DbaseError::IsADirectory(path) => write!(f, "The path {} is a directory, not a database", path.display()),
+            DbaseError::PermissionDenied(path) => write!(f, "Permission denied while opening the database {}", path.display()),
+            DbaseError::Encrypted => write!(f, "The database is encrypted"),
+            DbaseError::LimitExceeded { limit, declared, max } => write!(f, "The header declares a {} of {}, above the limit of {}", limit, declared, max),
+            DbaseError::FieldNotFound(name) => write!(f, "The database has no field named {}", name),
+            DbaseError::ReadOnly => write!(f, "The database was not opened for writing"),
+            DbaseError::InvalidFieldValue { field, message } => write!(f, "Cannot write the field {}: {}", field, message),
+            DbaseError::InvalidFieldDescriptor { field, message } => write!(f, "Invalid field {}: {}", field, message),
+            DbaseError::InvalidRange { start, end, record_count } => write!(f, "The range {}..{} is invalid for {} records", start, end, record_count),
+            DbaseError::InvalidConversion { value, target } => write!(f, "{} cannot be converted to {}", value, target)
+        }
+    }
+}
+
+impl Error for DbaseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DbaseError::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for DbaseError {
+    fn from(e: io::Error) -> Self {
+        DbaseError::Io(e)
+    }
+}
@@ -1,32 +1,341 @@
-use chrono::{Utc, Date, DateTime, TimeZone};
-use super::header::{Database};
+use chrono::{Utc, Date, DateTime, NaiveDate, NaiveDateTime, TimeZone};
+use super::header::{is_builtin_type, Database, FieldDescriptor, MemoType};
+use super::error::DbaseError;
+use super::writer;
+use std::convert::TryFrom;
 use std::io;
 use std::str::FromStr;
-use byteorder::{ReadBytesExt, LittleEndian};
+use byteorder::{ReadBytesExt, BigEndian, LittleEndian};
+use std::fmt;
 use std::fmt::Debug;
-pub trait FieldType:Debug {
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::RangeInclusive;
+#[cfg(feature = "rust_decimal")]
+use rust_decimal::Decimal;
+#[cfg(feature = "rust_decimal")]
+use rust_decimal::prelude::ToPrimitive;
+/// Reads, and writes, the fields of a type.
+///
+/// Only `parse` is required: the other methods describe the built-in types, which is how
+/// `FieldDescriptorBuilder` validates fields and `DbfWriter` writes them. Field types are shared
+/// between descriptors through an `Arc`, hence `Send + Sync`.
+pub trait FieldType:Debug + Send + Sync {
     fn parse(&self, database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error>;
+    /// The type byte of the fields this type is for, such as `b'C'`, if it is tied to one.
+    fn type_code(&self) -> Option<u8> {
+        None
+    }
+    /// The widths a field of this type can have, in bytes.
+    fn length_range(&self) -> RangeInclusive<u16> {
+        1..=u16::MAX
+    }
+    /// Encodes `value` into the bytes of `field` in a record, exactly `field.length()` of them.
+    ///
+    /// The default writes the built-in types the way `DbfWriter` does with its default options,
+    /// except for memos which need a memo file, and refuses any other type: types registered
+    /// with a byte of their own must override it to be written.
+    fn encode(&self, field: &FieldDescriptor, value: &FieldValue) -> Result<Vec<u8>, DbaseError> {
+        match is_builtin_type(field.type_code) {
+            true => writer::encode_field(field, Some(value), &mut None, writer::EncodeOptions::default()),
+            false => Err(DbaseError::InvalidFieldValue {
+                field: field.name.clone(),
+                message: format!("no encoder for type {}", field.type_code as char)
+            })
+        }
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum FieldValue {
     Text(String),
     Numeric(f64),
+    Float(f64), // Float (`F`) fields, kept apart from numeric (`N`) ones
+    Double(f64), // Visual FoxPro double (`B`) fields, stored as binary IEEE 754 doubles
     Integer(i32), // There's a special type for this
-    Boolean(Option<bool>),
+    Currency(i64), // Visual FoxPro currency (`Y`) fields, in ten thousandths
+    Boolean(Option<bool>), // `None` is the unknown value `?`, blank logicals are `Null`
     Date(Date<Utc>),
     DateTime(DateTime<Utc>),
-    Unknown(Vec<u8>)
+    #[cfg(feature = "rust_decimal")]
+    Decimal(Decimal), // Only produced when `OpenOptions::numeric_as_decimal` is set
+    Binary(Vec<u8>), // The object of a general (`G`) field, as stored in the memo file
+    Unknown(Vec<u8>),
+    MemoRef(MemoHandle), // A memo left unread, see `OpenOptions::lazy_memos`
+    Overflow, // A numeric field filled with asterisks, as dBASE does when the value doesn't fit
+    Null // A blank field: all spaces or zeros, which is how dBASE stores no value
+}
+
+/// Numeric values are compared by value, so `NaN` is never equal to itself and cannot be used
+/// to look up a key.
+impl Eq for FieldValue {}
+
+impl Hash for FieldValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+        match self {
+            FieldValue::Text(r) => r.hash(state),
+            // 0.0 and -0.0 are equal and must hash alike
+            FieldValue::Numeric(r) | FieldValue::Float(r) | FieldValue::Double(r) if *r == 0.0 => 0u64.hash(state),
+            FieldValue::Numeric(r) | FieldValue::Float(r) | FieldValue::Double(r) => r.to_bits().hash(state),
+            FieldValue::Integer(r) => r.hash(state),
+            FieldValue::Currency(r) => r.hash(state),
+            FieldValue::Boolean(r) => r.hash(state),
+            FieldValue::Date(r) => r.hash(state),
+            FieldValue::DateTime(r) => r.hash(state),
+            #[cfg(feature = "rust_decimal")]
+            FieldValue::Decimal(r) => r.hash(state),
+            FieldValue::Binary(r) | FieldValue::Unknown(r) => r.hash(state),
+            FieldValue::MemoRef(r) => r.hash(state),
+            FieldValue::Overflow | FieldValue::Null => ()
+        }
+    }
+}
+
+impl FieldValue {
+    /// Converts the value to JSON: dates as ISO 8601 strings, unknown bytes as base64 strings and
+    /// empty, overflowed or unread memo values as `null`.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> serde_json::Value {
+        use serde_json::Value;
+        use base64::Engine;
+        match self {
+            FieldValue::Text(r) => Value::String(r.clone()),
+            FieldValue::Numeric(r) | FieldValue::Float(r) | FieldValue::Double(r) => serde_json::Number::from_f64(*r).map(Value::Number).unwrap_or(Value::Null),
+            FieldValue::Integer(r) => Value::from(*r),
+            FieldValue::Currency(_) => Value::String(self.to_string()),
+            FieldValue::Boolean(r) => r.map(Value::Bool).unwrap_or(Value::Null),
+            FieldValue::Date(r) => Value::String(r.naive_utc().format("%Y-%m-%d").to_string()),
+            FieldValue::DateTime(r) => Value::String(r.to_rfc3339()),
+            #[cfg(feature = "rust_decimal")]
+            FieldValue::Decimal(r) => Value::String(r.to_string()),
+            FieldValue::Binary(r) | FieldValue::Unknown(r) => Value::String(base64::engine::general_purpose::STANDARD.encode(r)),
+            FieldValue::MemoRef(_) | FieldValue::Overflow | FieldValue::Null => Value::Null
+        }
+    }
+}
+
+/// Formats values the way dBASE shows them: dates as `CCYYMMDD`, datetimes as `CCYYMMDDhhmmss`,
+/// logicals as `Y`, `N` or `?`, currencies with their 4 decimals, bytes in hexadecimal, unread
+/// memos as `Memo` and blank values as nothing. Numbers take the precision of the format, if
+/// any, as the decimal count of their field: `format!("{:.2}", value)`.
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldValue::Text(r) => write!(f, "{}", r),
+            FieldValue::Numeric(r) | FieldValue::Float(r) | FieldValue::Double(r) => match f.precision() {
+                Some(decimal_count) => write!(f, "{:.*}", decimal_count, r),
+                None => write!(f, "{}", r)
+            },
+            FieldValue::Integer(r) => write!(f, "{}", r),
+            FieldValue::Currency(r) => {
+                let sign = match *r < 0 {
+                    true => "-",
+                    false => ""
+                };
+                write!(f, "{}{}.{:04}", sign, r.unsigned_abs() / 10_000, r.unsigned_abs() % 10_000)
+            },
+            FieldValue::Boolean(Some(true)) => write!(f, "Y"),
+            FieldValue::Boolean(Some(false)) => write!(f, "N"),
+            FieldValue::Boolean(None) => write!(f, "?"),
+            FieldValue::Date(r) => write!(f, "{}", r.format("%Y%m%d")),
+            FieldValue::DateTime(r) => write!(f, "{}", r.format("%Y%m%d%H%M%S")),
+            #[cfg(feature = "rust_decimal")]
+            FieldValue::Decimal(r) => match f.precision() {
+                Some(decimal_count) => write!(f, "{:.*}", decimal_count, r),
+                None => write!(f, "{}", r)
+            },
+            FieldValue::Binary(r) | FieldValue::Unknown(r) => r.iter().try_for_each(|byte| write!(f, "{:02x}", byte)),
+            FieldValue::MemoRef(_) => write!(f, "Memo"),
+            FieldValue::Overflow => write!(f, "*"),
+            FieldValue::Null => Ok(())
+        }
+    }
+}
+
+fn conversion_error(value: &FieldValue, target: &'static str) -> DbaseError {
+    DbaseError::InvalidConversion { value: format!("{:?}", value), target }
+}
+
+/// `f64` as an integer between `min` and `max`, if it has no fractional part.
+fn integral(value: f64, min: f64, max: f64) -> Option<f64> {
+    match value.fract() == 0.0 && value >= min && value <= max {
+        true => Some(value),
+        false => None
+    }
+}
+
+/// Converts a value to text. Only `Text` converts.
+impl TryFrom<&FieldValue> for String {
+    type Error = DbaseError;
+    fn try_from(value: &FieldValue) -> Result<Self, DbaseError> {
+        match value {
+            FieldValue::Text(r) => Ok(r.clone()),
+            r => Err(conversion_error(r, "String"))
+        }
+    }
+}
+
+impl TryFrom<FieldValue> for String {
+    type Error = DbaseError;
+    fn try_from(value: FieldValue) -> Result<Self, DbaseError> {
+        match value {
+            FieldValue::Text(r) => Ok(r),
+            r => Err(conversion_error(&r, "String"))
+        }
+    }
+}
+
+/// Converts any number, currencies included, to a float.
+impl TryFrom<&FieldValue> for f64 {
+    type Error = DbaseError;
+    fn try_from(value: &FieldValue) -> Result<Self, DbaseError> {
+        match value {
+            FieldValue::Numeric(r) | FieldValue::Float(r) | FieldValue::Double(r) => Ok(*r),
+            FieldValue::Integer(r) => Ok(f64::from(*r)),
+            FieldValue::Currency(r) => Ok(*r as f64 / 10_000.0),
+            #[cfg(feature = "rust_decimal")]
+            FieldValue::Decimal(r) => r.to_f64().ok_or_else(|| conversion_error(value, "f64")),
+            r => Err(conversion_error(r, "f64"))
+        }
+    }
+}
+
+/// Converts integers, and numbers without a fractional part which fit, to an `i32`.
+impl TryFrom<&FieldValue> for i32 {
+    type Error = DbaseError;
+    fn try_from(value: &FieldValue) -> Result<Self, DbaseError> {
+        match value {
+            FieldValue::Integer(r) => Ok(*r),
+            r => i64::try_from(r).ok()
+                .and_then(|r| i32::try_from(r).ok())
+                .ok_or_else(|| conversion_error(value, "i32"))
+        }
+    }
+}
+
+/// Converts integers, and numbers without a fractional part which fit, to an `i64`.
+impl TryFrom<&FieldValue> for i64 {
+    type Error = DbaseError;
+    fn try_from(value: &FieldValue) -> Result<Self, DbaseError> {
+        let integer = match value {
+            FieldValue::Integer(r) => Some(i64::from(*r)),
+            FieldValue::Numeric(r) | FieldValue::Float(r) | FieldValue::Double(r) => {
+                integral(*r, i64::MIN as f64, i64::MAX as f64).map(|r| r as i64)
+            },
+            #[cfg(feature = "rust_decimal")]
+            FieldValue::Decimal(r) if r.fract().is_zero() => r.to_i64(),
+            _ => None
+        };
+        integer.ok_or_else(|| conversion_error(value, "i64"))
+    }
+}
+
+/// Converts known logicals to a `bool`; the unknown `?` does not convert.
+impl TryFrom<&FieldValue> for bool {
+    type Error = DbaseError;
+    fn try_from(value: &FieldValue) -> Result<Self, DbaseError> {
+        match value {
+            FieldValue::Boolean(Some(r)) => Ok(*r),
+            r => Err(conversion_error(r, "bool"))
+        }
+    }
+}
+
+impl TryFrom<&FieldValue> for NaiveDate {
+    type Error = DbaseError;
+    fn try_from(value: &FieldValue) -> Result<Self, DbaseError> {
+        match value {
+            FieldValue::Date(r) => Ok(r.naive_utc()),
+            r => Err(conversion_error(r, "NaiveDate"))
+        }
+    }
+}
+
+/// Converts datetimes, and dates at midnight, to a `NaiveDateTime`.
+impl TryFrom<&FieldValue> for NaiveDateTime {
+    type Error = DbaseError;
+    fn try_from(value: &FieldValue) -> Result<Self, DbaseError> {
+        match value {
+            FieldValue::DateTime(r) => Ok(r.naive_utc()),
+            FieldValue::Date(r) => r.naive_utc().and_hms_opt(0, 0, 0).ok_or_else(|| conversion_error(value, "NaiveDateTime")),
+            r => Err(conversion_error(r, "NaiveDateTime"))
+        }
+    }
+}
+
+/// Implements the conversions from an owned `FieldValue` with those from a borrowed one.
+macro_rules! try_from_owned {
+    ($($target:ty),*) => {
+        $(
+            impl TryFrom<FieldValue> for $target {
+                type Error = DbaseError;
+                fn try_from(value: FieldValue) -> Result<Self, DbaseError> {
+                    <$target>::try_from(&value)
+                }
+            }
+        )*
+    }
+}
+
+try_from_owned!(f64, i32, i64, bool, NaiveDate, NaiveDateTime);
+
+/// Whether the field is blank, made only of spaces or null bytes.
+fn is_blank(data: &[u8]) -> bool {
+    data.iter().all(|r| *r == b' ' || *r == 0)
+}
+
+/// Decodes field content with the code page of the database, see `Header::encoding`.
+fn decode(database: &Database, data: &[u8]) -> Result<String, io::Error> {
+    database.header.charset().decode(data)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} cannot be casted to a string", data)))
 }
 
 #[derive(Clone, Debug)]
 pub struct FieldTypeC;
 impl FieldType for FieldTypeC {
-    fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
-        String::from_utf8(data.clone())
-            .map_err(|_e| io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} cannot be casted to a string", data)))
-            .map(|r| FieldValue::Text(r.trim().to_string()))
+    fn type_code(&self) -> Option<u8> {
+        Some(b'C')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        1..=254
+    }
+    fn parse(&self, database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        if is_blank(&data) {
+            return Ok(FieldValue::Null);
+        }
+        decode(database, &data).map(|r| FieldValue::Text(r.trim().to_string()))
+    }
+}
+
+/// A Visual FoxPro varchar field, read without the trimming of character fields as its length
+/// is known, see `Database::parse_row_with`.
+#[derive(Clone, Debug)]
+pub struct FieldTypeV;
+impl FieldType for FieldTypeV {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'V')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        1..=254
+    }
+    fn parse(&self, database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        decode(database, &data).map(FieldValue::Text)
+    }
+}
 
+/// A Visual FoxPro varbinary field, read as raw bytes.
+#[derive(Clone, Debug)]
+pub struct FieldTypeQ;
+impl FieldType for FieldTypeQ {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'Q')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        1..=254
+    }
+    fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        Ok(FieldValue::Binary(data))
     }
 }
 
@@ -34,14 +343,19 @@ impl FieldType for FieldTypeC {
 pub struct FieldTypeD;
 
 impl FieldType for FieldTypeD {
-    fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
-        let mut field_content =  String::from_utf8(data.clone())
-            .map_err(|_e| io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} cannot be casted to a string", data)))
-            .map(|r| r.trim().to_string())?;
+    fn type_code(&self) -> Option<u8> {
+        Some(b'D')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        8..=8
+    }
+    fn parse(&self, database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        // Some exporters fill empty dates with zeros rather than spaces
+        if is_blank(&data) || data.iter().all(|r| *r == b'0') {
+            return Ok(FieldValue::Null);
+        }
+        let mut field_content = decode(database, &data)?.trim().to_string();
         match field_content.len() {
-            0 => {
-                Ok(FieldValue::Date(Utc.ymd(1900, 1, 1)))
-            },
             8 => {
                 let day_str:String = field_content.split_off(6);
                 let month_str:String = field_content.split_off(4);
@@ -51,7 +365,7 @@ impl FieldType for FieldTypeD {
                     .map_err(|_e| io::Error::new(io::ErrorKind::InvalidData, "Month is invalid"))?;
                 let year:i32 = FromStr::from_str(&field_content)
                     .map_err(|_e| io::Error::new(io::ErrorKind::InvalidData, "Year is invalid"))?;
-                Ok(FieldValue::Date(Utc.ymd(year, month, day)))
+                date(year, month, day).map(FieldValue::Date)
             },
             _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("The field value {} is not a valid date", field_content)))
         }
@@ -59,25 +373,92 @@ impl FieldType for FieldTypeD {
 }
 
 #[derive(Clone, Debug)]
-pub struct FieldTypeOldNumeric;
+pub struct FieldTypeOldNumeric {
+    pub decimal_count: u8
+}
+
+impl FieldTypeOldNumeric {
+    /// Inserts the decimal point implied by the decimal count into `data`, for exporters which
+    /// leave it out (`12345` with 2 decimals is `123.45`).
+    fn imply_decimal(&self, data: &str) -> String {
+        if self.decimal_count == 0 || data.is_empty() || data.contains('.') {
+            return data.to_string();
+        }
+        let (sign, digits) = match data.starts_with('-') {
+            true => ("-", &data[1..]),
+            false => ("", data)
+        };
+        let decimal_count = self.decimal_count as usize;
+        let digits = format!("{:0>width$}", digits, width = decimal_count + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - decimal_count);
+        format!("{}{}.{}", sign, integer, fraction)
+    }
+    #[cfg(feature = "rust_decimal")]
+    fn parse_decimal(&self, data: &str) -> Result<FieldValue, io::Error> {
+        let mut value = Decimal::from_str(data)
+            .map_err(|_e| io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} cannot be casted to a decimal", data)))?;
+        value.rescale(self.decimal_count.into());
+        Ok(FieldValue::Decimal(value))
+    }
+}
 
 impl FieldType for FieldTypeOldNumeric {
-    fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
-        let num_length = String::from_utf8(data.clone()).unwrap().trim().len();
-        match num_length {
-            0 => Ok(FieldValue::Numeric(0.0 as f64)),
-            _ => {
-                String::from_utf8(data.clone())
-                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} cannot be casted to a string", data)))
-                    .and_then(|data| {
-                        FromStr::from_str(data.trim_start())
-                            .map(|r| FieldValue::Numeric(r))
-                            .map_err(|_e| {
-                                io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} cannot be casted to a float", data))
-                            })
-                    })
+    fn type_code(&self) -> Option<u8> {
+        Some(b'N')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        1..=20
+    }
+    fn parse(&self, database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        if is_blank(&data) {
+            return Ok(FieldValue::Null);
+        }
+        if data.contains(&b'*') && data.iter().all(|r| *r == b'*' || *r == b' ' || *r == b'.') {
+            return Ok(FieldValue::Overflow);
+        }
+        let field_content = decode(database, &data)?;
+        let field_content = match database.options().implied_decimal {
+            true => self.imply_decimal(field_content.trim()),
+            false => field_content.trim().to_string()
+        };
+        if self.decimal_count == 0 && database.options().numeric_as_integer {
+            if let Ok(integer) = field_content.parse() {
+                return Ok(FieldValue::Integer(integer));
             }
         }
+        #[cfg(feature = "rust_decimal")]
+        {
+            if database.options().numeric_as_decimal {
+                return self.parse_decimal(&field_content);
+            }
+        }
+        FromStr::from_str(&field_content)
+            .map(FieldValue::Numeric)
+            .map_err(|_e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} cannot be casted to a float", field_content))
+            })
+    }
+}
+
+/// A float (`F`) field, stored as text like numeric fields but read as `FieldValue::Float`.
+#[derive(Clone, Debug)]
+pub struct FieldTypeF {
+    pub decimal_count: u8
+}
+
+impl FieldType for FieldTypeF {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'F')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        1..=20
+    }
+    fn parse(&self, database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        let numeric = FieldTypeOldNumeric { decimal_count: self.decimal_count };
+        match numeric.parse(database, data)? {
+            FieldValue::Numeric(r) => Ok(FieldValue::Float(r)),
+            r => Ok(r)
+        }
     }
 }
 
@@ -85,16 +466,41 @@ impl FieldType for FieldTypeOldNumeric {
 pub struct FieldTypeL;
 
 impl FieldType for FieldTypeL {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'L')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        1..=1
+    }
     fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        // A blank logical has no value, unlike `?` which stands for an unknown one
+        if is_blank(&data) {
+            return Ok(FieldValue::Null);
+        }
         match data.first() {
-            Some(r) if *r == 89 || *r == 121 || *r == 49 => Ok(FieldValue::Boolean(Some(true))),
-            Some(r) if *r == 78 || *r == 110 || *r == 48 => Ok(FieldValue::Boolean(Some(false))),
+            // Clipper writes T and F rather than Y and N
+            Some(r) if *r == 89 || *r == 121 || *r == 84 || *r == 116 || *r == 49 => Ok(FieldValue::Boolean(Some(true))),
+            Some(r) if *r == 78 || *r == 110 || *r == 70 || *r == 102 || *r == 48 => Ok(FieldValue::Boolean(Some(false))),
             Some(_r) => Ok(FieldValue::Boolean(None)),
             _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid data for a boolean"))
         }
     }
 }
 
+/// The date `year`-`month`-`day`, or an error if there is no such day.
+fn date(year: i32, month: u32, day: u32) -> Result<Date<Utc>, io::Error> {
+    NaiveDate::from_ymd_opt(year, month, day)
+        .map(utc_date)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{:04}-{:02}-{:02} is not a valid date", year, month, day)))
+}
+
+/// `date` as the `chrono::Date` `FieldValue::Date` and the header hold. chrono deprecates that
+/// type, so it is only built here.
+#[allow(deprecated)]
+pub(crate) fn utc_date(date: NaiveDate) -> Date<Utc> {
+    Utc.from_utc_date(&date)
+}
+
 fn vec_u8_to_u32(in_val: Vec<u8>) -> Result<u32, io::Error> {
     let mut reader = io::Cursor::new(in_val);
     reader.read_u32::<LittleEndian>()
@@ -111,12 +517,21 @@ fn to_julian_date(input: u32) -> Result<Date<Utc>, io::Error> {
     let s4 = (q / 11.0).floor();
     let m = q + 2.0 - (12.0 * s4);
     let j = (100.0 * (n - 49.0)) + i + s4;
-    Ok(Utc.ymd(j as i32, m as u32, d as u32))
+    date(j as i32, m as u32, d as u32)
 }
 #[derive(Clone, Debug)]
 pub struct FieldTypeT;
 impl FieldType for FieldTypeT {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'T')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        8..=8
+    }
     fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        if is_blank(&data) {
+            return Ok(FieldValue::Null);
+        }
         let mut dword_iter = data.chunks(4);
         let date_word_vec = dword_iter.next()
             .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Date word not found"))?;
@@ -124,6 +539,10 @@ impl FieldType for FieldTypeT {
             .ok_or(io::Error::new(io::ErrorKind::InvalidData, "Time word not found"))?;
         let date_word = vec_u8_to_u32(date_word_vec.to_vec())?;
         let time_word = vec_u8_to_u32(time_word_vec.to_vec())?;
+        // A datetime without a day is as empty as a blank one
+        if date_word == 0 {
+            return Ok(FieldValue::Null);
+        }
         let date = to_julian_date(date_word)?;
 
         let mut time_word_f64:f64 = time_word.into();
@@ -132,13 +551,21 @@ impl FieldType for FieldTypeT {
         let minutes = (time_word_f64 / 60000.0).floor();
         time_word_f64 = time_word_f64 - minutes * 60000.0;
         let seconds = time_word_f64 / 1000.0;
-        Ok(FieldValue::DateTime(date.and_hms(hours as u32, minutes as u32, seconds as u32)))
+        date.and_hms_opt(hours as u32, minutes as u32, seconds as u32)
+            .map(FieldValue::DateTime)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("{} milliseconds is more than a day", time_word)))
     }
 }
 
 #[derive(Clone, Debug)]
 pub struct FieldTypeI;
 impl FieldType for FieldTypeI {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'I')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        4..=4
+    }
     fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
         let mut reader = io::Cursor::new(data);
         let integer = reader.read_i32::<LittleEndian>()?;
@@ -146,17 +573,166 @@ impl FieldType for FieldTypeI {
     }
 }
 
+/// A memo field whose content has not been read yet, see `Database::resolve_memo`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MemoHandle {
+    pointer: Vec<u8>,
+    is_text: bool
+}
+
+impl MemoHandle {
+    /// The content of the field: the block number of the memo, as stored in the record.
+    pub fn pointer(&self) -> &[u8] {
+        &self.pointer
+    }
+    /// Whether the memo is read as text (`M` fields) rather than bytes (`G` and `P` fields).
+    pub fn is_text(&self) -> bool {
+        self.is_text
+    }
+    /// Reads the memo from `database`, see `Database::resolve_memo`.
+    pub fn read(&self, database: &mut Database) -> Result<FieldValue, DbaseError> {
+        database.resolve_memo(self)
+    }
+}
+
+/// Reads the memo `data` points to, as text or as bytes, or returns the pointer itself without
+/// a memo file. Memos of text fields which the memo file records as pictures or objects are
+/// read as bytes.
+pub(crate) fn read_memo_value(database: &mut Database, data: Vec<u8>, is_text: bool) -> Result<FieldValue, io::Error> {
+    let (bytes, memo_type) = match database.read_memo(data.clone()) {
+        Some(memo) => memo?,
+        None => return Ok(FieldValue::Unknown(data))
+    };
+    match (is_text, memo_type) {
+        (true, None) | (true, Some(MemoType::Text)) => decode(database, &bytes).map(FieldValue::Text),
+        _ => Ok(FieldValue::Binary(bytes))
+    }
+}
+
+/// Reads a memo field, or only notes where its memo is with `OpenOptions::lazy_memos`.
+fn memo_value(database: &mut Database, data: Vec<u8>, is_text: bool) -> Result<FieldValue, io::Error> {
+    match database.options().lazy_memos {
+        true => Ok(FieldValue::MemoRef(MemoHandle { pointer: data, is_text })),
+        false => read_memo_value(database, data, is_text)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct FieldTypeM;
 impl FieldType for FieldTypeM {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'M')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        4..=10
+    }
     fn parse(&self, database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
-        database.get_memo(data)
-            .ok_or(io::Error::new(io::ErrorKind::NotFound, "Memo not found"))
-            .and_then(|bytes| {
-                String::from_utf8(bytes)
-                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "The memo cannot be casted to a string"))
-            })
-            .map(|r| FieldValue::Text(r))
+        // A blank pointer means the record has no memo
+        if is_blank(&data) {
+            return Ok(FieldValue::Text(String::new()));
+        }
+        // Without a memo file, the pointer is all there is to return
+        memo_value(database, data, true)
+    }
+}
+
+/// A Visual FoxPro double, stored as a little-endian IEEE 754 float.
+#[derive(Clone, Debug)]
+pub struct FieldTypeB;
+impl FieldType for FieldTypeB {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'B')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        8..=8
+    }
+    fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        let mut reader = io::Cursor::new(data);
+        Ok(FieldValue::Double(reader.read_f64::<LittleEndian>()?))
+    }
+}
+
+/// A dBASE 7 double, stored as a big-endian IEEE 754 float with its sign bit flipped, and all its
+/// bits flipped when negative, so that the bytes sort like the numbers. Zeros stand for a blank
+/// value.
+///
+/// Only tables with 32 byte field descriptors are read: genuine dBASE 7 tables (version 0x04 or
+/// 0x8C) use 48 byte descriptors and cannot be opened, nor can dBASE 7 tables be written.
+#[derive(Clone, Debug)]
+pub struct FieldTypeO;
+impl FieldType for FieldTypeO {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'O')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        8..=8
+    }
+    fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        if data.iter().all(|r| *r == 0) {
+            return Ok(FieldValue::Null);
+        }
+        let mut reader = io::Cursor::new(data);
+        let bits = reader.read_u64::<BigEndian>()?;
+        let bits = match bits & (1 << 63) != 0 {
+            true => bits ^ (1 << 63),
+            false => !bits
+        };
+        Ok(FieldValue::Double(f64::from_bits(bits)))
+    }
+}
+
+/// A Visual FoxPro currency, stored as a little-endian integer scaled by 10,000. It is read as
+/// `FieldValue::Currency`, or as a decimal with `OpenOptions::numeric_as_decimal`.
+#[derive(Clone, Debug)]
+pub struct FieldTypeY;
+impl FieldType for FieldTypeY {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'Y')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        8..=8
+    }
+    fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        let mut reader = io::Cursor::new(data);
+        let value = reader.read_i64::<LittleEndian>()?;
+        #[cfg(feature = "rust_decimal")]
+        {
+            if _database.options().numeric_as_decimal {
+                return Ok(FieldValue::Decimal(Decimal::new(value, 4)));
+            }
+        }
+        Ok(FieldValue::Currency(value))
+    }
+}
+
+/// A general (OLE object) field. Like memos, its content lives in the memo file, but it is
+/// returned as raw bytes in `FieldValue::Binary`. Without a memo file, the pointer itself is
+/// returned as `FieldValue::Unknown`.
+#[derive(Clone, Debug)]
+pub struct FieldTypeG;
+impl FieldType for FieldTypeG {
+    fn type_code(&self) -> Option<u8> {
+        Some(b'G')
+    }
+    fn length_range(&self) -> RangeInclusive<u16> {
+        4..=10
+    }
+    fn parse(&self, database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        // A blank pointer means the record has no object
+        if is_blank(&data) {
+            return Ok(FieldValue::Binary(vec![]));
+        }
+        memo_value(database, data, false)
+    }
+}
+
+/// Passes the field content through untouched, used for unknown field types when
+/// `OpenOptions::allow_unknown_field_types` is set.
+#[derive(Clone, Debug)]
+pub struct FieldTypeRaw;
+impl FieldType for FieldTypeRaw {
+    fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+        Ok(FieldValue::Unknown(data))
     }
 }
 
@@ -180,4 +756,46 @@ fn datetime_works() {
     let o = FieldTypeT {};
     assert_eq!(o.parse(&mut db, data).unwrap(), FieldValue::DateTime(Utc.ymd(2019, 03, 09).and_hms(01, 0, 0)));
 
-}
\ No newline at end of file
+}
+#[cfg(feature = "rust_decimal")]
+#[test]
+fn numeric_as_decimal_is_exact() {
+    let data = b"       0.1".to_vec();
+    let o = FieldTypeOldNumeric { decimal_count: 1 };
+
+    let mut db = Database::new_at("C:/test.txt");
+    let lossy = match o.parse(&mut db, data.clone()).unwrap() {
+        FieldValue::Numeric(r) => r,
+        r => panic!("Expected a numeric value, got {:?}", r)
+    };
+    assert_ne!(lossy * 3.0, 0.3);
+
+    let mut db = crate::OpenOptions::new().numeric_as_decimal(true).open("tests/reference_gis.dbf").unwrap();
+    let exact = match o.parse(&mut db, data).unwrap() {
+        FieldValue::Decimal(r) => r,
+        r => panic!("Expected a decimal value, got {:?}", r)
+    };
+    assert_eq!(exact, Decimal::new(1, 1));
+    assert_eq!(exact * Decimal::from(3), Decimal::new(3, 1));
+}
+
+#[test]
+fn date_equality() {
+    assert_eq!(FieldValue::Date(Utc.ymd(2019, 9, 1)), FieldValue::Date(Utc.ymd(2019, 9, 1)));
+    assert_ne!(FieldValue::Date(Utc.ymd(2019, 9, 1)), FieldValue::Date(Utc.ymd(2019, 9, 2)));
+    // A date never equals a datetime, even at midnight
+    assert_ne!(FieldValue::Date(Utc.ymd(2019, 9, 1)), FieldValue::DateTime(Utc.ymd(2019, 9, 1).and_hms(0, 0, 0)));
+}
+
+#[test]
+fn datetime_equality() {
+    use chrono::FixedOffset;
+
+    let datetime = Utc.ymd(2019, 9, 4).and_hms(11, 6, 0);
+    assert_eq!(FieldValue::DateTime(datetime), FieldValue::DateTime(Utc.ymd(2019, 9, 4).and_hms(11, 6, 0)));
+    assert_ne!(FieldValue::DateTime(datetime), FieldValue::DateTime(Utc.ymd(2019, 9, 4).and_hms(11, 6, 1)));
+    // Values are always held in UTC, so equality is on the UTC wall clock
+    let offset = FixedOffset::east_opt(2 * 3600).unwrap().ymd(2019, 9, 4).and_hms(13, 6, 0);
+    assert_eq!(FieldValue::DateTime(offset.with_timezone(&Utc)), FieldValue::DateTime(datetime));
+    assert_eq!(offset.with_timezone(&Utc).naive_utc(), datetime.naive_utc());
+}
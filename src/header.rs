@@ -1,16 +1,25 @@
-use chrono::{Date, Utc, TimeZone};
+use chrono::{Date, Datelike, NaiveDate, Utc};
+use std::fs;
 use std::fs::File;
 use super::fields::{FieldType, FieldValue};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::path::{Path, PathBuf};
 use std::io;
 use std::iter::{IntoIterator, Iterator};
-use std::io::{Seek, Read};
-use byteorder::{ReadBytesExt, BigEndian, LittleEndian};
-use std::str::FromStr;
+use std::io::{Seek, Read, Write};
+use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian, LittleEndian};
+use fs2::FileExt;
 use std::sync::Arc;
+use std::ops::Range;
+use std::rc::Rc;
 use std::fmt::Debug;
 use super::fields;
+use super::options::OpenOptions;
+use super::writer;
+use super::writer::{DbfWriter, MemoWriter};
+use super::codepage::{Charset, CodePage};
+use encoding_rs::Encoding;
+use super::error::{DbaseError, Limit};
 
 #[derive(Debug)]
 pub enum Version {
@@ -48,9 +57,299 @@ impl Version {
 pub struct FieldDescriptor {
     pub name: String,
     pub field_type: Arc<Box<dyn FieldType>>,
+    pub(crate) type_code: u8,
     data_address: u32,
-    length: u8,
-    decimal_count: u8
+    pub(crate) length: u16,
+    pub(crate) decimal_count: u8,
+    pub(crate) flags: u8,
+    pub(crate) autoincrement_next: u32,
+    pub(crate) autoincrement_step: u8
+}
+
+/// The kind of data a field holds, as declared by its type byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    Character,
+    /// Numeric (`N`) fields, stored as text
+    Numeric,
+    /// Float (`F`) fields, stored as text like numeric fields
+    Float,
+    Date,
+    Logical,
+    Memo,
+    Integer,
+    DateTime,
+    /// Visual FoxPro doubles (`B`), which dBASE IV tables use for binary memos instead
+    Double,
+    Currency,
+    General,
+    /// FoxPro pictures, stored in the memo file like general fields
+    Picture,
+    /// Visual FoxPro character fields of variable length
+    Varchar,
+    /// Visual FoxPro binary fields of variable length
+    Varbinary,
+    /// Autoincrement (`+`) fields, stored like integers
+    Autoincrement,
+    /// dBASE 7 timestamps (`@`), stored like datetimes. They are only read from tables with 32
+    /// byte field descriptors, not the 48 byte ones of genuine dBASE 7 tables
+    Timestamp,
+    /// dBASE 7 doubles (`O`), with the same limitation as timestamps
+    Dbase7Double,
+    /// Any other type byte
+    Other(u8)
+}
+
+impl FieldKind {
+    pub fn from_byte(byte: u8) -> FieldKind {
+        match byte {
+            b'C' => FieldKind::Character,
+            b'N' => FieldKind::Numeric,
+            b'F' => FieldKind::Float,
+            b'D' => FieldKind::Date,
+            b'L' => FieldKind::Logical,
+            b'M' => FieldKind::Memo,
+            b'I' => FieldKind::Integer,
+            b'T' => FieldKind::DateTime,
+            b'B' => FieldKind::Double,
+            b'Y' => FieldKind::Currency,
+            b'G' => FieldKind::General,
+            b'P' => FieldKind::Picture,
+            b'V' => FieldKind::Varchar,
+            b'Q' => FieldKind::Varbinary,
+            b'+' => FieldKind::Autoincrement,
+            b'@' => FieldKind::Timestamp,
+            b'O' => FieldKind::Dbase7Double,
+            r => FieldKind::Other(r)
+        }
+    }
+}
+
+impl FieldDescriptor {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// The type byte of the field, such as `b'C'`.
+    pub fn type_code(&self) -> u8 {
+        self.type_code
+    }
+    pub fn field_kind(&self) -> FieldKind {
+        FieldKind::from_byte(self.type_code)
+    }
+    /// The width of the field in a record, in bytes.
+    pub fn length(&self) -> u16 {
+        self.length
+    }
+    pub fn decimal_count(&self) -> u8 {
+        self.decimal_count
+    }
+    /// The offset of the field in a record, as stored by the header. Only Visual FoxPro fills it.
+    pub fn data_address(&self) -> u32 {
+        self.data_address
+    }
+    /// The Visual FoxPro field flags: 0x01 for system columns, 0x02 for nullable fields, 0x04
+    /// for binary character and memo fields and 0x0C for autoincrement integers.
+    pub fn flags(&self) -> u8 {
+        self.flags
+    }
+    /// Whether the field is a hidden system column, such as `_NullFlags`, left out of records.
+    pub fn is_system(&self) -> bool {
+        self.flags & 0x01 != 0
+    }
+    /// Whether the field can hold `Null` values, flagged in the hidden `_NullFlags` column.
+    pub fn is_nullable(&self) -> bool {
+        self.flags & 0x02 != 0
+    }
+    /// Whether a character or memo field holds bytes rather than text, which are then read as
+    /// `FieldValue::Binary` in Visual FoxPro tables.
+    pub fn is_binary(&self) -> bool {
+        matches!(self.type_code, b'C' | b'M') && self.flags & 0x04 != 0
+    }
+    /// Whether the field numbers records on its own: a `+` field, or a Visual FoxPro integer
+    /// flagged 0x0C.
+    pub fn is_autoincrement(&self) -> bool {
+        self.type_code == b'+' || (self.type_code == b'I' && self.flags & 0x0c == 0x0c)
+    }
+    /// The value the next record gets in an autoincrement field, stored in bytes 19 to 22 of the
+    /// field descriptor.
+    pub fn next_value(&self) -> Option<u32> {
+        match self.is_autoincrement() {
+            true => Some(self.autoincrement_next),
+            false => None
+        }
+    }
+    /// How much an autoincrement field grows from one record to the next, stored in byte 23 of
+    /// the field descriptor.
+    pub fn step(&self) -> Option<u8> {
+        match self.is_autoincrement() {
+            true => Some(self.autoincrement_step),
+            false => None
+        }
+    }
+    /// Describes a field of the built-in type `type_code` (such as `b'C'`), for `DbfWriter`.
+    ///
+    /// This is a shorthand for `FieldDescriptorBuilder`, and validates the field the same way.
+    pub fn new(name: &str, type_code: u8, length: u16, decimal_count: u8) -> Result<FieldDescriptor, DbaseError> {
+        FieldDescriptorBuilder::new(name, type_code)
+            .length(length)
+            .decimal_count(decimal_count)
+            .build()
+    }
+}
+
+/// Builds a `FieldDescriptor`, checking its name and size against the limits of its type.
+///
+/// Dates, logicals, integers, datetimes, doubles and currencies have a fixed length which is
+/// filled in when none is given. Memo and general fields default to the 10 byte dBASE pointer.
+///
+///     use dbase_parser::header::FieldDescriptorBuilder;
+///
+///     let amount = FieldDescriptorBuilder::new("AMOUNT", b'N')
+///         .length(10)
+///         .decimal_count(2)
+///         .build()
+///         .unwrap();
+///     assert_eq!(amount.length(), 10);
+///     let born = FieldDescriptorBuilder::new("BORN", b'D').build().unwrap();
+///     assert_eq!(born.length(), 8);
+#[derive(Debug, Clone)]
+pub struct FieldDescriptorBuilder {
+    name: String,
+    type_code: u8,
+    length: Option<u16>,
+    decimal_count: u8,
+    flags: u8,
+    autoincrement: (u32, u8),
+    field_type: Option<Arc<Box<dyn FieldType>>>
+}
+
+impl FieldDescriptorBuilder {
+    pub fn new(name: &str, type_code: u8) -> FieldDescriptorBuilder {
+        FieldDescriptorBuilder {
+            name: name.to_string(),
+            type_code,
+            length: None,
+            decimal_count: 0,
+            flags: 0,
+            autoincrement: (0, 0),
+            field_type: None
+        }
+    }
+    /// Sets the width of the field in a record, in bytes.
+    pub fn length(&mut self, length: u16) -> &mut Self {
+        self.length = Some(length);
+        self
+    }
+    /// Sets the number of digits after the decimal point, for numeric fields.
+    pub fn decimal_count(&mut self, decimal_count: u8) -> &mut Self {
+        self.decimal_count = decimal_count;
+        self
+    }
+    /// Sets the Visual FoxPro field flags, see `FieldDescriptor::flags`.
+    pub fn flags(&mut self, flags: u8) -> &mut Self {
+        self.flags = flags;
+        self
+    }
+    /// Marks the field as nullable, which makes `DbfWriter` write a Visual FoxPro table.
+    pub fn nullable(&mut self, nullable: bool) -> &mut Self {
+        match nullable {
+            true => self.flags |= 0x02,
+            false => self.flags &= !0x02
+        }
+        self
+    }
+    /// Sets the next value and the step of an autoincrement field, see
+    /// `FieldDescriptor::is_autoincrement`.
+    pub fn autoincrement(&mut self, next_value: u32, step: u8) -> &mut Self {
+        self.autoincrement = (next_value, step);
+        self
+    }
+    /// Reads and writes the field with `field_type` rather than the built-in type of its type
+    /// byte, such as a parser registered with `OpenOptions::register_field_type`. Its
+    /// `length_range` then limits the length of the field.
+    pub fn field_type(&mut self, field_type: Box<dyn FieldType>) -> &mut Self {
+        self.field_type = Some(Arc::new(field_type));
+        self
+    }
+    fn error(&self, message: String) -> DbaseError {
+        DbaseError::InvalidFieldDescriptor {
+            field: self.name.clone(),
+            message
+        }
+    }
+    pub fn build(&self) -> Result<FieldDescriptor, DbaseError> {
+        if self.name.is_empty() || self.name.len() > 10 {
+            return Err(self.error("the name must be between 1 and 10 bytes long".to_string()));
+        }
+        if !self.name.is_ascii() || self.name.bytes().any(|r| r == 0 || r == b' ') {
+            return Err(self.error("the name must be made of ASCII characters, without spaces".to_string()));
+        }
+        let field_type = match &self.field_type {
+            Some(field_type) => Arc::clone(field_type),
+            None => Database::resolve_field_type(self.type_code, self.decimal_count, self.flags, &Version::Unknown, &OpenOptions::new())?
+        };
+        let lengths = field_type.length_range();
+        let fixed_length = match lengths.start() == lengths.end() {
+            true => Some(*lengths.start()),
+            false => None
+        };
+        let length = match (self.length, fixed_length) {
+            (None, Some(fixed)) => fixed,
+            (None, None) if is_memo_type(self.type_code) => 10,
+            (None, None) => return Err(self.error("the field needs a length".to_string())),
+            (Some(length), Some(fixed)) if length != fixed => {
+                return Err(self.error(format!("a field of type {} is {} bytes long, not {}", self.type_code as char, fixed, length)));
+            },
+            (Some(length), _) if !lengths.contains(&length) => {
+                return Err(self.error(format!("a field of type {} must be between {} and {} bytes long, not {}", self.type_code as char, lengths.start(), lengths.end(), length)));
+            },
+            (Some(length), _) => length
+        };
+        if is_memo_type(self.type_code) && length != 4 && length != 10 {
+            return Err(self.error(format!("a memo pointer is 4 or 10 bytes long, not {}", length)));
+        }
+        // Numbers keep room for the integer digit and the decimal point
+        let max_decimal_count = match self.type_code {
+            b'N' | b'F' if length > 2 => (length - 2).min(15) as u8,
+            _ => 0
+        };
+        if self.decimal_count > max_decimal_count {
+            return Err(self.error(format!("at most {} decimals fit in a field of type {} and length {}", max_decimal_count, self.type_code as char, length)));
+        }
+        Ok(FieldDescriptor {
+            name: self.name.clone(),
+            field_type,
+            type_code: self.type_code,
+            data_address: 0,
+            length,
+            decimal_count: self.decimal_count,
+            flags: self.flags,
+            autoincrement_next: self.autoincrement.0,
+            autoincrement_step: self.autoincrement.1
+        })
+    }
+}
+
+/// What `Database::copy_to` does with a field of the source database.
+pub enum FieldTransform {
+    /// Copies the field unchanged
+    Keep,
+    /// Leaves the field out of the copy
+    Drop,
+    /// Copies the field with a new descriptor, passing each of its values through the function
+    Convert(FieldDescriptor, Box<dyn Fn(FieldValue) -> Result<FieldValue, DbaseError>>)
+}
+
+impl FieldTransform {
+    /// Copies `field` unchanged under a new name.
+    pub fn rename(field: &FieldDescriptor, name: &str) -> Result<FieldTransform, DbaseError> {
+        let descriptor = FieldDescriptorBuilder::new(name, field.type_code)
+            .length(field.length)
+            .decimal_count(field.decimal_count)
+            .flags(field.flags)
+            .build()?;
+        Ok(FieldTransform::Convert(descriptor, Box::new(Ok)))
+    }
 }
 
 #[derive(Debug)]
@@ -60,49 +359,371 @@ pub struct Header {
     pub record_count: u32,
     header_size: u16,
     record_size: u16,
-    fields: Vec<FieldDescriptor>
+    reserved: [u8; 20],
+    database_container: Option<String>,
+    charset: Charset,
+    fields: Rc<Vec<FieldDescriptor>>,
+    columns: Arc<Vec<String>>
 }
 
+impl Header {
+    /// Whether the dBASE IV encryption flag (header byte 15) is set.
+    pub fn is_encrypted(&self) -> bool {
+        self.reserved[3] != 0
+    }
+    /// Whether the dBASE IV incomplete transaction flag (header byte 14) is set, meaning the
+    /// file was being written to when it was last closed.
+    pub fn transaction_incomplete(&self) -> bool {
+        self.reserved[2] != 0
+    }
+    /// Whether the production index flag (header byte 28) is set, meaning a .mdx index file
+    /// accompanies the table.
+    pub fn has_mdx_index(&self) -> bool {
+        // Visual FoxPro uses the other bits of this byte for its own table flags
+        self.reserved[16] & 0x01 != 0
+    }
+    /// The reserved region of the header (bytes 12 to 31), which holds the dBASE IV flags, the
+    /// multi-user fields and the language driver.
+    pub fn reserved_bytes(&self) -> &[u8; 20] {
+        &self.reserved
+    }
+    /// The relative path to the database container (.dbc) a Visual FoxPro table is bound to.
+    pub fn database_container(&self) -> Option<&str> {
+        self.database_container.as_deref()
+    }
+    /// The code page text fields are decoded with, see `Database::with_encoding`, or `None` for
+    /// the DOS code pages 437 and 850, which `encoding_rs` has no encoding for.
+    pub fn encoding(&self) -> Option<&'static Encoding> {
+        match self.charset {
+            Charset::Encoding(encoding) => Some(encoding),
+            Charset::Dos(..) => None
+        }
+    }
+    /// The charset text fields are read and written in.
+    pub(crate) fn charset(&self) -> Charset {
+        self.charset
+    }
+    /// The field descriptors, in column order.
+    pub fn fields(&self) -> &[FieldDescriptor] {
+        &self.fields
+    }
+    /// The column position of the field called `name`.
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|column| column == name)
+    }
+    /// The size of the header on disk, field descriptors and terminator included.
+    pub fn header_size(&self) -> u16 {
+        self.header_size
+    }
+    /// The size of a single record on disk, including its leading deletion flag.
+    pub fn physical_record_size(&self) -> usize {
+        match self.record_size {
+            0 => 1 + self.fields.iter().map(|field| field.length as usize).sum::<usize>(),
+            r => r as usize
+        }
+    }
+}
+
+/// The source a database is read from.
+pub trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// The path of the copy of `path` made by `OpenOptions::atomic`, such as `table.tmp.dbf` for
+/// `table.dbf`. It keeps the extension so the copy of a memo file is still recognised.
+fn temp_sibling(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    match path.extension() {
+        Some(extension) => path.with_file_name(format!("{}.tmp.{}", stem, extension.to_string_lossy())),
+        None => path.with_file_name(format!("{}.tmp", stem))
+    }
+}
+
+/// The memo file rebuilt by `Database::pack` and the new pointers of the records kept, by record
+/// index and offset in the record.
+#[derive(Debug)]
+struct RebuiltMemos {
+    path: PathBuf,
+    pointers: Vec<(u32, usize, Vec<u8>)>
+}
+
+/// The temporary copy of a database opened with `OpenOptions::atomic`, removed when dropped
+/// unless committed.
+#[derive(Debug)]
+struct AtomicCopy {
+    target: PathBuf,
+    path: PathBuf,
+    memo_path: Option<PathBuf>,
+    // The memo file the copy of the memo file is moved over
+    memo_target: Option<PathBuf>,
+    committed: bool
+}
+
+impl Drop for AtomicCopy {
+    fn drop(&mut self) {
+        if !self.committed {
+            if let Some(memo_path) = &self.memo_path {
+                let _ = fs::remove_file(memo_path);
+            }
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// How many bytes of records `Database::append_all` buffers before writing them.
+const APPEND_BUFFER_SIZE: usize = 1 << 20;
+
 pub struct Database {
     path: PathBuf,
-    descriptor: Option<Box<dyn Read>>,
+    descriptor: Option<Box<dyn ReadSeek>>,
     pub memo: Option<Box<dyn MemoContainer>>,
-    pub header: Header
+    memo_path: Option<PathBuf>,
+    memo_writer: Option<Box<dyn MemoWriter>>,
+    memo_cache: MemoCache,
+    pub header: Header,
+    mdx_index: Option<PathBuf>,
+    // Another handle on the database file, for locking and writing
+    file: Option<File>,
+    deleted_count: Option<u32>,
+    include_deleted: bool,
+    options: OpenOptions,
+    // Last, so that it's dropped once the handles on the copy are closed
+    atomic_copy: Option<AtomicCopy>
 }
 
-fn parse_date(data: Vec<u8>) -> Result<Date<Utc>, io::Error> {
+/// Parses the last update date of the header. The year is stored as a single byte counting
+/// from 1900; bytes below `year_pivot` are taken to be two-digit years in the 2000s instead.
+///
+/// Some writers leave the month and day zeroed, or write impossible ones, in which case the
+/// date is read as January 1 of the year.
+fn parse_date(data: Vec<u8>, year_pivot: u8) -> Result<Date<Utc>, io::Error> {
     match data.len() {
         3 => {
-            let year:i32 = (data[0] as i32) + 1900;
+            let year:i32 = match data[0] < year_pivot {
+                true => (data[0] as i32) + 2000,
+                false => (data[0] as i32) + 1900
+            };
             let month = data[1];
             let day = data[2];
-            Ok(Utc.ymd(year, month.into(), day.into()))
+            let date = NaiveDate::from_ymd_opt(year, month.into(), day.into())
+                .or_else(|| NaiveDate::from_ymd_opt(year, 1, 1))
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("The year {} is not a valid date", year)))?;
+            Ok(fields::utc_date(date))
         },
         _ => Err(io::Error::new(io::ErrorKind::InvalidData, format!("The field value {:?} is not a valid date", data)))
     }
 }
 
-trait MemoContainer:Debug {
+/// Whether fields of type `type_code` hold a pointer to the memo file: memos, general fields
+/// and pictures.
+pub(crate) fn is_memo_type(type_code: u8) -> bool {
+    matches!(type_code, b'M' | b'G' | b'P')
+}
+
+/// Whether `DbfWriter` knows how to write fields of type `type_code` by itself, rather than
+/// with `FieldType::encode`.
+pub(crate) fn is_builtin_type(type_code: u8) -> bool {
+    b"CDFLMNGPITBYVQ+@O0".contains(&type_code)
+}
+
+/// Whether fields of type `type_code` are of variable length: Visual FoxPro varchar and
+/// varbinary fields.
+pub(crate) fn is_varlength_type(type_code: u8) -> bool {
+    matches!(type_code, b'V' | b'Q')
+}
+
+/// The content of the `_NullFlags` column of a record, or nothing if the table has none.
+fn null_flags<'a>(fields: &[FieldDescriptor], bytes: &'a [u8]) -> &'a [u8] {
+    let mut offset = 1;
+    for field in fields {
+        let end = offset + field.length as usize;
+        if field.type_code == writer::NULL_FLAGS_TYPE {
+            return bytes.get(offset..end).unwrap_or(&[]);
+        }
+        offset = end;
+    }
+    &[]
+}
+
+/// What a memo holds, as recorded by Visual FoxPro memo files at the start of each memo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoType {
+    Picture,
+    Text,
+    Object
+}
+impl MemoType {
+    pub fn from_u32(memo_type: u32) -> Option<MemoType> {
+        match memo_type {
+            0 => Some(MemoType::Picture),
+            1 => Some(MemoType::Text),
+            2 => Some(MemoType::Object),
+            _ => None
+        }
+    }
+}
+
+/// A memo and its type, if the memo file records one.
+pub type TypedMemo = (Vec<u8>, Option<MemoType>);
+
+/// Where the memo, general and picture fields of a database are read from: the `.dbt`, `.fpt` or
+/// `.smt` file next to it by default, or any other store set with `Database::with_memo_container`.
+pub trait MemoContainer:Debug {
+    /// Reads the memo `id` points to, `id` being the content of the field in the record. A blank
+    /// or zero pointer is an empty memo.
     fn memo(&mut self, id: Vec<u8>) -> Result<Vec<u8>, io::Error>;
+    /// Reads the memo `id` points to along with its type, for memo files which record one.
+    fn typed_memo(&mut self, id: Vec<u8>) -> Result<TypedMemo, io::Error> {
+        Ok((self.memo(id)?, None))
+    }
+    /// Streams the memo `id` points to instead of reading it whole, which by default it still is.
+    fn memo_reader(&mut self, id: Vec<u8>) -> Result<Box<dyn Read + '_>, io::Error> {
+        Ok(Box::new(io::Cursor::new(self.memo(id)?)))
+    }
+    /// Checks the memos `pointers` point to and the blocks around them, see
+    /// `Database::verify_memos`. Containers which cannot tell report no problem.
+    fn verify(&mut self, pointers: &[Vec<u8>]) -> Result<Vec<MemoProblem>, io::Error> {
+        let _ = pointers;
+        Ok(vec![])
+    }
+    /// The layout of the memo file, see `Database::memo_stats`. Containers which are not backed
+    /// by a memo file have none.
+    fn stats(&mut self) -> Result<Option<MemoStats>, io::Error> {
+        Ok(None)
+    }
+}
+
+/// A problem found in a memo file by `Database::verify_memos`. Blocks are numbered from the
+/// start of the file, header included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoProblem {
+    /// A record holds a pointer which is not a block number
+    InvalidPointer(Vec<u8>),
+    /// A record points into the header of the memo file, or past its end
+    OutOfBounds { block: u32 },
+    /// The memo at `block` cannot be read, such as for a length past the end of the file
+    InvalidMemo { block: u32, message: String },
+    /// The memo at `block` runs into the memo starting at `other`
+    Overlap { block: u32, other: u32 },
+    /// `count` blocks from `block` on are used by no memo
+    Orphan { block: u32, count: u32 },
+    /// The header gives `next_available` as the next free block, but memos run up to `end`, so
+    /// new memos would be written over them
+    StaleNextBlock { next_available: u32, end: u32 }
+}
+
+/// Checks the memos `pointers` point to, as read by `decode`, in a memo file of `file_size` bytes
+/// made of `block_size` byte blocks, memos taking up as many blocks as `span` says. See
+/// `MemoContainer::verify`. Also returns how many blocks the memos take up.
+fn verify_memo_blocks<F>(pointers: &[Vec<u8>], decode: fn(&[u8]) -> Result<Option<u32>, io::Error>, block_size: u64, next_available: u32, file_size: u64, mut span: F) -> (Vec<MemoProblem>, u32)
+    where F: FnMut(u32) -> Result<u32, io::Error> {
+    let mut problems = vec![];
+    let mut blocks = vec![];
+    for pointer in pointers {
+        match decode(pointer) {
+            Ok(Some(block)) => blocks.push(block),
+            Ok(None) => (),
+            Err(_) => problems.push(MemoProblem::InvalidPointer(pointer.clone()))
+        }
+    }
+    // Records may share a memo
+    blocks.sort_unstable();
+    blocks.dedup();
+    let first_block = u64::from(writer::MEMO_HEADER_SIZE).div_ceil(block_size) as u32;
+    let file_blocks = file_size.div_ceil(block_size) as u32;
+    let mut spans = vec![];
+    for block in blocks {
+        if block < first_block || block >= file_blocks {
+            problems.push(MemoProblem::OutOfBounds { block });
+            continue;
+        }
+        match span(block) {
+            Ok(count) => spans.push((block, block.saturating_add(count.max(1)))),
+            Err(e) => problems.push(MemoProblem::InvalidMemo { block, message: e.to_string() })
+        }
+    }
+    // Walk the memos in order, noting the blocks between them and those they share
+    let mut end = first_block;
+    let mut last_start = first_block;
+    let mut used_blocks = 0;
+    for (start, span_end) in spans {
+        match start < end {
+            true => problems.push(MemoProblem::Overlap { block: last_start, other: start }),
+            false if start > end => problems.push(MemoProblem::Orphan { block: end, count: start - end }),
+            false => ()
+        }
+        used_blocks += span_end.min(file_blocks).saturating_sub(start.max(end));
+        last_start = start;
+        end = end.max(span_end);
+    }
+    let used_end = next_available.min(file_blocks);
+    if used_end > end {
+        problems.push(MemoProblem::Orphan { block: end, count: used_end - end });
+    }
+    if next_available < end {
+        problems.push(MemoProblem::StaleNextBlock { next_available, end });
+    }
+    (problems, used_blocks)
+}
+
+/// Reads the next free block from the header of a memo file, big-endian in .fpt files, again
+/// rather than as it was when opened, since memos may have been written since.
+fn header_next_available<R: Read + Seek>(descriptor: &mut R, big_endian: bool) -> Result<u32, io::Error> {
+    descriptor.seek(io::SeekFrom::Start(0))?;
+    match big_endian {
+        true => descriptor.read_u32::<BigEndian>(),
+        false => descriptor.read_u32::<LittleEndian>()
+    }
+}
+
+/// The layout of a memo file and, once it has been checked with `Database::verify_memos`, how
+/// much of it memos take up. See `Database::memo_stats`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoStats {
+    pub block_size: u32,
+    /// The blocks of the file, header included
+    pub block_count: u32,
+    /// The next free block according to the header, where the next memo is written
+    pub next_available: u32,
+    /// The blocks taken up by the memos of the records, as of the last check
+    pub used_blocks: Option<u32>,
+    /// The blocks past the header taken up by no memo, as of the last check. `Database::pack`
+    /// reclaims them along with the blocks of the memos of deleted records.
+    pub free_blocks: Option<u32>
+}
+impl MemoStats {
+    fn new(block_size: u32, file_size: u64, next_available: u32, used_blocks: Option<u32>) -> MemoStats {
+        let block_count = file_size.div_ceil(u64::from(block_size)) as u32;
+        let first_block = writer::MEMO_HEADER_SIZE.div_ceil(block_size);
+        MemoStats {
+            block_size,
+            block_count,
+            next_available,
+            used_blocks,
+            free_blocks: used_blocks.map(|used_blocks| block_count.saturating_sub(first_block).saturating_sub(used_blocks))
+        }
+    }
 }
 
 #[derive(Debug)]
-pub struct FoxProMemoContainer {
-    descriptor: File,
+pub struct FoxProMemoContainer<R = File> {
+    descriptor: R,
+    max_memo_size: usize,
+    // The blocks taken up by memos, as of the last `verify`
+    used_blocks: Option<u32>,
     fragment_size: u32,
     block_size: u32
 }
 impl FoxProMemoContainer {
     pub fn open<T:AsRef<Path>>(path: T) -> Result<Self, io::Error> {
-        let mut file = File::open(path)?;
+        Self::from_reader(File::open(path)?)
+    }
+}
+impl<R: Read + Seek> FoxProMemoContainer<R> {
+    /// Reads the memos of an .fpt file from any source, such as an in-memory buffer.
+    pub fn from_reader(mut file: R) -> Result<Self, io::Error> {
         let mut buf = vec![];
         buf.resize(8, 0);
         file.read_exact(&mut buf)?;
-        let next_available = {
-            let bytes = buf[0..4].to_vec();
-            let mut reader = io::Cursor::new(bytes);
-            reader.read_u32::<LittleEndian>()?
-        };
         let block_size = {
             let bytes = buf[4..6].to_vec();
             let mut reader = io::Cursor::new(bytes);
@@ -111,91 +732,272 @@ impl FoxProMemoContainer {
                 v => v
             }
         };
-        let fragment_size = {
-            let bytes = buf[7];
-            bytes
-
+        let fragment_size = match (&buf[6..8]).read_u16::<BigEndian>()? {
+            0 => 64,
+            v => v
         };
         Ok(FoxProMemoContainer {
             descriptor: file,
+            max_memo_size: usize::MAX,
+            used_blocks: None,
             fragment_size: fragment_size as u32,
             block_size: block_size as u32
         })
     }
 }
-impl MemoContainer for FoxProMemoContainer {
-    fn memo(&mut self, data:Vec<u8>) -> Result<Vec<u8>, io::Error> {
-        let id:u32 = {
-            let mut reader = io::Cursor::new(data);
-            reader.read_u32::<LittleEndian>()?
-        };
+impl<R: Read + Seek> FoxProMemoContainer<R> {
+    /// Seeks to the memo `data` points to, returning its type and length, or `None` if the field
+    /// has no memo.
+    fn seek_memo(&mut self, data: Vec<u8>) -> Result<Option<(Option<MemoType>, u32)>, io::Error> {
+        match memo_block(&data)? {
+            Some(id) => self.seek_block(id).map(Some),
+            None => Ok(None)
+        }
+    }
+    /// Seeks to the content of the memo in block `id`, returning its type and length.
+    fn seek_block(&mut self, id: u32) -> Result<(Option<MemoType>, u32), io::Error> {
         self.descriptor.seek(io::SeekFrom::Start((self.fragment_size as u64)* (id as u64)))?;
-        let data_type = {
-            let mut buf_header = vec![];
-            buf_header.resize(4, 0);
-            self.descriptor.read_exact(&mut buf_header)?;
-            buf_header[2]
-        };
-        // Seek another 4 bytes to get the length of the memo
-        let memo_len = {
-            let mut buf_length = vec![];
-            buf_length.resize(4, 0);
-            self.descriptor.read_exact(&mut buf_length)?;
-            let mut reader = io::Cursor::new(buf_length);
-            reader.read_u32::<BigEndian>()?
+        // Each memo starts with its type and length
+        let memo_type = self.descriptor.read_u32::<BigEndian>()?;
+        let memo_len = self.descriptor.read_u32::<BigEndian>()?;
+        check_memo_length(&mut self.descriptor, id, memo_len, self.max_memo_size)?;
+        Ok((MemoType::from_u32(memo_type), memo_len))
+    }
+}
+impl<R: Read + Seek + Debug> MemoContainer for FoxProMemoContainer<R> {
+    fn memo(&mut self, data:Vec<u8>) -> Result<Vec<u8>, io::Error> {
+        self.typed_memo(data).map(|(memo, _)| memo)
+    }
+    fn typed_memo(&mut self, data: Vec<u8>) -> Result<TypedMemo, io::Error> {
+        let (memo_type, memo_len) = match self.seek_memo(data)? {
+            Some(memo) => memo,
+            None => return Ok((vec![], None))
         };
         // Read the memo
         let mut memo_buf = vec![];
         memo_buf.resize(memo_len as usize, 0);
         self.descriptor.read_exact(&mut memo_buf)?;
-        Ok(memo_buf)
+        Ok((memo_buf, memo_type))
+    }
+    fn memo_reader(&mut self, data: Vec<u8>) -> Result<Box<dyn Read + '_>, io::Error> {
+        match self.seek_memo(data)? {
+            Some((_, memo_len)) => Ok(Box::new((&mut self.descriptor).take(u64::from(memo_len)))),
+            None => Ok(Box::new(io::empty()))
+        }
+    }
+    fn verify(&mut self, pointers: &[Vec<u8>]) -> Result<Vec<MemoProblem>, io::Error> {
+        let block_size = u64::from(self.fragment_size);
+        let file_size = self.descriptor.seek(io::SeekFrom::End(0))?;
+        let next_available = header_next_available(&mut self.descriptor, true)?;
+        // Memos take up their 8 byte header and their content
+        let (problems, used_blocks) = verify_memo_blocks(pointers, memo_block, block_size, next_available, file_size, |block| {
+            let (_, memo_len) = self.seek_block(block)?;
+            Ok((8 + u64::from(memo_len)).div_ceil(block_size) as u32)
+        });
+        self.used_blocks = Some(used_blocks);
+        Ok(problems)
+    }
+    fn stats(&mut self) -> Result<Option<MemoStats>, io::Error> {
+        let file_size = self.descriptor.seek(io::SeekFrom::End(0))?;
+        Ok(Some(MemoStats::new(self.fragment_size, file_size, header_next_available(&mut self.descriptor, true)?, self.used_blocks)))
+    }
+}
+/// The block number a memo field points to, or `None` if the field has no memo.
+///
+/// The encoding of the pointer follows from the length of the field rather than from the memo
+/// file: 4 byte fields hold a little-endian integer, as FoxPro writes them, and 10 byte fields
+/// the block number in ASCII, as dBASE writes them. Block 0 is the header of the memo file.
+fn memo_block(data: &[u8]) -> Result<Option<u32>, io::Error> {
+    // A blank pointer means the field has no memo
+    if data.iter().all(|r| *r == b' ' || *r == 0) {
+        return Ok(None);
+    }
+    let id = match data.len() {
+        4 => u32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+        _ => String::from_utf8(data.to_vec()).ok()
+            .and_then(|data_str| data_str.trim_matches(|c| c == ' ' || c == '\0').parse().ok())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} is not a memo block number", data)))?
+    };
+    match id {
+        0 => Ok(None),
+        id => Ok(Some(id))
     }
 }
+
+/// Checks the length a memo declares before it is read from the current position of
+/// `descriptor`, so a corrupt length is an error rather than an allocation of up to 4 GiB.
+fn check_memo_length<R: Seek>(descriptor: &mut R, id: u32, length: u32, max_memo_size: usize) -> Result<(), io::Error> {
+    let position = descriptor.stream_position()?;
+    let end = descriptor.seek(io::SeekFrom::End(0))?;
+    descriptor.seek(io::SeekFrom::Start(position))?;
+    if length as usize > max_memo_size {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("The memo at block {} declares {} bytes, above the limit of {}", id, length, max_memo_size)));
+    }
+    if u64::from(length) > end.saturating_sub(position) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("The memo at block {} declares {} bytes, past the end of the memo file", id, length)));
+    }
+    Ok(())
+}
+
+/// The memos read last, keyed by the pointer stored in the record, see `OpenOptions::memo_cache`.
 #[derive(Debug)]
-pub struct DBaseMemoContainer {
-    descriptor: File,
+struct MemoCache {
+    capacity: usize,
+    memos: HashMap<Vec<u8>, TypedMemo>,
+    // The pointers of the cached memos, least recently used first
+    order: VecDeque<Vec<u8>>
+}
+impl MemoCache {
+    fn new(capacity: usize) -> Self {
+        MemoCache {
+            capacity,
+            memos: HashMap::new(),
+            order: VecDeque::new()
+        }
+    }
+    fn get(&mut self, pointer: &[u8]) -> Option<TypedMemo> {
+        let memo = self.memos.get(pointer)?.clone();
+        if let Some(position) = self.order.iter().position(|r| r == pointer) {
+            let pointer = self.order.remove(position)?;
+            self.order.push_back(pointer);
+        }
+        Some(memo)
+    }
+    fn insert(&mut self, pointer: Vec<u8>, memo: TypedMemo) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.memos.len() >= self.capacity {
+            if let Some(evicted) = self.order.pop_front() {
+                self.memos.remove(&evicted);
+            }
+        }
+        self.order.push_back(pointer.clone());
+        self.memos.insert(pointer, memo);
+    }
+    fn clear(&mut self) {
+        self.memos.clear();
+        self.order.clear();
+    }
+}
+
+/// Where dBASE III memos, which have no length, end. See `OpenOptions::memo_end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoEnd {
+    /// At the last 0x1A byte of the first block holding one
+    FirstMarker,
+    /// At the 0x1A 0x1A pair dBASE III writes, so lone 0x1A bytes in binary memos are kept. Memos
+    /// starting with a dBASE IV block header are read as long as it says.
+    DoubleMarker
+}
+
+#[derive(Debug)]
+pub struct DBaseMemoContainer<R = File> {
+    descriptor: R,
+    max_memo_size: usize,
+    // The blocks taken up by memos, as of the last `verify`
+    used_blocks: Option<u32>,
+    memo_end: MemoEnd,
     block_size: usize,
-    next_available: usize
+    dbase4: bool
 }
 impl DBaseMemoContainer {
     pub fn open<T:AsRef<Path>>(path: T) -> Result<Self, io::Error> {
-        let mut file = File::open(path)?;
-        let mut buf = vec![];
-        buf.resize(8, 0);
+        Self::from_reader(File::open(path)?)
+    }
+}
+impl<R: Read + Seek> DBaseMemoContainer<R> {
+    /// Reads the memos of a .dbt file from any source, such as an in-memory buffer.
+    pub fn from_reader(mut file: R) -> Result<Self, io::Error> {
+        let mut buf = [0; 22];
         file.read_exact(&mut buf)?;
-        let next_available = {
-            let bytes = buf[0..4].to_vec();
-            let mut reader = io::Cursor::new(bytes);
-            reader.read_u32::<LittleEndian>()?
-        };
-        let block_size = {
-            let bytes = buf[4..6].to_vec();
-            let mut reader = io::Cursor::new(bytes);
-            match reader.read_u16::<LittleEndian>()? {
-                0 => 512,
-                v => v
-            }
-        };
+        let (block_size, dbase4) = dbt_layout(&buf);
         Ok(DBaseMemoContainer {
             descriptor: file,
+            max_memo_size: usize::MAX,
+            used_blocks: None,
+            memo_end: MemoEnd::FirstMarker,
             block_size: block_size as usize,
-            next_available: next_available as usize
+            dbase4
         })
     }
 }
-impl MemoContainer for DBaseMemoContainer {
+/// Reads the block size of a .dbt memo file from its header, and whether it is laid out the
+/// dBASE IV way. dBASE IV stores the block size in bytes 20 and 21 and starts each memo with a
+/// marker and its length, where dBASE III always uses 512 byte blocks (which some writers still
+/// store in bytes 4 and 5) and ends memos with 0x1A.
+pub(crate) fn dbt_layout(header: &[u8; 22]) -> (u32, bool) {
+    let dbase4_block_size = u16::from_le_bytes([header[20], header[21]]);
+    let block_size = u16::from_le_bytes([header[4], header[5]]);
+    match (dbase4_block_size, block_size) {
+        (0, 0) => (512, false),
+        (0, v) => (u32::from(v), false),
+        (v, _) => (u32::from(v), true)
+    }
+}
+impl<R: Read + Seek> DBaseMemoContainer<R> {
+    /// Reads the dBASE III memo in block `id` up to the 0x1A 0x1A pair ending it, or as long as
+    /// its length says if it starts with a dBASE IV block header, see `MemoEnd::DoubleMarker`.
+    fn read_to_double_marker(&mut self, id: u32) -> Result<Vec<u8>, io::Error> {
+        let mut block_header = [0; 8];
+        if self.descriptor.read_exact(&mut block_header).is_ok() && block_header[0..4] == writer::DBASE4_MEMO_MARKER {
+            let length = self.seek_dbase4_memo(id)?;
+            let mut memo_bytes = vec![0; length as usize];
+            self.descriptor.read_exact(&mut memo_bytes)?;
+            return Ok(memo_bytes);
+        }
+        self.descriptor.seek(io::SeekFrom::Start((self.block_size as u64) * (id as u64)))?;
+        let mut memo_bytes = vec![];
+        loop {
+            let mut bytes = vec![0; self.block_size];
+            let bytes_read = self.descriptor.read(&mut bytes)?;
+            bytes.truncate(bytes_read);
+            // The pair may straddle two blocks
+            let searched = memo_bytes.len().saturating_sub(1);
+            memo_bytes.append(&mut bytes);
+            if let Some(end) = memo_bytes[searched..].windows(2).position(|r| r == [0x1a, 0x1a]) {
+                memo_bytes.truncate(searched + end);
+                break;
+            }
+            if bytes_read == 0 || memo_bytes.len() > self.max_memo_size {
+                break;
+            }
+        }
+        if memo_bytes.len() > self.max_memo_size {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("The memo at block {} is longer than the limit of {} bytes", id, self.max_memo_size)));
+        }
+        Ok(memo_bytes)
+    }
+    /// Seeks to the content of the dBASE IV memo in block `id`, returning its length.
+    fn seek_dbase4_memo(&mut self, id: u32) -> Result<u32, io::Error> {
+        self.descriptor.seek(io::SeekFrom::Start((self.block_size as u64) * (id as u64)))?;
+        // dBASE IV memos start with a marker and their length, header included
+        let mut block_header = [0; 8];
+        self.descriptor.read_exact(&mut block_header)?;
+        if block_header[0..4] != writer::DBASE4_MEMO_MARKER {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("The memo block {} has no dBASE IV marker", id)));
+        }
+        let length = (&block_header[4..8]).read_u32::<LittleEndian>()?.saturating_sub(8);
+        check_memo_length(&mut self.descriptor, id, length, self.max_memo_size)?;
+        Ok(length)
+    }
+}
+impl<R: Read + Seek + Debug> MemoContainer for DBaseMemoContainer<R> {
     fn memo(&mut self, data: Vec<u8>) -> Result<Vec<u8>, io::Error> {
-        let id:u32 = {
-            String::from_utf8(data.clone())
-                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} cannot be casted to a string", data)))
-                .and_then(|data_str| {
-                    FromStr::from_str(data_str.trim_start())
-                    .map_err(|_| {
-                        io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} cannot be casted to a float", data))
-                    })
-                })?
+        let id = match memo_block(&data)? {
+            Some(id) => id,
+            None => return Ok(vec![])
         };
+        if self.dbase4 {
+            let length = self.seek_dbase4_memo(id)?;
+            let mut memo_bytes = vec![0; length as usize];
+            self.descriptor.read_exact(&mut memo_bytes)?;
+            return Ok(memo_bytes);
+        }
         self.descriptor.seek(io::SeekFrom::Start((self.block_size as u64) * (id as u64)))?;
+        if self.memo_end == MemoEnd::DoubleMarker {
+            return self.read_to_double_marker(id);
+        }
         let mut memo_bytes = vec![];
         let mut done = false;
         while !done {
@@ -204,6 +1006,10 @@ impl MemoContainer for DBaseMemoContainer {
             let bytes_read = self.descriptor.read(&mut bytes)?;
             done = bytes_read < self.block_size || bytes.contains(&0x1a);
             memo_bytes.append(&mut bytes);
+            // dBASE III memos have no length, so stop reading them once past the limit
+            if memo_bytes.len() > self.max_memo_size.saturating_add(self.block_size) {
+                break;
+            }
         }
         let mut new_bytes:Vec<Vec<u8>> = memo_bytes.rsplitn(2, |n| *n == 0x1a).map(|r| r.to_vec()).collect();
         if new_bytes.len() > 1 {
@@ -216,83 +1022,344 @@ impl MemoContainer for DBaseMemoContainer {
             Some(r) if *r == 0x1a => { output.pop(); },
             _ => ()
         }
+        if output.len() > self.max_memo_size {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("The memo at block {} is longer than the limit of {} bytes", id, self.max_memo_size)));
+        }
         Ok(output)
     }
+    fn verify(&mut self, pointers: &[Vec<u8>]) -> Result<Vec<MemoProblem>, io::Error> {
+        let block_size = self.block_size as u64;
+        let file_size = self.descriptor.seek(io::SeekFrom::End(0))?;
+        let next_available = header_next_available(&mut self.descriptor, false)?;
+        // dBASE IV memos take up their 8 byte header and their content, dBASE III memos their
+        // content and end markers
+        let markers = match self.memo_end {
+            MemoEnd::FirstMarker => 1,
+            MemoEnd::DoubleMarker => 2
+        };
+        let (problems, used_blocks) = verify_memo_blocks(pointers, memo_block, block_size, next_available, file_size, |block| {
+            let length = match self.dbase4 {
+                true => 8 + u64::from(self.seek_dbase4_memo(block)?),
+                false => self.memo(block.to_string().into_bytes())?.len() as u64 + markers
+            };
+            Ok(length.div_ceil(block_size) as u32)
+        });
+        self.used_blocks = Some(used_blocks);
+        Ok(problems)
+    }
+    fn stats(&mut self) -> Result<Option<MemoStats>, io::Error> {
+        let file_size = self.descriptor.seek(io::SeekFrom::End(0))?;
+        Ok(Some(MemoStats::new(self.block_size as u32, file_size, header_next_available(&mut self.descriptor, false)?, self.used_blocks)))
+    }
+    fn memo_reader(&mut self, data: Vec<u8>) -> Result<Box<dyn Read + '_>, io::Error> {
+        // dBASE III memos only end where their terminator is found, so they are still read whole
+        if !self.dbase4 {
+            return Ok(Box::new(io::Cursor::new(self.memo(data)?)));
+        }
+        match memo_block(&data)? {
+            Some(id) => {
+                let length = self.seek_dbase4_memo(id)?;
+                Ok(Box::new((&mut self.descriptor).take(u64::from(length))))
+            },
+            None => Ok(Box::new(io::empty()))
+        }
+    }
+}
+
+/// Reads the .smt memo files of the HiPer-Six (SIx) driver for Clipper. Unlike other memo files,
+/// the record holds the length and type of the memo along with its block, so memos are stored
+/// as they are, without any header or end marker.
+#[derive(Debug)]
+pub struct SmtMemoContainer<R = File> {
+    descriptor: R,
+    max_memo_size: usize,
+    // The blocks taken up by memos, as of the last `verify`
+    used_blocks: Option<u32>,
+    block_size: u32
+}
+impl SmtMemoContainer {
+    pub fn open<T:AsRef<Path>>(path: T) -> Result<Self, io::Error> {
+        Self::from_reader(File::open(path)?)
+    }
+}
+impl<R: Read + Seek> SmtMemoContainer<R> {
+    /// Reads the memos of a .smt file from any source, such as an in-memory buffer.
+    pub fn from_reader(mut file: R) -> Result<Self, io::Error> {
+        // The next free block is read again whenever needed, see `header_next_available`
+        file.read_u32::<LittleEndian>()?;
+        let block_size = match file.read_u32::<LittleEndian>()? {
+            0 => 32,
+            v => v
+        };
+        Ok(SmtMemoContainer {
+            descriptor: file,
+            max_memo_size: usize::MAX,
+            used_blocks: None,
+            block_size
+        })
+    }
+    /// Seeks to the memo `data` points to, returning its type and length, or `None` if the field
+    /// has no memo.
+    fn seek_memo(&mut self, data: &[u8]) -> Result<Option<(Option<MemoType>, u32)>, io::Error> {
+        let (memo_type, length, block) = match smt_pointer(data)? {
+            Some(pointer) => pointer,
+            None => return Ok(None)
+        };
+        self.descriptor.seek(io::SeekFrom::Start(u64::from(self.block_size) * u64::from(block)))?;
+        check_memo_length(&mut self.descriptor, block, length, self.max_memo_size)?;
+        // Clipper values other than strings are stored serialized
+        let memo_type = match memo_type {
+            1 => MemoType::Text,
+            _ => MemoType::Object
+        };
+        Ok(Some((Some(memo_type), length)))
+    }
+}
+/// Splits the 10 bytes of a HiPer-Six memo field into the type of the Clipper value stored, its
+/// length and its block, all little-endian, or returns `None` if the field has no memo.
+fn smt_pointer(data: &[u8]) -> Result<Option<(u16, u32, u32)>, io::Error> {
+    if data.iter().all(|r| *r == b' ' || *r == 0) {
+        return Ok(None);
+    }
+    if data.len() != 10 {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("The field content {:?} is not a HiPer-Six memo pointer", data)));
+    }
+    let mut reader = data;
+    let memo_type = reader.read_u16::<LittleEndian>()?;
+    let length = reader.read_u32::<LittleEndian>()?;
+    let block = reader.read_u32::<LittleEndian>()?;
+    match (length, block) {
+        (0, _) | (_, 0) => Ok(None),
+        _ => Ok(Some((memo_type, length, block)))
+    }
+}
+impl<R: Read + Seek + Debug> MemoContainer for SmtMemoContainer<R> {
+    fn memo(&mut self, data: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+        self.typed_memo(data).map(|(memo, _)| memo)
+    }
+    fn typed_memo(&mut self, data: Vec<u8>) -> Result<TypedMemo, io::Error> {
+        let (memo_type, length) = match self.seek_memo(&data)? {
+            Some(memo) => memo,
+            None => return Ok((vec![], None))
+        };
+        let mut memo_bytes = vec![0; length as usize];
+        self.descriptor.read_exact(&mut memo_bytes)?;
+        Ok((memo_bytes, memo_type))
+    }
+    fn memo_reader(&mut self, data: Vec<u8>) -> Result<Box<dyn Read + '_>, io::Error> {
+        match self.seek_memo(&data)? {
+            Some((_, length)) => Ok(Box::new((&mut self.descriptor).take(u64::from(length)))),
+            None => Ok(Box::new(io::empty()))
+        }
+    }
+    fn verify(&mut self, pointers: &[Vec<u8>]) -> Result<Vec<MemoProblem>, io::Error> {
+        let block_size = u64::from(self.block_size);
+        let file_size = self.descriptor.seek(io::SeekFrom::End(0))?;
+        // The lengths are in the records, the longest one wins for memos shared by several
+        let mut lengths = HashMap::new();
+        for (_, length, block) in pointers.iter().filter_map(|pointer| smt_pointer(pointer).ok().flatten()) {
+            let known = lengths.entry(block).or_insert(0);
+            *known = length.max(*known);
+        }
+        let decode = |data: &[u8]| smt_pointer(data).map(|pointer| pointer.map(|(_, _, block)| block));
+        let next_available = header_next_available(&mut self.descriptor, false)?;
+        let (problems, used_blocks) = verify_memo_blocks(pointers, decode, block_size, next_available, file_size, |block| {
+            let length = lengths.get(&block).copied().unwrap_or(0);
+            self.descriptor.seek(io::SeekFrom::Start(block_size * u64::from(block)))?;
+            check_memo_length(&mut self.descriptor, block, length, self.max_memo_size)?;
+            Ok(u64::from(length).div_ceil(block_size) as u32)
+        });
+        self.used_blocks = Some(used_blocks);
+        Ok(problems)
+    }
+    fn stats(&mut self) -> Result<Option<MemoStats>, io::Error> {
+        let file_size = self.descriptor.seek(io::SeekFrom::End(0))?;
+        Ok(Some(MemoStats::new(self.block_size, file_size, header_next_available(&mut self.descriptor, false)?, self.used_blocks)))
+    }
 }
 
 #[derive(Debug)]
 pub struct Record {
-    pub fields: HashMap<String, FieldValue>
+    pub fields: HashMap<String, FieldValue>,
+    /// Whether the record is flagged as deleted
+    pub deleted: bool,
+    columns: Arc<Vec<String>>
 }
 impl Record {
+    /// Builds an active record from values keyed by field name, such as for
+    /// `Database::append_all`. Its columns are ordered by name.
+    pub fn new(fields: HashMap<String, FieldValue>) -> Record {
+        let mut columns:Vec<String> = fields.keys().cloned().collect();
+        columns.sort();
+        Record {
+            fields,
+            deleted: false,
+            columns: Arc::new(columns)
+        }
+    }
     pub fn get(&self, field: &str) -> Option<&FieldValue> {
         self.fields.get(&field.to_string())
     }
+    /// Gets a field by its column position, see `Header::field_index`.
+    pub fn get_by_index(&self, idx: usize) -> Option<&FieldValue> {
+        self.columns.get(idx).and_then(|name| self.fields.get(name))
+    }
+    /// Converts the record to a JSON object keyed by field name, see `FieldValue::to_json`.
+    #[cfg(feature = "serde")]
+    pub fn to_json_map(&self) -> serde_json::Map<String, serde_json::Value> {
+        self.fields.iter().map(|(name, value)| (name.clone(), value.to_json())).collect()
+    }
 }
 
+/// Whether a record is active or flagged as deleted, as read from its leading status byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordStatus {
+    Active,
+    Deleted
+}
+
+impl RecordStatus {
+    fn from_byte(byte: u8) -> RecordStatus {
+        match byte {
+            0x2a => RecordStatus::Deleted,
+            _ => RecordStatus::Active
+        }
+    }
+}
+
+/// Iterates over the records of a database, skipping deleted ones unless
+/// `Database::include_deleted` is set.
+///
+/// A record which cannot be read or parsed is yielded as an error without ending the iteration.
+/// To skip those records instead:
+///
+///     let records = dbase_parser::open("tests/reference_gis.dbf").unwrap()
+///         .into_iter()
+///         .filter_map(Result::ok);
 pub struct DatabaseRecordIterator {
     database: Database,
-    record_size: usize,
-    fields: Arc<Vec<FieldDescriptor>>
-}
-
-impl DatabaseRecordIterator {
-    fn parse_row(&mut self, mut bytes: Vec<u8>) -> Result<Record, io::Error> {
-        let fields_clone = Arc::clone(&self.fields);
-        let fields:Result<Vec<(String, FieldValue)>, io::Error> = fields_clone.iter().fold(Ok(vec![]), |fields, field| {
-            fields.and_then(|mut fields| {
-                let record_bytes:Vec<u8> = bytes.drain(0..(field.length as usize)).collect();
-                field.field_type.parse(&mut self.database, record_bytes).map(|r| {
-                    fields.push((field.name.clone(), r));
-                    fields
-                }).map_err(|e| {
-                    e
-                })
-            })
-        });
-        fields.map(|fields| {
-            Record {
-                fields: fields.into_iter().collect()
-            }
-        })
-    }
+    index: u32
+}
+
+/// A SAX-style receiver for the contents of a database, see `Database::visit`.
+///
+/// The visitor is handed every field of every record in column order, without a `Record` or
+/// `HashMap` ever being built.
+pub trait RecordVisitor {
+    /// Called once for every field of the current record.
+    fn field(&mut self, name: &str, value: FieldValue);
+    /// Called after the last field of a record has been visited.
+    fn end_record(&mut self) {}
 }
 
 impl Iterator for DatabaseRecordIterator {
-    type Item = Record;
+    type Item = Result<Record, DbaseError>;
     fn next(&mut self) -> Option<Self::Item> {
-        // Read the next record
-        self.database.read_bytes(self.record_size)
-        .and_then(|buf| {
-            self.parse_row(buf)
-        }).ok()
+        while self.index < self.database.header.record_count {
+            // Read the next record
+            let record = self.database.read_record(self.index);
+            self.index += 1;
+            match record {
+                Ok(ref r) if r.deleted && !self.database.include_deleted => continue,
+                r => return Some(r)
+            }
+        }
+        None
     }
 }
 impl IntoIterator for Database {
-    type Item = Record;
+    type Item = Result<Record, DbaseError>;
     type IntoIter = DatabaseRecordIterator;
 
     fn into_iter(self) -> Self::IntoIter {
-        let fields = self.header.fields.clone();
-        let record_size:usize = self.header.fields.iter().fold(0 as usize, |current, field| current + (field.length as usize)) as usize;
         DatabaseRecordIterator {
             database: self,
-            record_size: record_size,
-            fields: Arc::new(fields)
+            index: 0
         }
     }
 }
 impl Database {
-    fn read_bytes(&mut self, count: usize) -> Result<Vec<u8>, io::Error> {
+    fn descriptor(&mut self) -> Result<&mut Box<dyn ReadSeek>, io::Error> {
         self.descriptor.as_mut().ok_or(io::Error::new(io::ErrorKind::NotFound, "No descriptor"))
-        .and_then(|file| {
-            let mut buf = vec![];
-            buf.resize(count+1, 0);
-            file.read_exact(&mut buf)?;
-            Ok(buf)
+    }
+    /// Reads the record under the cursor, deletion flag included.
+    fn read_record_bytes(&mut self) -> Result<Vec<u8>, io::Error> {
+        let mut buf = vec![0; self.header.physical_record_size()];
+        self.descriptor()?.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+    fn seek_to_record(&mut self, index: u32) -> Result<(), io::Error> {
+        let offset = u64::from(self.header.header_size) + u64::from(index) * (self.header.physical_record_size() as u64);
+        self.descriptor()?.seek(io::SeekFrom::Start(offset))?;
+        Ok(())
+    }
+    fn parse_record(&mut self, bytes: Vec<u8>) -> Result<Record, io::Error> {
+        let deleted = bytes.first().map(|r| RecordStatus::from_byte(*r) == RecordStatus::Deleted).unwrap_or(false);
+        let fields = Rc::clone(&self.header.fields);
+        let mut values = HashMap::new();
+        self.parse_row_with(&fields, bytes, |name, value| {
+            values.insert(name.to_string(), value);
+        })?;
+        Ok(Record {
+            deleted,
+            fields: values,
+            columns: Arc::clone(&self.header.columns)
         })
     }
-    fn parse_fields(buffer: Vec<u8>) -> Result<Vec<FieldDescriptor>, io::Error> {
+    fn read_record(&mut self, index: u32) -> Result<Record, DbaseError> {
+        self.read_record_with_status(index).map(|(_status, record)| record)
+    }
+    fn read_record_with_status(&mut self, index: u32) -> Result<(RecordStatus, Record), DbaseError> {
+        self.seek_to_record(index)?;
+        let bytes = self.read_record_bytes()?;
+        let status = RecordStatus::from_byte(bytes[0]);
+        Ok((status, self.parse_record(bytes)?))
+    }
+    /// Finds the parser for a field type byte.
+    ///
+    /// Parsers registered through `OpenOptions::register_field_type` are consulted first, then the
+    /// built-in types. An unknown byte is an error unless `OpenOptions::allow_unknown_field_types`
+    /// is set, in which case the field is passed through raw as `FieldValue::Unknown`.
+    fn resolve_field_type(type_code: u8, decimal_count: u8, flags: u8, version: &Version, options: &OpenOptions) -> Result<Arc<Box<dyn FieldType>>, io::Error> {
+        if let Some(field_type) = options.field_types.get(&type_code) {
+            return Ok(Arc::clone(field_type));
+        }
+        // Visual FoxPro flags character and memo fields which hold bytes rather than text
+        let binary = matches!(version, Version::VisualFoxPro(_, _)) && flags & 0x04 != 0;
+        let builtin:Option<Box<dyn FieldType>> = match type_code {
+            67 if binary => Some(Box::new(fields::FieldTypeQ)),
+            77 if binary => Some(Box::new(fields::FieldTypeG)),
+            67 => Some(Box::new(fields::FieldTypeC)),
+            68 => Some(Box::new(fields::FieldTypeD)),
+            70 => Some(Box::new(fields::FieldTypeF { decimal_count })),
+            78 => Some(Box::new(fields::FieldTypeOldNumeric { decimal_count })),
+            76 => Some(Box::new(fields::FieldTypeL)),
+            // dBASE 7 timestamps share the days and milliseconds of datetimes
+            84 | 64 => Some(Box::new(fields::FieldTypeT)),
+            73 | 43 => Some(Box::new(fields::FieldTypeI)),
+            77 => Some(Box::new(fields::FieldTypeM)),
+            // B is a binary memo for dBASE IV and later, but a double for the FoxPro family
+            66 => match version {
+                Version::dBASE4Table(_) | Version::dBASE4System(_) => Some(Box::new(fields::FieldTypeG)),
+                _ => Some(Box::new(fields::FieldTypeB))
+            },
+            79 => Some(Box::new(fields::FieldTypeO)),
+            89 => Some(Box::new(fields::FieldTypeY)),
+            // Pictures are read like general fields, as raw bytes from the memo file
+            71 | 80 => Some(Box::new(fields::FieldTypeG)),
+            86 => Some(Box::new(fields::FieldTypeV)),
+            81 => Some(Box::new(fields::FieldTypeQ)),
+            // The Visual FoxPro _NullFlags column, kept as raw bits
+            48 => Some(Box::new(fields::FieldTypeRaw)),
+            _ => None
+        };
+        match builtin {
+            Some(field_type) => Ok(Arc::new(field_type)),
+            None if options.allow_unknown_field_types => Ok(Arc::new(Box::new(fields::FieldTypeRaw))),
+            None => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown field type {}", type_code)))
+        }
+    }
+    fn parse_fields(buffer: &[u8], version: &Version, options: &OpenOptions) -> Result<Vec<FieldDescriptor>, io::Error> {
         let mut iter = buffer.chunks(32);
         let mut fields = vec![];
         let mut done = false;
@@ -302,31 +1369,33 @@ impl Database {
                 .map(|e| {
                     e.trim().replace("\0", "")
                 })?;
-            let field_type_res:Result<Box<dyn fields::FieldType>, io::Error> = match data[11] {
-                67 => Ok(Box::new(fields::FieldTypeC)),
-                68 => Ok(Box::new(fields::FieldTypeD)),
-                70 | 78 => Ok(Box::new(fields::FieldTypeOldNumeric)),
-                76 => Ok(Box::new(fields::FieldTypeL)),
-                84 => Ok(Box::new(fields::FieldTypeT)),
-                73 => Ok(Box::new(fields::FieldTypeI)),
-                77 => Ok(Box::new(fields::FieldTypeM)),
-                d => Err(io::Error::new(io::ErrorKind::InvalidData, format!("Unknown field type {}", d)))
-            };
-            let field_type = field_type_res?;
+            let field_type = Self::resolve_field_type(data[11], data[17], data[18], version, options)?;
             let field_address = {
                 let bytes = data[12..16].to_vec();
                 let mut reader = io::Cursor::new(bytes);
                 reader.read_u32::<LittleEndian>()?
             };
-            let field_length = data[16];
-            let field_decimal_count = data[17];
-            // We don't really care about the rest
+            // FoxPro and Clipper use the decimal count as the high byte of wide character fields
+            let (field_length, field_decimal_count) = match data[11] {
+                b'C' => (u16::from_le_bytes([data[16], data[17]]), 0),
+                _ => (u16::from(data[16]), data[17])
+            };
+            let (autoincrement_next, autoincrement_step) = match data.get(19..24) {
+                Some(counter) => (u32::from_le_bytes([counter[0], counter[1], counter[2], counter[3]]), counter[4]),
+                None => (0, 0)
+            };
+            // We don't really care about the rest, apart from the Visual FoxPro flags and
+            // autoincrement counter
             Ok(FieldDescriptor {
                 name: field_name,
-                field_type: Arc::new(field_type),
+                field_type,
+                type_code: data[11],
                 data_address: field_address,
                 length: field_length,
-                decimal_count: field_decimal_count
+                decimal_count: field_decimal_count,
+                flags: data[18],
+                autoincrement_next,
+                autoincrement_step
             })
         };
         while !done {
@@ -345,14 +1414,132 @@ impl Database {
         }
         Ok(fields)
     }
-    pub fn parse(path: &str, mut file: impl Read + 'static) -> Result<Database, io::Error> {
+    pub fn parse(path: &str, file: impl Read + Seek + 'static) -> Result<Database, DbaseError> {
+        Self::parse_with_options(path, file, OpenOptions::new())
+    }
+    pub fn parse_with_options(path: &str, file: impl Read + Seek + 'static, options: OpenOptions) -> Result<Database, DbaseError> {
+        Self::parse_from(Some(Path::new(path)), file, options)
+    }
+    /// Parses a database from an already opened file.
+    ///
+    /// Memo files are looked up next to `path_for_memo`, which should be the path the file was
+    /// opened from (the memo file shares its stem). Without it, no memo file is opened.
+    pub fn from_file(file: File, path_for_memo: Option<&Path>) -> Result<Database, DbaseError> {
+        Self::parse_file(file, path_for_memo, OpenOptions::new())
+    }
+    pub(crate) fn parse_file(file: File, path: Option<&Path>, options: OpenOptions) -> Result<Database, DbaseError> {
+        let handle = file.try_clone()?;
+        if options.locking {
+            FileExt::lock_shared(&handle)?;
+        }
+        let mut database = Self::parse_from(path, file, options)?;
+        database.file = Some(handle);
+        Ok(database)
+    }
+    /// Copies the database at `path` and its memo file to temporary siblings, and opens the copy
+    /// for `commit` to move back over the original.
+    pub(crate) fn open_atomic(path: &Path, options: OpenOptions) -> Result<Database, DbaseError> {
+        let temp_path = temp_sibling(path);
+        fs::copy(path, &temp_path)?;
+        let mut options = options;
+        let memo_target = options.memo_path.clone().or_else(|| Self::discover_memo(path));
+        if let Some(memo_target) = &memo_target {
+            let memo_path = temp_sibling(memo_target);
+            fs::copy(memo_target, &memo_path)?;
+            options.memo_path = Some(memo_path);
+        }
+        let file = fs::OpenOptions::new().read(true).write(true).open(&temp_path)?;
+        let mut database = Self::parse_file(file, Some(&temp_path), options)?;
+        database.atomic_copy = Some(AtomicCopy {
+            target: path.to_path_buf(),
+            path: temp_path,
+            memo_path: database.memo_path.clone(),
+            memo_target,
+            committed: false
+        });
+        Ok(database)
+    }
+    /// Moves the changes made to a database opened with `OpenOptions::atomic` over the original
+    /// database and memo file. Dropping the database without committing discards them, and other
+    /// databases have nothing to commit.
+    pub fn commit(mut self) -> Result<(), DbaseError> {
+        let mut copy = match self.atomic_copy.take() {
+            Some(copy) => copy,
+            None => return Ok(())
+        };
+        // Close every handle on the copies before moving them
+        self.descriptor = None;
+        self.memo = None;
+        self.memo_writer = None;
+        if let Some(file) = self.file.take() {
+            file.sync_all()?;
+        }
+        // The memo file only ever grows, so the original database can still read it if the
+        // second rename doesn't happen
+        if let (Some(memo_path), Some(memo_target)) = (&copy.memo_path, &copy.memo_target) {
+            File::open(memo_path)?.sync_all()?;
+            fs::rename(memo_path, memo_target)?;
+        }
+        fs::rename(&copy.path, &copy.target)?;
+        copy.committed = true;
+        Ok(())
+    }
+    /// Finds the memo file sharing the stem of the database at `path`.
+    ///
+    /// Files copied from DOS or Windows often have upper case extensions, so both cases are
+    /// probed, starting with the case of the database's own extension.
+    fn discover_memo(path: &Path) -> Option<PathBuf> {
+        let stem = path.file_stem().and_then(|r| r.to_str()).map(|r| r.to_string()).unwrap_or("".to_string());
+        let dir = path.parent().map(|r| r.to_path_buf()).unwrap_or_default();
+        let upper_case = path.extension().and_then(|r| r.to_str()).is_some_and(|r| r.chars().any(|c| c.is_ascii_uppercase()));
+        let extensions = match upper_case {
+            true => ["DBT", "FPT", "SMT", "dbt", "fpt", "smt"],
+            false => ["dbt", "fpt", "smt", "DBT", "FPT", "SMT"]
+        };
+
+        extensions.iter()
+            .map(|extension| dir.join(format!("{}.{}", stem, extension)))
+            .find(|memo_path| memo_path.is_file())
+    }
+    /// Opens a .dbt (dBASE), .fpt (FoxPro) or .smt (HiPer-Six) memo file.
+    fn open_memo(path: &Path, options: &OpenOptions) -> Result<Box<dyn MemoContainer>, io::Error> {
+        let block_size = options.memo_block_size;
+        let extension = path.extension().and_then(|r| r.to_str()).map(|r| r.to_lowercase());
+        match extension.as_deref() {
+            Some("fpt") => {
+                let mut container = FoxProMemoContainer::open(path)?;
+                if block_size != 0 {
+                    container.fragment_size = block_size;
+                }
+                container.max_memo_size = options.max_memo_size;
+                Ok(Box::new(container))
+            },
+            Some("smt") => {
+                let mut container = SmtMemoContainer::open(path)?;
+                if block_size != 0 {
+                    container.block_size = block_size;
+                }
+                container.max_memo_size = options.max_memo_size;
+                Ok(Box::new(container))
+            },
+            _ => {
+                let mut container = DBaseMemoContainer::open(path)?;
+                if block_size != 0 {
+                    container.block_size = block_size as usize;
+                }
+                container.max_memo_size = options.max_memo_size;
+                container.memo_end = options.memo_end;
+                Ok(Box::new(container))
+            }
+        }
+    }
+    fn parse_from(path: Option<&Path>, mut file: impl Read + Seek + 'static, options: OpenOptions) -> Result<Database, DbaseError> {
         let mut byte_header = [0; 12];
-        let file_path = PathBuf::from(path);
         file.read_exact(&mut byte_header)?;
         let version_byte = byte_header.first().ok_or(io::Error::new(io::ErrorKind::NotFound, "No version descriptor"))?;
         let version = Version::from_byte(&version_byte);
         // This is where things get hilarious
-        let date_modified = parse_date(byte_header[1..4].to_vec())?;
+        let date_modified = parse_date(byte_header[1..4].to_vec(), options.year_pivot)?;
         let num_records = {
             let bytes = byte_header[4..8].to_vec();
             let mut reader = io::Cursor::new(bytes);
@@ -368,47 +1555,65 @@ impl Database {
             let mut reader = io::Cursor::new(bytes);
             reader.read_u16::<LittleEndian>()?
         };
-        {
-            let mut wasted_buffer = [0;20];
-            file.read_exact(&mut wasted_buffer)?;
+        let mut reserved = [0;20];
+        file.read_exact(&mut reserved)?;
+        // Byte 15 of the header; records can't be read without decrypting them first
+        if reserved[3] != 0 {
+            return Err(DbaseError::Encrypted);
+        }
+        // The field descriptors and anything trailing them, up to the first record
+        let size:usize = (header_size as usize).checked_sub(32)
+            .ok_or(io::Error::new(io::ErrorKind::InvalidData, format!("The header size {} is too small", header_size)))?;
+        // Refuse oversized headers before allocating anything for them
+        let trailer_size = match version {
+            Version::VisualFoxPro(_, _) => 1 + 263,
+            _ => 1
         };
-        let size:usize = (header_size - 32 + 1).into();
-        let mut field_buffer = vec![];
-        field_buffer.resize(size, 0);
+        let field_count = size.saturating_sub(trailer_size) / 32;
+        if field_count > options.max_fields {
+            return Err(DbaseError::LimitExceeded { limit: Limit::FieldCount, declared: field_count, max: options.max_fields });
+        }
+        if record_size as usize > options.max_record_size {
+            return Err(DbaseError::LimitExceeded { limit: Limit::RecordSize, declared: record_size as usize, max: options.max_record_size });
+        }
+        let mut field_buffer = vec![0; size];
         file.read_exact(&mut field_buffer)?;
 
-        let fields:Vec<FieldDescriptor> = Self::parse_fields(field_buffer)?;
+        let fields:Vec<FieldDescriptor> = Self::parse_fields(&field_buffer, &version, &options)?;
+
+        // Visual FoxPro tables store the path to their database container (.dbc) right after the
+        // field terminator
+        let database_container = match version {
+            Version::VisualFoxPro(_, _) => {
+                let start = fields.len() * 32 + 1;
+                field_buffer.get(start..).map(|backlink| {
+                    let backlink:Vec<u8> = backlink.iter().take(263).take_while(|r| **r != 0).cloned().collect();
+                    String::from_utf8_lossy(&backlink).trim().to_string()
+                }).filter(|backlink| !backlink.is_empty())
+            },
+            _ => None
+        };
 
         // Do we have a memo file?
-        let stem = file_path.file_stem().and_then(|r| r.to_str()).map(|r| r.to_string()).unwrap_or("".to_string());
-        let mut dir_path:Vec<_> = file_path.components().map(|r| r.as_os_str()).collect();
-        dir_path.pop();
-        let mut dir = PathBuf::new();
-        dir_path.into_iter().for_each(|component| dir.push(component));
-
-        let memo_file:Option<Box<MemoContainer>> = {
-            let mut dbt_pathbuf = dir.clone();
-            dbt_pathbuf.push(format!("{}.dbt", stem));
-            match dbt_pathbuf.is_file() {
-                true => {
-                    Some(Box::new(DBaseMemoContainer::open(dbt_pathbuf)?))
-                },
-                false => {
-                    let mut fpt_pathbuf = dir.clone();
-                    fpt_pathbuf.push(format!("{}.fpt", stem));
-                    match fpt_pathbuf.is_file() {
-                        true => {
-                            Some(Box::new(FoxProMemoContainer::open(fpt_pathbuf)?))
-                        },
-                        false => None
-                    }
-                }
-            }
+        let memo_path = options.memo_path.clone().or_else(|| path.and_then(Self::discover_memo));
+        let memo_file = match &memo_path {
+            Some(memo_path) => Some(Self::open_memo(memo_path, &options)?),
+            None => None
         };
+        // Or a production index? It isn't parsed yet, only noted
+        let mdx_index = path.map(|path| path.with_extension("mdx")).filter(|r| r.is_file());
 
         Ok(Database {
-            path: path.into(),
+            path: path.map(|r| r.to_path_buf()).unwrap_or_default(),
             memo: memo_file,
+            memo_path,
+            memo_writer: None,
+            memo_cache: MemoCache::new(options.memo_cache),
+            mdx_index,
+            file: None,
+            deleted_count: None,
+            include_deleted: false,
+            atomic_copy: None,
             descriptor: Some(Box::new(file)),
             header: Header {
                 version: version,
@@ -416,29 +1621,835 @@ impl Database {
                 record_count: num_records,
                 header_size: header_size,
                 record_size: record_size,
-                fields: fields
+                reserved,
+                charset: CodePage::from_language_driver(reserved[17]).charset(),
+                database_container,
+                columns: Arc::new(fields.iter().map(|field| field.name.clone()).collect()),
+                fields: Rc::new(fields)
+            },
+            options
+        })
+    }
+
+    fn parse_row_with<F>(&mut self, fields: &[FieldDescriptor], bytes: Vec<u8>, mut callback: F) -> Result<(), io::Error>
+        where F: FnMut(&str, FieldValue) {
+        let null_flags = null_flags(fields, &bytes).to_vec();
+        let mut bit = 0;
+        // The first byte of every record is its deletion flag
+        let mut offset = 1;
+        for field in fields {
+            let end = offset + field.length as usize;
+            let mut record_bytes = bytes.get(offset..end)
+                .ok_or(io::Error::new(io::ErrorKind::InvalidData, format!("The record is too short to contain field {}", field.name)))?
+                .to_vec();
+            offset = end;
+            // Variable length fields flag values shorter than the field, whose length is then
+            // stored in the last byte
+            if is_varlength_type(field.type_code) {
+                if writer::flag_is_set(&null_flags, bit) {
+                    let length = record_bytes.last().map_or(0, |r| *r as usize).min(record_bytes.len().saturating_sub(1));
+                    record_bytes.truncate(length);
+                }
+                bit += 1;
+            }
+            let null = field.is_nullable() && writer::flag_is_set(&null_flags, bit);
+            if field.is_nullable() {
+                bit += 1;
+            }
+            if field.is_system() {
+                continue;
+            }
+            let value = match null {
+                true => FieldValue::Null,
+                false => field.field_type.parse(self, record_bytes)?
+            };
+            callback(&field.name, value);
+        }
+        Ok(())
+    }
+
+    /// Streams every record of the database through `visitor`, returning the visitor once done.
+    /// Like iteration, deleted records are skipped unless `include_deleted` is set.
+    ///
+    /// This is the allocation-light alternative to iterating over `Record`s.
+    pub fn visit<V: RecordVisitor>(mut self, mut visitor: V) -> Result<V, io::Error> {
+        let fields = Rc::clone(&self.header.fields);
+        self.seek_to_record(0)?;
+        for _ in 0..self.header.record_count {
+            let bytes = self.read_record_bytes()?;
+            if RecordStatus::from_byte(bytes[0]) == RecordStatus::Deleted && !self.include_deleted {
+                continue;
+            }
+            self.parse_row_with(&fields, bytes, |name, value| visitor.field(name, value))?;
+            visitor.end_record();
+        }
+        Ok(visitor)
+    }
+
+    /// Re-reads the header exactly as it is on disk, from the version byte up to the first record.
+    pub fn raw_header_bytes(&mut self) -> Result<Vec<u8>, DbaseError> {
+        let mut buf = vec![0; self.header.header_size as usize];
+        let descriptor = self.descriptor()?;
+        descriptor.seek(io::SeekFrom::Start(0))?;
+        descriptor.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Iterates over every record, deleted ones included, alongside its deletion status.
+    pub fn records_with_status(self) -> impl Iterator<Item = Result<(RecordStatus, Record), DbaseError>> {
+        let mut database = self;
+        (0..database.header.record_count).map(move |index| database.read_record_with_status(index))
+    }
+
+    /// Reads the record at `index` directly, deleted or not, or `None` past the last record.
+    pub fn record(&mut self, index: u32) -> Result<Option<Record>, DbaseError> {
+        match index < self.header.record_count {
+            true => self.read_record(index).map(Some),
+            false => Ok(None)
+        }
+    }
+
+    /// Decodes text fields with `encoding` instead of the code page declared by the header, for
+    /// files with a wrong or blank language driver.
+    ///
+    ///     let mut database = dbase_parser::open("tests/reference_gis.dbf").unwrap();
+    ///     database.with_encoding(encoding_rs::UTF_8);
+    pub fn with_encoding(&mut self, encoding: &'static Encoding) -> &mut Self {
+        self.header.charset = Charset::Encoding(encoding);
+        self
+    }
+
+    /// Reads memos from `container` instead of the memo file found next to the database, such as
+    /// memos kept in another store or a stub in tests.
+    pub fn with_memo_container(&mut self, container: Box<dyn MemoContainer>) -> &mut Self {
+        self.memo = Some(container);
+        self.memo_cache.clear();
+        self
+    }
+
+    /// Whether iterating over the database also yields the records flagged as deleted, which
+    /// are skipped by default. See `Record::deleted`.
+    pub fn include_deleted(&mut self, include_deleted: bool) -> &mut Self {
+        self.include_deleted = include_deleted;
+        self
+    }
+
+    /// Runs `write` under an exclusive lock when `OpenOptions::locking` is set, going back to the
+    /// shared lock taken when opening afterwards.
+    fn write_locked<F, T>(&mut self, write: F) -> Result<T, DbaseError>
+        where F: FnOnce(&mut Self) -> Result<T, DbaseError> {
+        if !self.options.write || self.file.is_none() {
+            return Err(DbaseError::ReadOnly);
+        }
+        if self.options.locking {
+            self.lock_exclusive()?;
+        }
+        let result = write(self);
+        if self.options.locking {
+            self.lock_shared()?;
+        }
+        result
+    }
+    fn write_at(&mut self, offset: u64, bytes: &[u8]) -> Result<(), io::Error> {
+        let mut file = self.file.as_ref().ok_or_else(|| io::Error::other("The database was not opened from a file"))?;
+        file.seek(io::SeekFrom::Start(offset))?;
+        file.write_all(bytes)
+    }
+    fn record_offset(&self, index: u32) -> u64 {
+        u64::from(self.header.header_size) + u64::from(index) * (self.header.physical_record_size() as u64)
+    }
+    /// Stamps the header with today's date and rewrites its record count.
+    fn write_header_counts(&mut self) -> Result<(), io::Error> {
+        let bytes = self.header_counts(self.header.record_count)?;
+        self.write_at(1, &bytes)
+    }
+    /// Stamps the header with today's date and returns the bytes of the date and `record_count`,
+    /// which follow the version byte.
+    fn header_counts(&mut self, record_count: u32) -> Result<Vec<u8>, io::Error> {
+        self.header.last_update = fields::utc_date(Utc::now().date_naive());
+        let last_update = self.header.last_update;
+        let mut bytes = vec![(last_update.year() - 1900) as u8, last_update.month() as u8, last_update.day() as u8];
+        bytes.write_u32::<LittleEndian>(record_count)?;
+        Ok(bytes)
+    }
+    /// Encodes `values` as an active record laid out for this database, writing its memos to the
+    /// memo file.
+    fn encode_record(&mut self, values: &HashMap<String, FieldValue>) -> Result<Vec<u8>, DbaseError> {
+        self.open_memo_writer()?;
+        let fields = Rc::clone(&self.header.fields);
+        let options = self.encode_options();
+        let mut bytes = vec![b' '];
+        bytes.append(&mut writer::encode_fields(&fields, values, &mut self.memo_writer, options)?);
+        bytes.resize(self.header.physical_record_size(), b' ');
+        Ok(bytes)
+    }
+    fn encode_options(&self) -> writer::EncodeOptions {
+        writer::EncodeOptions {
+            charset: self.header.charset,
+            unmappable: self.options.unmappable,
+            overflow: self.options.overflow,
+            padding: self.options.padding,
+            numeric_fill: self.options.numeric_fill
+        }
+    }
+
+    /// Where the pointers of the memo fields are in a record, with the type of their field.
+    fn memo_fields(&self) -> Vec<(Range<usize>, u8)> {
+        let mut memo_fields = vec![];
+        let mut offset = 1;
+        for field in self.header.fields.iter() {
+            let length = field.length as usize;
+            if is_memo_type(field.type_code) {
+                memo_fields.push((offset..offset + length, field.type_code));
+            }
+            offset += length;
+        }
+        memo_fields
+    }
+    fn is_dbase4(&self) -> bool {
+        matches!(self.header.version, Version::dBASE4Table(_) | Version::dBASE4System(_))
+    }
+
+    /// Opens the memo file for writing, if the database has memo fields and it isn't open yet.
+    fn open_memo_writer(&mut self) -> Result<(), io::Error> {
+        // Blocks freed by a failed write are written over by the next one
+        self.memo_cache.clear();
+        let has_memo = self.header.fields.iter().any(|field| is_memo_type(field.type_code));
+        if has_memo && self.memo_writer.is_none() {
+            if let Some(memo_path) = &self.memo_path {
+                self.memo_writer = Some(writer::open_memo_writer(memo_path, self.is_dbase4(), self.options.memo_block_size)?);
             }
+        }
+        Ok(())
+    }
+
+    /// Appends a record made of `values`, keyed by field name, and updates the record count and
+    /// last update date in the header. Missing and `Null` fields are left blank.
+    ///
+    /// If the record cannot be written, the memos it added to the memo file are freed.
+    ///
+    /// The database must have been opened with `OpenOptions::write`.
+    pub fn append(&mut self, values: &HashMap<String, FieldValue>) -> Result<(), DbaseError> {
+        self.write_locked(|database| {
+            database.open_memo_writer()?;
+            let index = database.header.record_count;
+            writer::with_memo_rollback(database, |database| &mut database.memo_writer, |database| {
+                let bytes = database.encode_record(values)?;
+                database.write_at(database.record_offset(index), &bytes)?;
+                database.write_at(database.record_offset(index + 1), &[0x1a])?;
+                Ok(())
+            })?;
+            database.header.record_count += 1;
+            database.deleted_count = None;
+            database.write_header_counts()?;
+            Ok(())
         })
     }
 
-    pub fn get_memo(&mut self, data: Vec<u8>) -> Option<Vec<u8>> {
-        self.memo.as_mut().and_then(|container| {
-            container.memo(data).ok()
+    /// Appends every record of `records`, deleted ones flagged as such, and returns how many were
+    /// appended.
+    ///
+    /// Records are written in large chunks with a single header update at the end, which is much
+    /// faster than calling `append` for each of them. If a record cannot be written, none of them
+    /// are kept, memos included.
+    ///
+    /// The database must have been opened with `OpenOptions::write`.
+    pub fn append_all<I: IntoIterator<Item = Record>>(&mut self, records: I) -> Result<u32, DbaseError> {
+        self.write_locked(|database| {
+            let start = database.header.record_count;
+            database.open_memo_writer()?;
+            let written = writer::with_memo_rollback(database, |database| &mut database.memo_writer, |database| {
+                database.write_records_from(start, records)
+            });
+            match written {
+                Ok(count) => {
+                    database.header.record_count += count;
+                    database.write_at(database.record_offset(start + count), &[0x1a])?;
+                    database.deleted_count = None;
+                    database.write_header_counts()?;
+                    Ok(count)
+                },
+                Err(e) => {
+                    // Drop whatever was written past the last record
+                    database.write_at(database.record_offset(start), &[0x1a])?;
+                    if let Some(file) = &database.file {
+                        file.set_len(database.record_offset(start) + 1)?;
+                    }
+                    Err(e)
+                }
+            }
+        })
+    }
+
+    /// Writes `records` from the record at `index` on, without touching the header.
+    fn write_records_from<I: IntoIterator<Item = Record>>(&mut self, index: u32, records: I) -> Result<u32, DbaseError> {
+        let mut buffer = Vec::with_capacity(APPEND_BUFFER_SIZE);
+        let mut written = 0;
+        let mut count = 0;
+        for record in records {
+            let mut bytes = self.encode_record(&record.fields)?;
+            if record.deleted {
+                bytes[0] = 0x2a;
+            }
+            buffer.append(&mut bytes);
+            count += 1;
+            if buffer.len() >= APPEND_BUFFER_SIZE {
+                self.write_at(self.record_offset(index + written), &buffer)?;
+                buffer.clear();
+                written = count;
+            }
+        }
+        self.write_at(self.record_offset(index + written), &buffer)?;
+        Ok(count)
+    }
+
+    /// Rewrites the record at `index` with `values`, keyed by field name. Missing and `Null`
+    /// fields are left blank, while the deletion flag of the record is kept.
+    ///
+    /// The database must have been opened with `OpenOptions::write`.
+    pub fn update_record(&mut self, index: u32, values: &HashMap<String, FieldValue>) -> Result<(), DbaseError> {
+        self.check_index(index)?;
+        self.write_locked(|database| {
+            database.open_memo_writer()?;
+            writer::with_memo_rollback(database, |database| &mut database.memo_writer, |database| {
+                let mut bytes = database.encode_record(values)?;
+                database.seek_to_record(index)?;
+                database.descriptor()?.read_exact(&mut bytes[0..1])?;
+                database.write_at(database.record_offset(index), &bytes)?;
+                Ok(())
+            })
+        })
+    }
+
+    /// Overwrites the field `field_name` of the record at `index` with `value`, leaving the rest of
+    /// the record untouched. This is much cheaper than `update_record` for a single field of a
+    /// large record.
+    ///
+    /// The database must have been opened with `OpenOptions::write`.
+    pub fn patch_field(&mut self, index: u32, field_name: &str, value: &FieldValue) -> Result<(), DbaseError> {
+        self.check_index(index)?;
+        let fields = Rc::clone(&self.header.fields);
+        let position = fields.iter().position(|field| field.name == field_name)
+            .ok_or_else(|| DbaseError::FieldNotFound(field_name.to_string()))?;
+        let field_offset = |position: usize| 1 + fields[..position].iter().map(|field| u64::from(field.length)).sum::<u64>();
+        let field = &fields[position];
+        self.write_locked(|database| {
+            database.open_memo_writer()?;
+            let options = database.encode_options();
+            let offset = database.record_offset(index);
+            writer::with_memo_rollback(database, |database| &mut database.memo_writer, |database| {
+                let bytes = writer::encode_field(field, Some(value), &mut database.memo_writer, options)?;
+                database.write_at(offset + field_offset(position), &bytes)?;
+                Ok(())
+            })?;
+            // Nullable and variable length fields have their own bits in the _NullFlags column,
+            // in field order
+            let null_flags = fields.iter().position(|field| field.type_code == writer::NULL_FLAGS_TYPE);
+            if let (true, Some(null_flags)) = (writer::flag_count(field) > 0, null_flags) {
+                let first_bit:usize = fields[..position].iter().map(writer::flag_count).sum();
+                database.seek_to_record(index)?;
+                let mut record = database.read_record_bytes()?;
+                let flags_offset = field_offset(null_flags) as usize;
+                let flags_length = fields[null_flags].length as usize;
+                let flags = &mut record[flags_offset..flags_offset + flags_length];
+                for (i, set) in writer::field_flags(field, Some(value), options)?.into_iter().enumerate() {
+                    let bit = first_bit + i;
+                    if let Some(byte) = flags.get_mut(bit / 8) {
+                        match set {
+                            true => *byte |= 1 << (bit % 8),
+                            false => *byte &= !(1 << (bit % 8))
+                        }
+                    }
+                }
+                database.write_at(offset + flags_offset as u64, flags)?;
+            }
+            Ok(())
         })
     }
+
+    fn check_index(&self, index: u32) -> Result<(), DbaseError> {
+        let record_count = self.header.record_count;
+        match index < record_count {
+            true => Ok(()),
+            false => Err(DbaseError::InvalidRange { start: index, end: index.saturating_add(1), record_count })
+        }
+    }
+    fn write_status(&mut self, index: u32, status: RecordStatus) -> Result<(), DbaseError> {
+        self.check_index(index)?;
+        let flag = match status {
+            RecordStatus::Active => b' ',
+            RecordStatus::Deleted => 0x2a
+        };
+        self.write_locked(|database| {
+            database.write_at(database.record_offset(index), &[flag])?;
+            database.deleted_count = None;
+            Ok(())
+        })
+    }
+
+    /// Flags the record at `index` as deleted, like the dBASE `DELETE` command. The record stays
+    /// in the file until it is packed.
+    ///
+    /// The database must have been opened with `OpenOptions::write`.
+    pub fn mark_deleted(&mut self, index: u32) -> Result<(), DbaseError> {
+        self.write_status(index, RecordStatus::Deleted)
+    }
+
+    /// Clears the deletion flag of the record at `index`, like the dBASE `RECALL` command.
+    ///
+    /// The database must have been opened with `OpenOptions::write`.
+    pub fn undelete(&mut self, index: u32) -> Result<(), DbaseError> {
+        self.write_status(index, RecordStatus::Active)
+    }
+
+    /// Removes the records flagged as deleted from the file, like the dBASE `PACK` command, and
+    /// returns how many were removed. Active records keep their order but not their index.
+    ///
+    /// The memo file is rebuilt along with the records, unless it is a HiPer-Six .smt file, which
+    /// is left untouched: the memos of the records kept are copied to a new memo file, so the
+    /// blocks of deleted records and of memos written over are reclaimed.
+    ///
+    /// The records kept are written to a temporary copy of the database, and both copies are only
+    /// moved over the originals once complete, so a failure before then leaves the database as it
+    /// was. The two moves are not atomic together: a crash between them leaves the old records
+    /// pointing into the new memo file. The database must have been opened with
+    /// `OpenOptions::write`.
+    pub fn pack(&mut self) -> Result<u32, DbaseError> {
+        self.write_locked(|database| {
+            let rebuilt = database.rebuild_memos()?;
+            let memo = rebuilt.as_ref().map(|rebuilt| rebuilt.path.clone());
+            let path = temp_sibling(&database.path);
+            let pointers = rebuilt.map(|rebuilt| rebuilt.pointers).unwrap_or_default();
+            let kept = database.write_packed(&path, &pointers);
+            if kept.is_err() {
+                let _ = fs::remove_file(&path);
+                if let Some(memo) = &memo {
+                    let _ = fs::remove_file(memo);
+                }
+            }
+            let kept = kept?;
+            let removed = database.header.record_count - kept;
+            database.swap_packed_files(&path, memo.as_deref())?;
+            database.header.record_count = kept;
+            database.deleted_count = Some(0);
+            Ok(removed)
+        })
+    }
+
+    /// Writes the header and the active records to a new file at `path`, with the memo pointers
+    /// of `pointers` patched in, and returns how many records were kept. See `pack`.
+    fn write_packed(&mut self, path: &Path, pointers: &[(u32, usize, Vec<u8>)]) -> Result<u32, DbaseError> {
+        let mut file = io::BufWriter::new(File::create(path)?);
+        file.write_all(&self.raw_header_bytes()?)?;
+        let mut pointers = pointers.iter().peekable();
+        let mut kept = 0;
+        for index in 0..self.header.record_count {
+            self.seek_to_record(index)?;
+            let mut bytes = self.read_record_bytes()?;
+            if RecordStatus::from_byte(bytes[0]) == RecordStatus::Deleted {
+                continue;
+            }
+            while let Some((_, offset, pointer)) = pointers.next_if(|(record, _, _)| *record == index) {
+                bytes[*offset..*offset + pointer.len()].copy_from_slice(pointer);
+            }
+            file.write_all(&bytes)?;
+            kept += 1;
+        }
+        file.write_all(&[0x1a])?;
+        file.seek(io::SeekFrom::Start(1))?;
+        file.write_all(&self.header_counts(kept)?)?;
+        file.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        Ok(kept)
+    }
+
+    /// Moves the database at `path` and memo file at `memo`, written by `pack`, over the current
+    /// ones and reopens them, or removes them and reopens the current ones if they cannot be moved.
+    fn swap_packed_files(&mut self, path: &Path, memo: Option<&Path>) -> Result<(), DbaseError> {
+        // Close every handle on the files before moving over them
+        let had_memo = self.memo.is_some();
+        self.descriptor = None;
+        self.memo = None;
+        self.memo_writer = None;
+        self.memo_cache.clear();
+        self.file = None;
+        // As in `commit`, the memo file goes first
+        let renamed = match (memo, &self.memo_path) {
+            (Some(memo), Some(memo_path)) => fs::rename(memo, memo_path),
+            _ => Ok(())
+        }.and_then(|_| fs::rename(path, &self.path));
+        if renamed.is_err() {
+            let _ = fs::remove_file(path);
+            if let Some(memo) = memo {
+                let _ = fs::remove_file(memo);
+            }
+        }
+        let file = fs::OpenOptions::new().read(true).write(true).open(&self.path)?;
+        self.descriptor = Some(Box::new(file.try_clone()?));
+        self.file = Some(file);
+        if let (true, Some(memo_path)) = (had_memo, &self.memo_path) {
+            self.memo = Some(Self::open_memo(memo_path, &self.options)?);
+        }
+        Ok(renamed?)
+    }
+
+    /// Copies the memos of the active records to a new memo file next to the current one, see
+    /// `pack`. Returns `None` if the database has no memo file, or a .smt one, which cannot be
+    /// written and is kept as it is.
+    fn rebuild_memos(&mut self) -> Result<Option<RebuiltMemos>, DbaseError> {
+        let memo_fields = self.memo_fields();
+        let source = match (&self.memo_path, memo_fields.is_empty()) {
+            (Some(memo_path), false) => memo_path.clone(),
+            _ => return Ok(None)
+        };
+        if source.extension().and_then(|r| r.to_str()).is_some_and(|r| r.eq_ignore_ascii_case("smt")) {
+            return Ok(None);
+        }
+        let path = temp_sibling(&source);
+        let pointers = self.copy_memos(&source, &path, &memo_fields);
+        if pointers.is_err() {
+            let _ = fs::remove_file(&path);
+        }
+        Ok(Some(RebuiltMemos { path, pointers: pointers? }))
+    }
+    fn copy_memos(&mut self, source: &Path, path: &Path, memo_fields: &[(Range<usize>, u8)]) -> Result<Vec<(u32, usize, Vec<u8>)>, DbaseError> {
+        let mut writer = writer::rebuild_memo_writer(source, path, self.is_dbase4(), self.options.memo_block_size)?;
+        let mut pointers = vec![];
+        for index in 0..self.header.record_count {
+            self.seek_to_record(index)?;
+            let record = self.read_record_bytes()?;
+            if RecordStatus::from_byte(record[0]) == RecordStatus::Deleted {
+                continue;
+            }
+            for (range, type_code) in memo_fields {
+                let pointer = match record.get(range.clone()) {
+                    Some(pointer) => pointer.to_vec(),
+                    None => continue
+                };
+                if memo_block(&pointer)?.is_none() {
+                    continue;
+                }
+                let (memo, memo_type) = match self.read_memo(pointer) {
+                    Some(memo) => memo?,
+                    None => continue
+                };
+                let is_text = match memo_type {
+                    Some(memo_type) => memo_type == MemoType::Text,
+                    None => *type_code == b'M'
+                };
+                let block = writer.write_memo(&memo, is_text)?;
+                pointers.push((index, range.start, writer::memo_pointer(block, range.len())));
+            }
+        }
+        writer.flush()?;
+        Ok(pointers)
+    }
+
+    /// Counts the records flagged as deleted, reading only the status byte of each record.
+    ///
+    /// The count is cached after the first call; `header.record_count - deleted_count` gives the
+    /// number of active records.
+    pub fn deleted_count(&mut self) -> Result<u32, DbaseError> {
+        if let Some(count) = self.deleted_count {
+            return Ok(count);
+        }
+        let mut count = 0;
+        let mut flag = [0; 1];
+        for i in 0..self.header.record_count {
+            self.seek_to_record(i)?;
+            self.descriptor()?.read_exact(&mut flag)?;
+            if RecordStatus::from_byte(flag[0]) == RecordStatus::Deleted {
+                count += 1;
+            }
+        }
+        self.deleted_count = Some(count);
+        Ok(count)
+    }
+
+    /// Appends every record of the database to `dest`, deleted ones included.
+    ///
+    /// When both databases share the same field layout, records are copied byte for byte without
+    /// being parsed. Otherwise, or when the records hold pointers into a memo file, each record is
+    /// parsed and written again field by field.
+    pub fn copy_records_to<W: Write + Seek>(mut self, dest: &mut DbfWriter<W>) -> Result<(), DbaseError> {
+        let same_layout = self.header.physical_record_size() == dest.record_size()
+            && self.header.fields.len() == dest.fields().len()
+            && self.header.fields.iter().zip(dest.fields()).all(|(source, dest)| {
+                source.name == dest.name
+                    && source.type_code == dest.type_code
+                    && source.length == dest.length
+                    && source.decimal_count == dest.decimal_count
+                    && !is_memo_type(source.type_code)
+            });
+        if same_layout {
+            self.seek_to_record(0)?;
+            for _ in 0..self.header.record_count {
+                let bytes = self.read_record_bytes()?;
+                dest.write_raw_record(&bytes)?;
+            }
+        } else {
+            for i in 0..self.header.record_count {
+                let (status, record) = self.read_record_with_status(i)?;
+                let mut bytes = dest.encode_record(&record.fields)?;
+                if status == RecordStatus::Deleted {
+                    bytes[0] = 0x2a;
+                }
+                dest.write_raw_record(&bytes)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the database to a new one at `path`, reshaping its fields on the way, and returns the
+    /// number of records copied.
+    ///
+    /// `transform` is called once for each field of this database, in order, to keep, drop, rename
+    /// or convert it. Deleted records are copied with their deletion flag.
+    ///
+    ///     use dbase_parser::open;
+    ///     use dbase_parser::header::FieldTransform;
+    ///
+    ///     let path = std::env::temp_dir().join("only_names.dbf");
+    ///     let db = open("tests/five_records.dbf").unwrap();
+    ///     db.copy_to(path.to_str().unwrap(), |field| match field.name() {
+    ///         "NAME" => FieldTransform::rename(field, "FULLNAME").unwrap(),
+    ///         _ => FieldTransform::Drop
+    ///     }).unwrap();
+    pub fn copy_to<F>(mut self, path: &str, mut transform: F) -> Result<u32, DbaseError>
+        where F: FnMut(&FieldDescriptor) -> FieldTransform {
+        let mut fields = vec![];
+        // The source field, and how to convert its values
+        let mut copies = vec![];
+        for field in self.header.fields.iter() {
+            match transform(field) {
+                FieldTransform::Keep => {
+                    fields.push(field.clone());
+                    copies.push((field.name.clone(), None));
+                },
+                FieldTransform::Drop => {},
+                FieldTransform::Convert(descriptor, convert) => {
+                    fields.push(descriptor);
+                    copies.push((field.name.clone(), Some(convert)));
+                }
+            }
+        }
+        let mut dest = DbfWriter::create(path, fields)?;
+        for i in 0..self.header.record_count {
+            let (status, mut record) = self.read_record_with_status(i)?;
+            let mut values = HashMap::new();
+            for ((source, convert), field) in copies.iter().zip(dest.fields()) {
+                let value = record.fields.remove(source).unwrap_or(FieldValue::Null);
+                let value = match convert {
+                    Some(convert) => convert(value)?,
+                    None => value
+                };
+                values.insert(field.name.clone(), value);
+            }
+            let mut bytes = dest.encode_record(&values)?;
+            if status == RecordStatus::Deleted {
+                bytes[0] = 0x2a;
+            }
+            dest.write_raw_record(&bytes)?;
+        }
+        dest.finish()?;
+        Ok(self.header.record_count)
+    }
+
+    /// Writes the database to `sink` exactly as it is stored: the header with its reserved bytes
+    /// and padding, every record, deleted ones included, and whatever follows the last record,
+    /// such as the end of file marker. Reading a database and writing it back this way gives an
+    /// identical file.
+    ///
+    /// Memo pointers are copied as they are, so the memo file can be copied alongside unchanged.
+    pub fn write_to<W: Write>(&mut self, mut sink: W) -> Result<(), DbaseError> {
+        let mut header = vec![0; self.header.header_size as usize];
+        let descriptor = self.descriptor()?;
+        descriptor.seek(io::SeekFrom::Start(0))?;
+        descriptor.read_exact(&mut header)?;
+        sink.write_all(&header)?;
+        self.seek_to_record(0)?;
+        for _ in 0..self.header.record_count {
+            let bytes = self.read_record_bytes()?;
+            sink.write_all(&bytes)?;
+        }
+        let mut trailer = vec![];
+        self.descriptor()?.read_to_end(&mut trailer)?;
+        sink.write_all(&trailer)?;
+        sink.flush()?;
+        Ok(())
+    }
+
+    /// Reads every record into a map keyed by the value of `field`, for lookup tables.
+    ///
+    /// When several records share a key, the last one read wins.
+    pub fn index_by(&mut self, field: &str) -> Result<HashMap<FieldValue, Record>, DbaseError> {
+        if self.header.field_index(field).is_none() {
+            return Err(DbaseError::FieldNotFound(field.to_string()));
+        }
+        let mut index = HashMap::new();
+        for i in 0..self.header.record_count {
+            let record = self.read_record(i)?;
+            if let Some(key) = record.get(field).cloned() {
+                index.insert(key, record);
+            }
+        }
+        Ok(index)
+    }
+
+    /// Iterates over the records from `start` (inclusive) to `end` (exclusive), seeking directly
+    /// to the first one.
+    ///
+    /// If the range is not within `0..=record_count`, the iterator yields a single
+    /// `DbaseError::InvalidRange`.
+    pub fn records_range(&mut self, start: u32, end: u32) -> impl Iterator<Item = Result<Record, DbaseError>> + '_ {
+        let record_count = self.header.record_count;
+        let (error, range) = match start <= end && end <= record_count {
+            true => (None, start..end),
+            false => (Some(Err(DbaseError::InvalidRange { start, end, record_count })), 0..0)
+        };
+        error.into_iter().chain(range.map(move |index| self.read_record(index)))
+    }
+
+    pub fn get_memo(&mut self, data: Vec<u8>) -> Option<Vec<u8>> {
+        self.read_memo(data).and_then(|memo| memo.ok()).map(|(memo, _)| memo)
+    }
+    /// Reads the memo of a field left unread by `OpenOptions::lazy_memos`, as the field would
+    /// have been read otherwise.
+    pub fn resolve_memo(&mut self, handle: &fields::MemoHandle) -> Result<FieldValue, DbaseError> {
+        Ok(fields::read_memo_value(self, handle.pointer().to_vec(), handle.is_text())?)
+    }
+    /// Streams the memo of a field left unread by `OpenOptions::lazy_memos`, so a large memo can
+    /// be copied elsewhere without holding it in memory. Text memos are not decoded.
+    ///
+    /// Visual FoxPro and dBASE IV memos are streamed from the memo file, while dBASE III memos,
+    /// whose length is only known once their end is found, are read whole first. Without a memo
+    /// file, the pointer itself is read.
+    pub fn memo_reader(&mut self, handle: &fields::MemoHandle) -> Result<Box<dyn Read + '_>, DbaseError> {
+        match self.memo.as_mut() {
+            Some(container) => Ok(container.memo_reader(handle.pointer().to_vec())?),
+            None => Ok(Box::new(io::Cursor::new(handle.pointer().to_vec())))
+        }
+    }
+    /// Reads the field `field_name` of the record at `index`, memo included, without reading the
+    /// memos of its other fields. Along with `OpenOptions::lazy_memos`, this lets a scan load only
+    /// the memos it needs.
+    pub fn memo_for(&mut self, index: u32, field_name: &str) -> Result<FieldValue, DbaseError> {
+        self.check_index(index)?;
+        let lazy_memos = self.options.lazy_memos;
+        self.options.lazy_memos = true;
+        let record = self.read_record(index);
+        self.options.lazy_memos = lazy_memos;
+        match record?.fields.remove(field_name) {
+            Some(FieldValue::MemoRef(handle)) => self.resolve_memo(&handle),
+            Some(value) => Ok(value),
+            None => Err(DbaseError::FieldNotFound(field_name.to_string()))
+        }
+    }
+    /// Checks the memo file against the memo pointers of every record, deleted ones included:
+    /// pointers must point to memos which can be read, memos must not overlap, every block up to
+    /// the next free one must belong to a memo and the header must not give a next free block
+    /// which memos still use. Problems are listed rather than raised, so a whole file can be
+    /// checked at once, and a database without a memo file has none.
+    pub fn verify_memos(&mut self) -> Result<Vec<MemoProblem>, DbaseError> {
+        if self.memo.is_none() {
+            return Ok(vec![]);
+        }
+        let memo_fields = self.memo_fields();
+        let mut pointers = vec![];
+        for index in 0..self.header.record_count {
+            self.seek_to_record(index)?;
+            let record = self.read_record_bytes()?;
+            pointers.extend(memo_fields.iter().filter_map(|(range, _)| record.get(range.clone())).map(<[u8]>::to_vec));
+        }
+        match self.memo.as_mut() {
+            Some(container) => Ok(container.verify(&pointers)?),
+            None => Ok(vec![])
+        }
+    }
+    /// Reports the block size of the memo file, how many blocks it holds and the next free block
+    /// its header gives, or `None` if the database has no memo file. Once the memo file has been
+    /// checked with `verify_memos`, how many blocks memos take up and how many are free are
+    /// reported as well, to estimate what `pack` would reclaim. Memos written since are not
+    /// accounted for.
+    pub fn memo_stats(&mut self) -> Result<Option<MemoStats>, DbaseError> {
+        match self.memo.as_mut() {
+            Some(container) => Ok(container.stats()?),
+            None => Ok(None)
+        }
+    }
+    /// Follows a memo pointer, or returns `None` if the database has no memo file.
+    pub(crate) fn read_memo(&mut self, data: Vec<u8>) -> Option<Result<TypedMemo, io::Error>> {
+        let container = self.memo.as_mut()?;
+        if let Some(memo) = self.memo_cache.get(&data) {
+            return Some(Ok(memo));
+        }
+        let memo = container.typed_memo(data.clone());
+        if let Ok(memo) = &memo {
+            self.memo_cache.insert(data, memo.clone());
+        }
+        Some(memo)
+    }
     pub fn new_at(s: &str) -> Self {
         Database {
             path: PathBuf::from(s),
             memo: None,
+            memo_path: None,
+            memo_writer: None,
+            memo_cache: MemoCache::new(0),
+            mdx_index: None,
+            file: None,
+            deleted_count: None,
+            include_deleted: false,
+            atomic_copy: None,
             descriptor: None,
             header: Header {
                 version: Version::Unknown,
-                last_update: Utc::now().date(),
+                last_update: fields::utc_date(Utc::now().date_naive()),
                 record_count: 0,
                 header_size: 0,
                 record_size: 0,
-                fields: vec![]
-            }
+                reserved: [0; 20],
+                charset: Charset::Encoding(encoding_rs::WINDOWS_1252),
+                database_container: None,
+                fields: Rc::new(vec![]),
+                columns: Arc::new(vec![])
+            },
+            options: OpenOptions::new()
         }
     }
-}
\ No newline at end of file
+    fn lock_file(&self) -> Result<&File, io::Error> {
+        self.file.as_ref().ok_or_else(|| io::Error::other("The database was not opened from a file and cannot be locked"))
+    }
+    /// Takes an advisory shared lock on the database file, waiting for any exclusive lock to be
+    /// released. Other processes can still read, but not take an exclusive lock.
+    pub fn lock_shared(&self) -> Result<(), DbaseError> {
+        Ok(FileExt::lock_shared(self.lock_file()?)?)
+    }
+    /// Takes an advisory exclusive lock on the database file, waiting for any other lock to be
+    /// released.
+    pub fn lock_exclusive(&self) -> Result<(), DbaseError> {
+        Ok(FileExt::lock_exclusive(self.lock_file()?)?)
+    }
+    /// Takes an advisory exclusive lock on the database file, failing with
+    /// `io::ErrorKind::WouldBlock` if another lock is held.
+    pub fn try_lock_exclusive(&self) -> Result<(), DbaseError> {
+        Ok(FileExt::try_lock_exclusive(self.lock_file()?)?)
+    }
+    /// Releases the lock held on the database file.
+    pub fn unlock(&self) -> Result<(), DbaseError> {
+        Ok(FileExt::unlock(self.lock_file()?)?)
+    }
+    /// The path of the .mdx production index found next to the database, if any.
+    pub fn mdx_index_path(&self) -> Option<&Path> {
+        self.mdx_index.as_deref()
+    }
+    pub fn options(&self) -> &OpenOptions {
+        &self.options
+    }
+}
+
+#[test]
+fn dbase_memo_blank_pointer_is_empty() {
+    let mut container = DBaseMemoContainer::open("tests/reference_dbase.dbt").unwrap();
+    assert_eq!(container.memo(b"          ".to_vec()).unwrap(), Vec::<u8>::new());
+    assert_eq!(container.memo(b"         0".to_vec()).unwrap(), Vec::<u8>::new());
+}
@@ -1,14 +1,42 @@
 // #![feature(slicing_syntax)]
 extern crate chrono;
 extern crate byteorder;
+extern crate fs2;
+extern crate encoding_rs;
+#[cfg(feature = "serde")]
+extern crate serde_crate;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+#[cfg(feature = "derive")]
+extern crate dbase_parser_derive;
 
 pub mod header;
 pub mod fields;
+pub mod options;
+pub mod error;
+pub mod writer;
+pub mod csv;
+pub mod codepage;
+#[cfg(feature = "serde")]
+pub mod serialize;
 
-use std::io;
 pub use fields::FieldValue;
+pub use options::OpenOptions;
+pub use error::DbaseError;
+pub use codepage::{CodePage, Unmappable};
+pub use csv::{from_csv, CsvOptions};
+#[cfg(feature = "serde")]
+pub use serialize::from_serialize;
+pub use writer::{DbfWriter, FieldData, NumericFill, Overflow, Padding, WritableRecord};
+#[cfg(feature = "derive")]
+pub use dbase_parser_derive::WritableRecord;
+pub use encoding_rs::Encoding;
 
-pub fn open(path: &str) -> Result<header::Database, io::Error> {
-    let file = std::fs::File::open(path)?;
-    header::Database::parse(path, file)
+pub fn open(path: &str) -> Result<header::Database, DbaseError> {
+    OpenOptions::new().open(path)
+}
+
+/// Creates a new database at `path` with the given fields, see `DbfWriter`.
+pub fn create(path: &str, fields: Vec<header::FieldDescriptor>) -> Result<DbfWriter, DbaseError> {
+    DbfWriter::create(path, fields)
 }
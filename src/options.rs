@@ -0,0 +1,268 @@
+use super::header::{Database, MemoEnd};
+use super::error::DbaseError;
+use super::fields::FieldType;
+use super::writer::{NumericFill, Overflow, Padding};
+use super::codepage::Unmappable;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Options and flags which can be used to configure how a database is opened and parsed.
+///
+/// This follows the same builder pattern as `std::fs::OpenOptions`:
+///
+///     use dbase_parser::OpenOptions;
+///
+///     let database = OpenOptions::new().open("tests/reference_gis.dbf");
+#[derive(Debug, Clone)]
+pub struct OpenOptions {
+    pub(crate) numeric_as_decimal: bool,
+    pub(crate) implied_decimal: bool,
+    pub(crate) numeric_as_integer: bool,
+    pub(crate) lazy_memos: bool,
+    pub(crate) memo_block_size: u32,
+    pub(crate) memo_cache: usize,
+    pub(crate) memo_path: Option<PathBuf>,
+    pub(crate) field_types: HashMap<u8, Arc<Box<dyn FieldType>>>,
+    pub(crate) allow_unknown_field_types: bool,
+    pub(crate) year_pivot: u8,
+    pub(crate) max_fields: usize,
+    pub(crate) max_record_size: usize,
+    pub(crate) max_memo_size: usize,
+    pub(crate) memo_end: MemoEnd,
+    pub(crate) locking: bool,
+    pub(crate) write: bool,
+    pub(crate) atomic: bool,
+    pub(crate) overflow: Overflow,
+    pub(crate) padding: Padding,
+    pub(crate) numeric_fill: NumericFill,
+    pub(crate) unmappable: Unmappable
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        OpenOptions {
+            numeric_as_decimal: false,
+            implied_decimal: false,
+            numeric_as_integer: false,
+            lazy_memos: false,
+            memo_block_size: 0,
+            memo_cache: 0,
+            memo_path: None,
+            field_types: HashMap::new(),
+            allow_unknown_field_types: false,
+            year_pivot: 0,
+            max_fields: 4096,
+            max_record_size: 65535,
+            max_memo_size: usize::MAX,
+            memo_end: MemoEnd::FirstMarker,
+            locking: false,
+            write: false,
+            atomic: false,
+            overflow: Overflow::Error,
+            padding: Padding::Right,
+            numeric_fill: NumericFill::Blank,
+            unmappable: Unmappable::Error
+        }
+    }
+}
+
+impl OpenOptions {
+    pub fn new() -> Self {
+        OpenOptions::default()
+    }
+
+    /// Parse numeric (`N`/`F`) and currency (`Y`) fields into `FieldValue::Decimal` instead of
+    /// `FieldValue::Numeric`, `FieldValue::Float` and `FieldValue::Currency`.
+    ///
+    /// The decimal is built from the ASCII content of the field and its declared decimal count,
+    /// so no precision is lost to a floating point intermediary.
+    #[cfg(feature = "rust_decimal")]
+    pub fn numeric_as_decimal(&mut self, numeric_as_decimal: bool) -> &mut Self {
+        self.numeric_as_decimal = numeric_as_decimal;
+        self
+    }
+
+    /// Parse numeric (`N`/`F`) fields without decimals into `FieldValue::Integer` rather than
+    /// `FieldValue::Numeric`, so identifiers don't read as `1.0`.
+    ///
+    /// `FieldValue::Integer` holds an `i32`: values outside -2147483648 to 2147483647, such as
+    /// 11-digit identifiers, are still read as `FieldValue::Numeric`, so match on both when the
+    /// field may hold them.
+    pub fn numeric_as_integer(&mut self, numeric_as_integer: bool) -> &mut Self {
+        self.numeric_as_integer = numeric_as_integer;
+        self
+    }
+
+    /// Read numeric fields without a decimal point as if it sat before their last `decimal_count`
+    /// digits, so `12345` in a field with 2 decimals reads as `123.45`.
+    ///
+    /// Some legacy exporters omit the decimal point and rely on the field's declared decimal
+    /// count instead. Fields which do contain a decimal point are parsed as usual.
+    pub fn implied_decimal(&mut self, implied_decimal: bool) -> &mut Self {
+        self.implied_decimal = implied_decimal;
+        self
+    }
+
+    /// Leave memo, general and picture fields unread as `FieldValue::MemoRef`, to be read on
+    /// demand with `Database::resolve_memo`, so scanning the other fields reads no memo file.
+    pub fn lazy_memos(&mut self, lazy_memos: bool) -> &mut Self {
+        self.lazy_memos = lazy_memos;
+        self
+    }
+
+    /// Read memos from the file at `memo_path`, instead of looking for a `.dbt`, `.fpt` or `.smt`
+    /// file sharing the stem of the database, for memo files kept elsewhere or named differently.
+    pub fn memo_path<P: AsRef<Path>>(&mut self, memo_path: P) -> &mut Self {
+        self.memo_path = Some(memo_path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Read and write the memo file in blocks of `memo_block_size` bytes, whatever its header
+    /// declares.
+    ///
+    /// Some writers store 0 or a bogus block size in the memo header. A block size of 0 is
+    /// already read as the default of the format (512 bytes for .dbt files, 64 for .fpt files),
+    /// but other values can only be corrected by the caller. 0, the default, trusts the header.
+    pub fn memo_block_size(&mut self, memo_block_size: u32) -> &mut Self {
+        self.memo_block_size = memo_block_size;
+        self
+    }
+
+    /// Sets where the memos of dBASE III memo files end, `MemoEnd::FirstMarker` by default.
+    ///
+    /// dBASE III memos have no length and end with 0x1A bytes, which binary memos may also hold.
+    /// `MemoEnd::DoubleMarker` reads them through to the 0x1A 0x1A pair dBASE III writes instead.
+    pub fn memo_end(&mut self, memo_end: MemoEnd) -> &mut Self {
+        self.memo_end = memo_end;
+        self
+    }
+
+    /// Keep the last `memo_cache` memos read in memory, so records sharing a memo, or reading it
+    /// again, don't seek back into the memo file. 0, the default, caches nothing.
+    ///
+    /// The least recently read memo makes way for a new one once the cache is full.
+    pub fn memo_cache(&mut self, memo_cache: usize) -> &mut Self {
+        self.memo_cache = memo_cache;
+        self
+    }
+
+    /// Parse fields of type `type_code` with `field_type`.
+    ///
+    /// Field types are resolved in order: registered parsers first, then the built-in types,
+    /// then the unknown type fallback (see `allow_unknown_field_types`). A registered parser
+    /// therefore also overrides a built-in type using the same byte.
+    pub fn register_field_type(&mut self, type_code: u8, field_type: Box<dyn FieldType>) -> &mut Self {
+        self.field_types.insert(type_code, Arc::new(field_type));
+        self
+    }
+
+    /// Pass fields of an unknown type through as `FieldValue::Unknown` instead of failing to open
+    /// the database.
+    pub fn allow_unknown_field_types(&mut self, allow_unknown_field_types: bool) -> &mut Self {
+        self.allow_unknown_field_types = allow_unknown_field_types;
+        self
+    }
+
+    /// Treat header years below `year_pivot` as belonging to the 2000s.
+    ///
+    /// The last update date in the header stores its year as a single byte. dBASE counts it from
+    /// 1900 (so 2019 is stored as 119), which is the default pivot of 0. Some writers store a two
+    /// digit year instead; with a pivot of 50, a stored 19 reads as 2019 while 95 reads as 1995.
+    ///
+    /// This only affects the header: `D` fields store a full four digit year.
+    pub fn year_pivot(&mut self, year_pivot: u8) -> &mut Self {
+        self.year_pivot = year_pivot;
+        self
+    }
+
+    /// Refuse databases whose header declares more than `max_fields` fields (4096 by default).
+    pub fn max_fields(&mut self, max_fields: usize) -> &mut Self {
+        self.max_fields = max_fields;
+        self
+    }
+
+    /// Refuse databases whose records are larger than `max_record_size` bytes (65535 by default).
+    pub fn max_record_size(&mut self, max_record_size: usize) -> &mut Self {
+        self.max_record_size = max_record_size;
+        self
+    }
+
+    /// Refuse memos longer than `max_memo_size` bytes, which are otherwise only bounded by the
+    /// size of the memo file.
+    pub fn max_memo_size(&mut self, max_memo_size: usize) -> &mut Self {
+        self.max_memo_size = max_memo_size;
+        self
+    }
+
+    /// Take an advisory shared lock on the file as soon as it is opened, waiting for any
+    /// exclusive lock held by another process (such as a FoxPro application writing to the
+    /// table) to be released. See `Database::lock_shared`.
+    pub fn locking(&mut self, locking: bool) -> &mut Self {
+        self.locking = locking;
+        self
+    }
+
+    /// Open the database for writing as well as reading, see `Database::append`.
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.write = write;
+        self
+    }
+
+    /// Open the database for writing through a temporary copy of it and its memo file, which
+    /// `Database::commit` moves over the originals in one step. A crash before then leaves the
+    /// originals untouched. This implies `write`.
+    pub fn atomic(&mut self, atomic: bool) -> &mut Self {
+        self.atomic = atomic;
+        if atomic {
+            self.write = true;
+        }
+        self
+    }
+
+    /// Sets what happens to values which don't fit in their field when writing records,
+    /// `Overflow::Error` by default.
+    pub fn overflow(&mut self, overflow: Overflow) -> &mut Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Sets which side text is padded on in character fields when writing records,
+    /// `Padding::Right` by default.
+    pub fn padding(&mut self, padding: Padding) -> &mut Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets what numbers are padded with when writing records, `NumericFill::Blank` by default.
+    pub fn numeric_fill(&mut self, numeric_fill: NumericFill) -> &mut Self {
+        self.numeric_fill = numeric_fill;
+        self
+    }
+
+    /// Sets what happens to characters the code page of the database cannot represent when
+    /// writing records, `Unmappable::Error` by default.
+    pub fn unmappable(&mut self, unmappable: Unmappable) -> &mut Self {
+        self.unmappable = unmappable;
+        self
+    }
+
+    pub fn open(&self, path: &str) -> Result<Database, DbaseError> {
+        let path_buf = PathBuf::from(path);
+        // Opening a directory succeeds on some platforms, so check for it up front
+        if path_buf.is_dir() {
+            return Err(DbaseError::IsADirectory(path_buf));
+        }
+        let file = fs::OpenOptions::new().read(true).write(self.write && !self.atomic).open(&path_buf).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => DbaseError::NotFound(path_buf.clone()),
+            io::ErrorKind::PermissionDenied => DbaseError::PermissionDenied(path_buf.clone()),
+            _ => DbaseError::Io(e)
+        })?;
+        match self.atomic {
+            true => Database::open_atomic(&path_buf, self.clone()),
+            false => Database::parse_file(file, Some(&path_buf), self.clone())
+        }
+    }
+}
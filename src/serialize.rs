@@ -0,0 +1,120 @@
+use serde_crate::Serialize;
+use serde_json::{Map, Number, Value};
+use std::collections::HashMap;
+use std::io;
+use super::csv;
+use super::error::DbaseError;
+use super::fields::FieldValue;
+use super::header::FieldDescriptor;
+use super::writer::DbfWriter;
+
+/// Writes `records` as a new database at `path`, overwriting any existing file, and returns the
+/// number of records written.
+///
+/// Every record is serialized first, then the schema is picked from the serde types of the
+/// values: there is one field per struct field or map key, in the order they first appear, with
+/// booleans written to `L` fields, numbers to `N` fields and strings to `C` fields, or `M` fields
+/// when a value doesn't fit in 254 bytes. Only the lengths and decimal counts of the fields
+/// depend on the values. Nested values, and columns mixing several types, are written as JSON
+/// text.
+///
+/// Records must serialize to structs or maps.
+pub fn from_serialize<T: Serialize, I: IntoIterator<Item = T>>(path: &str, records: I) -> Result<u32, DbaseError> {
+    let mut keys:Vec<String> = vec![];
+    let mut maps = vec![];
+    for record in records {
+        let map = match serde_json::to_value(&record).map_err(io::Error::from)? {
+            Value::Object(map) => map,
+            r => {
+                let message = format!("Only structs and maps can be written as records, not {}", r);
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message).into());
+            }
+        };
+        for key in map.keys() {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+        maps.push(map);
+    }
+    let names = csv::column_names(Some(&keys), keys.len());
+    let mut fields = vec![];
+    for (key, name) in keys.iter().zip(names.iter()) {
+        let column:Vec<&Value> = maps.iter().filter_map(|map| map.get(key)).filter(|value| !value.is_null()).collect();
+        fields.push(infer_field(name, &column)?);
+    }
+    let mut writer = DbfWriter::create(path, fields.clone())?;
+    for map in maps.iter() {
+        writer.write_record(&record_values(&keys, &fields, map))?;
+    }
+    writer.finish()?;
+    Ok(maps.len() as u32)
+}
+
+/// The field holding every value of a column, typed after the values.
+fn infer_field(name: &str, column: &[&Value]) -> Result<FieldDescriptor, DbaseError> {
+    if !column.is_empty() && column.iter().all(|value| value.is_boolean()) {
+        return FieldDescriptor::new(name, b'L', 1, 0);
+    }
+    let numbers:Option<Vec<&Number>> = column.iter().map(|value| value.as_number()).collect();
+    if let (false, Some(numbers)) = (column.is_empty(), numbers) {
+        let digits:Vec<(usize, usize)> = numbers.into_iter().map(number_digits).collect();
+        let integer = digits.iter().map(|(integer, _)| *integer).max().unwrap_or(1);
+        // Decimals are rounded off rather than overflowing the 20 characters of the field
+        let decimals = digits.iter().map(|(_, decimals)| *decimals).max().unwrap_or(0)
+            .min(15)
+            .min(19_usize.saturating_sub(integer));
+        let length = match decimals {
+            0 => integer,
+            r => integer + r + 1
+        };
+        return FieldDescriptor::new(name, b'N', length.clamp(1, 20) as u16, decimals as u8);
+    }
+    let length = column.iter()
+        .map(|value| encoding_rs::WINDOWS_1252.encode(&text(value)).0.len())
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    match length {
+        0..=254 => FieldDescriptor::new(name, b'C', length as u16, 0),
+        _ => FieldDescriptor::new(name, b'M', 10, 0)
+    }
+}
+
+/// The integer digit count, sign included, and decimal digit count of `number` written out in
+/// full, without an exponent.
+fn number_digits(number: &Number) -> (usize, usize) {
+    let text = match (number.as_i64(), number.as_u64(), number.as_f64()) {
+        (Some(r), _, _) => r.to_string(),
+        (_, Some(r), _) => r.to_string(),
+        (_, _, r) => r.unwrap_or(0.0).to_string()
+    };
+    match text.split_once('.') {
+        Some((integer, decimals)) => (integer.len(), decimals.len()),
+        None => (text.len(), 0)
+    }
+}
+
+/// The values of a record for `fields`, which were inferred from the same keys.
+fn record_values(keys: &[String], fields: &[FieldDescriptor], map: &Map<String, Value>) -> HashMap<String, FieldValue> {
+    let mut values = HashMap::new();
+    for (key, field) in keys.iter().zip(fields.iter()) {
+        let value = match (field.type_code(), map.get(key)) {
+            (_, None) | (_, Some(Value::Null)) => FieldValue::Null,
+            (b'L', Some(value)) => FieldValue::Boolean(value.as_bool()),
+            (b'N', Some(value)) => value.as_f64().map_or(FieldValue::Null, FieldValue::Numeric),
+            (_, Some(value)) => FieldValue::Text(text(value))
+        };
+        values.insert(field.name().to_string(), value);
+    }
+    values
+}
+
+/// The text a value is written as in a `C` or `M` field: strings as they are, anything else as
+/// JSON.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(r) => r.clone(),
+        r => r.to_string()
+    }
+}
@@ -0,0 +1,1161 @@
+use chrono::{Datelike, NaiveDate, Timelike, Utc};
+use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian, LittleEndian};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{Read, Seek, Write};
+use std::path::Path;
+use super::header::{dbt_layout, is_builtin_type, is_memo_type, is_varlength_type, FieldDescriptor, FieldDescriptorBuilder, Version};
+use super::fields;
+use super::fields::FieldValue;
+use super::error::DbaseError;
+use super::codepage::{Charset, CodePage, Unmappable};
+
+const DBASE_MEMO_BLOCK_SIZE: u32 = 512;
+const FOXPRO_MEMO_BLOCK_SIZE: u32 = 64;
+// Both memo formats reserve the first 512 bytes of the file for their header
+pub(crate) const MEMO_HEADER_SIZE: u32 = 512;
+pub(crate) const DBASE4_MEMO_MARKER: [u8; 4] = [0xff, 0xff, 0x08, 0x00];
+
+/// The pointer to memo block `block` stored in a memo field of `length` bytes: a little-endian
+/// integer in 4 byte fields, as FoxPro writes them, or the block number in ASCII otherwise.
+pub(crate) fn memo_pointer(block: u32, length: usize) -> Vec<u8> {
+    match length {
+        4 => block.to_le_bytes().to_vec(),
+        _ => format!("{:>width$}", block, width = length).into_bytes()
+    }
+}
+
+pub(crate) trait MemoWriter: Debug {
+    /// Writes `data` to the next free blocks of the memo file, returning the block it starts at,
+    /// see `memo_pointer`.
+    fn write_memo(&mut self, data: &[u8], is_text: bool) -> Result<u32, io::Error>;
+    /// The next free block, to give back to `rollback`.
+    fn next_block(&self) -> u32;
+    fn block_size(&self) -> u32;
+    /// Frees every block written since `next_block` returned `block`, truncating the memo file
+    /// when it was opened from disk.
+    fn rollback(&mut self, block: u32) -> Result<(), io::Error>;
+    fn flush(&mut self) -> Result<(), io::Error>;
+}
+
+/// Truncates a memo file opened from disk, generic sinks cannot be shortened.
+type Truncate<W> = fn(&mut W, u64) -> Result<(), io::Error>;
+
+fn truncate_file(file: &mut File, len: u64) -> Result<(), io::Error> {
+    file.set_len(len)
+}
+
+/// Runs `write`, then frees the memo blocks it allocated if it failed, so that a record which
+/// could not be written leaves no memo behind.
+pub(crate) fn with_memo_rollback<C, T, F>(context: &mut C, memo: fn(&mut C) -> &mut Option<Box<dyn MemoWriter>>, write: F) -> Result<T, DbaseError>
+    where F: FnOnce(&mut C) -> Result<T, DbaseError> {
+    let checkpoint = memo(context).as_ref().map(|writer| writer.next_block());
+    let result = write(context);
+    if let (Err(_), Some(writer), Some(block)) = (&result, memo(context).as_mut(), checkpoint) {
+        writer.rollback(block)?;
+    }
+    result
+}
+
+/// Opens the existing .dbt or .fpt memo file at `path` to append memos to it, in blocks of
+/// `block_size` bytes or of the size its header declares when `block_size` is 0. `dbase4` picks
+/// the dBASE IV block layout for .dbt files.
+pub(crate) fn open_memo_writer(path: &Path, dbase4: bool, block_size: u32) -> Result<Box<dyn MemoWriter>, io::Error> {
+    let extension = path.extension().and_then(|r| r.to_str()).map(|r| r.to_lowercase());
+    match extension.as_deref() {
+        Some("fpt") => Ok(Box::new(FoxProMemoWriter::open(path, block_size)?)),
+        Some("smt") => Err(io::Error::new(io::ErrorKind::Unsupported, "Writing to .smt memo files is not supported")),
+        _ => Ok(Box::new(DBaseMemoWriter::open(path, dbase4, block_size)?))
+    }
+}
+
+/// Starts a memo file at `path` with the header of the memo file at `source` but none of its
+/// memos, to copy the memos still in use to, see `Database::pack`.
+pub(crate) fn rebuild_memo_writer(source: &Path, path: &Path, dbase4: bool, block_size: u32) -> Result<Box<dyn MemoWriter>, io::Error> {
+    let mut header = vec![];
+    File::open(source)?.take(u64::from(MEMO_HEADER_SIZE)).read_to_end(&mut header)?;
+    header.resize(MEMO_HEADER_SIZE as usize, 0);
+    fs::write(path, &header)?;
+    let mut writer = open_memo_writer(path, dbase4, block_size)?;
+    let first_block = MEMO_HEADER_SIZE.div_ceil(writer.block_size());
+    writer.rollback(first_block)?;
+    Ok(writer)
+}
+
+#[derive(Debug)]
+struct DBaseMemoWriter<W: Write + Seek> {
+    descriptor: W,
+    block_size: u32,
+    next_available: u32,
+    dbase4: bool,
+    truncate: Option<Truncate<W>>
+}
+impl<W: Write + Seek> DBaseMemoWriter<W> {
+    fn create(mut descriptor: W, dbase4: bool) -> Result<Self, io::Error> {
+        let mut header = vec![0; MEMO_HEADER_SIZE as usize];
+        match dbase4 {
+            // dBASE IV stores the block size instead of a version byte
+            true => (&mut header[20..22]).write_u16::<LittleEndian>(DBASE_MEMO_BLOCK_SIZE as u16)?,
+            false => header[16] = 0x03
+        }
+        descriptor.write_all(&header)?;
+        let mut writer = DBaseMemoWriter {
+            descriptor,
+            block_size: DBASE_MEMO_BLOCK_SIZE,
+            next_available: MEMO_HEADER_SIZE / DBASE_MEMO_BLOCK_SIZE,
+            dbase4,
+            truncate: None
+        };
+        writer.write_next_available()?;
+        Ok(writer)
+    }
+    fn write_next_available(&mut self) -> Result<(), io::Error> {
+        self.descriptor.seek(io::SeekFrom::Start(0))?;
+        self.descriptor.write_u32::<LittleEndian>(self.next_available)
+    }
+}
+impl DBaseMemoWriter<File> {
+    fn open<T: AsRef<Path>>(path: T, dbase4: bool, block_size: u32) -> Result<Self, io::Error> {
+        let mut descriptor = fs::OpenOptions::new().read(true).write(true).open(path)?;
+        let mut header = [0; 22];
+        descriptor.read_exact(&mut header)?;
+        let next_available = (&header[0..4]).read_u32::<LittleEndian>()?;
+        let block_size = match block_size {
+            0 => dbt_layout(&header).0,
+            r => r
+        };
+        // Some writers leave a stale next block in the header, never write over the end of the file
+        let block_count = descriptor.metadata()?.len().div_ceil(u64::from(block_size)) as u32;
+        let next_available = next_available.max(block_count);
+        Ok(DBaseMemoWriter {
+            descriptor,
+            block_size,
+            next_available,
+            dbase4,
+            truncate: Some(truncate_file)
+        })
+    }
+}
+impl<W: Write + Seek + Debug> MemoWriter for DBaseMemoWriter<W> {
+    fn write_memo(&mut self, data: &[u8], _is_text: bool) -> Result<u32, io::Error> {
+        let id = self.next_available;
+        // dBASE IV memos start with a marker and their length, header included, while dBASE III
+        // memos end with two end of file markers. Both fill whole blocks.
+        let mut block = vec![];
+        match self.dbase4 {
+            true => {
+                block.extend_from_slice(&DBASE4_MEMO_MARKER);
+                block.write_u32::<LittleEndian>(data.len() as u32 + 8)?;
+                block.extend_from_slice(data);
+            },
+            false => {
+                block.extend_from_slice(data);
+                block.extend_from_slice(&[0x1a, 0x1a]);
+            }
+        }
+        let block_count = block.len().div_ceil(self.block_size as usize);
+        block.resize(block_count * self.block_size as usize, 0);
+        self.descriptor.seek(io::SeekFrom::Start(u64::from(id) * u64::from(self.block_size)))?;
+        self.descriptor.write_all(&block)?;
+        self.next_available += block_count as u32;
+        self.write_next_available()?;
+        Ok(id)
+    }
+    fn next_block(&self) -> u32 {
+        self.next_available
+    }
+    fn block_size(&self) -> u32 {
+        self.block_size
+    }
+    fn rollback(&mut self, block: u32) -> Result<(), io::Error> {
+        self.next_available = block;
+        self.write_next_available()?;
+        match self.truncate {
+            Some(truncate) => truncate(&mut self.descriptor, u64::from(block) * u64::from(self.block_size)),
+            None => Ok(())
+        }
+    }
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.descriptor.flush()
+    }
+}
+
+#[derive(Debug)]
+struct FoxProMemoWriter<W: Write + Seek> {
+    descriptor: W,
+    block_size: u32,
+    next_available: u32,
+    truncate: Option<Truncate<W>>
+}
+impl<W: Write + Seek> FoxProMemoWriter<W> {
+    fn create(mut descriptor: W) -> Result<Self, io::Error> {
+        let mut header = vec![];
+        header.write_u32::<BigEndian>(MEMO_HEADER_SIZE / FOXPRO_MEMO_BLOCK_SIZE)?;
+        header.write_u16::<BigEndian>(0)?;
+        header.write_u16::<BigEndian>(FOXPRO_MEMO_BLOCK_SIZE as u16)?;
+        header.resize(MEMO_HEADER_SIZE as usize, 0);
+        descriptor.write_all(&header)?;
+        Ok(FoxProMemoWriter {
+            descriptor,
+            block_size: FOXPRO_MEMO_BLOCK_SIZE,
+            next_available: MEMO_HEADER_SIZE / FOXPRO_MEMO_BLOCK_SIZE,
+            truncate: None
+        })
+    }
+}
+impl<W: Write + Seek> FoxProMemoWriter<W> {
+    fn write_next_available(&mut self) -> Result<(), io::Error> {
+        self.descriptor.seek(io::SeekFrom::Start(0))?;
+        self.descriptor.write_u32::<BigEndian>(self.next_available)
+    }
+}
+impl FoxProMemoWriter<File> {
+    fn open<T: AsRef<Path>>(path: T, block_size: u32) -> Result<Self, io::Error> {
+        let mut descriptor = fs::OpenOptions::new().read(true).write(true).open(path)?;
+        let next_available = descriptor.read_u32::<BigEndian>()?;
+        descriptor.read_u16::<BigEndian>()?;
+        let block_size = match (block_size, descriptor.read_u16::<BigEndian>()?) {
+            (0, 0) => FOXPRO_MEMO_BLOCK_SIZE,
+            (0, r) => u32::from(r),
+            (r, _) => r
+        };
+        Ok(FoxProMemoWriter {
+            descriptor,
+            block_size,
+            next_available,
+            truncate: Some(truncate_file)
+        })
+    }
+}
+impl<W: Write + Seek + Debug> MemoWriter for FoxProMemoWriter<W> {
+    fn write_memo(&mut self, data: &[u8], is_text: bool) -> Result<u32, io::Error> {
+        let id = self.next_available;
+        // Every memo starts with its type (0 for pictures and objects, 1 for text) and length
+        let mut block = vec![];
+        block.write_u32::<BigEndian>(is_text as u32)?;
+        block.write_u32::<BigEndian>(data.len() as u32)?;
+        block.extend_from_slice(data);
+        let block_count = block.len().div_ceil(self.block_size as usize);
+        block.resize(block_count * self.block_size as usize, 0);
+        self.descriptor.seek(io::SeekFrom::Start(u64::from(id) * u64::from(self.block_size)))?;
+        self.descriptor.write_all(&block)?;
+        self.next_available += block_count as u32;
+        self.write_next_available()?;
+        Ok(id)
+    }
+    fn next_block(&self) -> u32 {
+        self.next_available
+    }
+    fn block_size(&self) -> u32 {
+        self.block_size
+    }
+    fn rollback(&mut self, block: u32) -> Result<(), io::Error> {
+        self.next_available = block;
+        self.write_next_available()?;
+        match self.truncate {
+            Some(truncate) => truncate(&mut self.descriptor, u64::from(block) * u64::from(self.block_size)),
+            None => Ok(())
+        }
+    }
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.descriptor.flush()
+    }
+}
+
+/// A type written as a record, see `DbfWriter::write`.
+///
+/// With the `derive` feature, `#[derive(WritableRecord)]` implements it with one field per
+/// struct field, named after it in upper case and typed after its `FieldData`. The
+/// `#[dbase(name = "...", length = ..., decimal_count = ...)]` attribute overrides any of them.
+///
+///     use dbase_parser::{create, FieldValue, WritableRecord};
+///     use dbase_parser::error::DbaseError;
+///     use dbase_parser::header::FieldDescriptor;
+///     use std::collections::HashMap;
+///
+///     struct Product {
+///         name: String
+///     }
+///     impl WritableRecord for Product {
+///         fn fields() -> Result<Vec<FieldDescriptor>, DbaseError> {
+///             Ok(vec![FieldDescriptor::new("NAME", b'C', 20, 0)?])
+///         }
+///         fn values(&self) -> HashMap<String, FieldValue> {
+///             let mut values = HashMap::new();
+///             values.insert("NAME".to_string(), FieldValue::Text(self.name.clone()));
+///             values
+///         }
+///     }
+///
+///     let path = std::env::temp_dir().join("products.dbf");
+///     let mut writer = create(path.to_str().unwrap(), Product::fields().unwrap()).unwrap();
+///     writer.write(&Product { name: "Widget".to_string() }).unwrap();
+///     writer.finish().unwrap();
+pub trait WritableRecord {
+    /// The fields of a database holding records of this type.
+    fn fields() -> Result<Vec<FieldDescriptor>, DbaseError>;
+    /// The values of the record, keyed by field name.
+    fn values(&self) -> HashMap<String, FieldValue>;
+}
+
+/// A Rust type stored in a single field, and the field it is stored in by default.
+pub trait FieldData {
+    const TYPE_CODE: u8;
+    const LENGTH: u16;
+    const DECIMAL_COUNT: u8;
+    fn to_field_value(&self) -> FieldValue;
+}
+
+impl FieldData for String {
+    const TYPE_CODE: u8 = b'C';
+    const LENGTH: u16 = 254;
+    const DECIMAL_COUNT: u8 = 0;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Text(self.clone())
+    }
+}
+
+impl FieldData for f64 {
+    const TYPE_CODE: u8 = b'N';
+    const LENGTH: u16 = 20;
+    const DECIMAL_COUNT: u8 = 4;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Numeric(*self)
+    }
+}
+
+impl FieldData for f32 {
+    const TYPE_CODE: u8 = b'N';
+    const LENGTH: u16 = 20;
+    const DECIMAL_COUNT: u8 = 4;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Numeric(f64::from(*self))
+    }
+}
+
+impl FieldData for i32 {
+    const TYPE_CODE: u8 = b'N';
+    const LENGTH: u16 = 11;
+    const DECIMAL_COUNT: u8 = 0;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Integer(*self)
+    }
+}
+
+impl FieldData for u32 {
+    const TYPE_CODE: u8 = b'N';
+    const LENGTH: u16 = 10;
+    const DECIMAL_COUNT: u8 = 0;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Numeric(f64::from(*self))
+    }
+}
+
+impl FieldData for i16 {
+    const TYPE_CODE: u8 = b'N';
+    const LENGTH: u16 = 6;
+    const DECIMAL_COUNT: u8 = 0;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Integer(i32::from(*self))
+    }
+}
+
+impl FieldData for u16 {
+    const TYPE_CODE: u8 = b'N';
+    const LENGTH: u16 = 5;
+    const DECIMAL_COUNT: u8 = 0;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Integer(i32::from(*self))
+    }
+}
+
+impl FieldData for u8 {
+    const TYPE_CODE: u8 = b'N';
+    const LENGTH: u16 = 3;
+    const DECIMAL_COUNT: u8 = 0;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Integer(i32::from(*self))
+    }
+}
+
+impl FieldData for bool {
+    const TYPE_CODE: u8 = b'L';
+    const LENGTH: u16 = 1;
+    const DECIMAL_COUNT: u8 = 0;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Boolean(Some(*self))
+    }
+}
+
+impl FieldData for NaiveDate {
+    const TYPE_CODE: u8 = b'D';
+    const LENGTH: u16 = 8;
+    const DECIMAL_COUNT: u8 = 0;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Date(fields::utc_date(*self))
+    }
+}
+
+/// Kept for the dates `FieldValue::Date` holds, `NaiveDate` is preferred as chrono deprecates
+/// `Date`.
+#[allow(deprecated)]
+impl FieldData for chrono::Date<Utc> {
+    const TYPE_CODE: u8 = b'D';
+    const LENGTH: u16 = 8;
+    const DECIMAL_COUNT: u8 = 0;
+    fn to_field_value(&self) -> FieldValue {
+        FieldValue::Date(*self)
+    }
+}
+
+/// `None` leaves the field blank.
+impl<T: FieldData> FieldData for Option<T> {
+    const TYPE_CODE: u8 = T::TYPE_CODE;
+    const LENGTH: u16 = T::LENGTH;
+    const DECIMAL_COUNT: u8 = T::DECIMAL_COUNT;
+    fn to_field_value(&self) -> FieldValue {
+        match self {
+            Some(r) => r.to_field_value(),
+            None => FieldValue::Null
+        }
+    }
+}
+
+/// Writes a new database, one record at a time.
+///
+/// Memo (`M`) and general (`G`) fields are written to a memo file sharing the stem of the
+/// database. Their length picks the format: 10 byte fields make a dBASE III table with a .dbt
+/// file, 4 byte fields a Visual FoxPro table with a .fpt file.
+///
+///     use dbase_parser::DbfWriter;
+///     use dbase_parser::header::FieldDescriptor;
+///
+///     let path = std::env::temp_dir().join("names.dbf");
+///     let fields = vec![FieldDescriptor::new("NAME", b'C', 20, 0).unwrap()];
+///     let writer = DbfWriter::create(path.to_str().unwrap(), fields).unwrap();
+///     writer.finish().unwrap();
+///
+/// Any seekable sink works too, such as an in-memory buffer:
+///
+///     use dbase_parser::DbfWriter;
+///     use dbase_parser::header::FieldDescriptor;
+///     use std::io::Cursor;
+///
+///     let fields = vec![FieldDescriptor::new("NAME", b'C', 20, 0).unwrap()];
+///     let writer = DbfWriter::new(Cursor::new(vec![]), fields).unwrap();
+///     let bytes = writer.finish().unwrap().into_inner();
+///     assert_eq!(bytes.len(), 32 + 32 + 1 + 1);
+///
+/// The header is kept up to date by `flush`, `finish`, and when the writer is dropped.
+#[derive(Debug)]
+pub struct DbfWriter<W: Write + Seek = File> {
+    // Only taken back by `finish`
+    descriptor: Option<W>,
+    memo: Option<Box<dyn MemoWriter>>,
+    version: u8,
+    fields: Vec<FieldDescriptor>,
+    header_size: u16,
+    record_size: u16,
+    record_count: u32,
+    overflow: Overflow,
+    padding: Padding,
+    numeric_fill: NumericFill,
+    code_page: CodePage,
+    unmappable: Unmappable
+}
+
+/// The version byte of a new database: memo fields of 10 bytes make a dBASE III table, memo
+/// fields of 4 bytes a Visual FoxPro table, as do nullable and variable length fields and the
+/// binary `I`, `T`, `B` and `Y` types.
+///
+/// dBASE 7 `@` and `O` fields are refused, as dBASE 7 tables cannot be written.
+fn version_for(fields: &[FieldDescriptor]) -> Result<u8, DbaseError> {
+    if let Some(field) = fields.iter().find(|field| matches!(field.type_code, b'@' | b'O')) {
+        return Err(DbaseError::InvalidFieldDescriptor {
+            field: field.name.clone(),
+            message: format!("fields of type {} need a dBASE 7 table, which cannot be written", field.type_code as char)
+        });
+    }
+    let memo_lengths:Vec<u16> = fields.iter().filter(|field| is_memo_type(field.type_code)).map(|field| field.length).collect();
+    let varlength = has_varlength(fields);
+    let foxpro = varlength || fields.iter().any(|field| field.is_nullable() || matches!(field.type_code, b'I' | b'T' | b'B' | b'Y'));
+    let visual_foxpro = 0x30 | (varlength as u8) << 1;
+    match memo_lengths.first() {
+        None if foxpro => Ok(visual_foxpro),
+        None => Ok(0x03),
+        Some(10) if !foxpro && memo_lengths.iter().all(|r| *r == 10) => Ok(0x83),
+        Some(4) if memo_lengths.iter().all(|r| *r == 4) => Ok(visual_foxpro),
+        Some(_) => Err(io::Error::new(io::ErrorKind::InvalidInput, "Memo fields must all be 10 bytes (dBASE III) or 4 bytes (Visual FoxPro, required by nullable, varchar, integer, datetime, double and currency fields) long").into())
+    }
+}
+
+/// The version byte of a new `version` database, checking that it can hold `fields`.
+fn version_byte(version: &Version, fields: &[FieldDescriptor]) -> Result<u8, DbaseError> {
+    let (types, memo_length):(&[u8], u16) = match version {
+        Version::dBASE3(_) => (b"CDLMN", 10),
+        Version::dBASE4Table(_) | Version::dBASE4System(_) => (b"CDFLMN", 10),
+        Version::VisualFoxPro(_, _) => (b"CDFLMNGPITBYVQ", 4),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Tables cannot be written as {:?}", version)).into())
+    };
+    let vfp = matches!(version, Version::VisualFoxPro(_, _));
+    for field in fields.iter().filter(|field| field.type_code != NULL_FLAGS_TYPE) {
+        let invalid = |message: String| DbaseError::InvalidFieldDescriptor { field: field.name.clone(), message };
+        if !types.contains(&field.type_code) {
+            return Err(invalid(format!("a {:?} table has no fields of type {}", version, field.type_code as char)));
+        }
+        if is_memo_type(field.type_code) && field.length != memo_length {
+            return Err(invalid(format!("memo fields of a {:?} table are {} bytes long", version, memo_length)));
+        }
+        if field.is_nullable() && !vfp {
+            return Err(invalid(format!("a {:?} table has no nullable fields", version)));
+        }
+    }
+    let memo = has_memo(fields);
+    Ok(match version {
+        Version::dBASE3(_) if memo => 0x83,
+        Version::dBASE4Table(_) if memo => 0xcb,
+        Version::dBASE4System(_) if memo => 0x8b,
+        Version::dBASE4Table(_) => 0x43,
+        Version::dBASE4System(_) => 0x63,
+        Version::VisualFoxPro(autoincrement, varchar) => 0x30 | *autoincrement as u8 | ((*varchar || has_varlength(fields)) as u8) << 1,
+        _ => 0x03
+    })
+}
+
+fn has_memo(fields: &[FieldDescriptor]) -> bool {
+    fields.iter().any(|field| is_memo_type(field.type_code))
+}
+
+/// Encodes a dBASE 7 double, see `fields::FieldTypeO`.
+fn dbase7_double(value: f64) -> [u8; 8] {
+    let bits = value.to_bits();
+    let bits = match value.is_sign_negative() {
+        true => !bits,
+        false => bits ^ (1 << 63)
+    };
+    bits.to_be_bytes()
+}
+
+fn has_varlength(fields: &[FieldDescriptor]) -> bool {
+    fields.iter().any(|field| is_varlength_type(field.type_code))
+}
+
+fn is_visual_foxpro(version: u8) -> bool {
+    version & 0xf0 == 0x30
+}
+
+/// The memo file format of a database with the `version` byte.
+fn create_memo_writer<M: Write + Seek + Debug + 'static>(version: u8, memo_sink: M) -> Result<Box<dyn MemoWriter>, io::Error> {
+    match version {
+        0xcb | 0x8b => Ok(Box::new(DBaseMemoWriter::create(memo_sink, true)?)),
+        r if is_visual_foxpro(r) => Ok(Box::new(FoxProMemoWriter::create(memo_sink)?)),
+        _ => Ok(Box::new(DBaseMemoWriter::create(memo_sink, false)?))
+    }
+}
+
+impl DbfWriter<File> {
+    /// Creates the database at `path` with the given fields, overwriting any existing file.
+    pub fn create(path: &str, fields: Vec<FieldDescriptor>) -> Result<DbfWriter, DbaseError> {
+        let version = version_for(&fields)?;
+        DbfWriter::create_with_version_byte(path, version, fields)
+    }
+
+    /// Creates the database at `path` as a `version` table, such as `Version::dBASE3(false)`.
+    /// Whether it has a memo file is decided by its fields, not by `version`.
+    ///
+    /// Fields of a type `version` doesn't know, memo fields of the wrong length and nullable
+    /// fields outside of Visual FoxPro are refused.
+    pub fn create_as(path: &str, version: Version, fields: Vec<FieldDescriptor>) -> Result<DbfWriter, DbaseError> {
+        let version = version_byte(&version, &fields)?;
+        DbfWriter::create_with_version_byte(path, version, fields)
+    }
+
+    fn create_with_version_byte(path: &str, version: u8, fields: Vec<FieldDescriptor>) -> Result<DbfWriter, DbaseError> {
+        let path = Path::new(path);
+        let memo = match (has_memo(&fields), is_visual_foxpro(version)) {
+            (true, true) => Some(create_memo_writer(version, File::create(path.with_extension("fpt"))?)?),
+            (true, false) => Some(create_memo_writer(version, File::create(path.with_extension("dbt"))?)?),
+            (false, _) => None
+        };
+        DbfWriter::with_memo_writer(File::create(path)?, memo, version, fields)
+    }
+}
+
+impl<W: Write + Seek> DbfWriter<W> {
+    /// Writes a database without memo fields to `sink`.
+    pub fn new(sink: W, fields: Vec<FieldDescriptor>) -> Result<DbfWriter<W>, DbaseError> {
+        let version = version_for(&fields)?;
+        DbfWriter::new_with_version_byte(sink, version, fields)
+    }
+
+    /// Writes a `version` database without memo fields to `sink`, see `DbfWriter::create_as`.
+    pub fn new_as(sink: W, version: Version, fields: Vec<FieldDescriptor>) -> Result<DbfWriter<W>, DbaseError> {
+        let version = version_byte(&version, &fields)?;
+        DbfWriter::new_with_version_byte(sink, version, fields)
+    }
+
+    fn new_with_version_byte(sink: W, version: u8, fields: Vec<FieldDescriptor>) -> Result<DbfWriter<W>, DbaseError> {
+        if has_memo(&fields) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Memo fields need a memo sink, see DbfWriter::with_memo").into());
+        }
+        DbfWriter::with_memo_writer(sink, None, version, fields)
+    }
+
+    /// Writes a database to `sink`, and the content of its memo fields to `memo_sink`.
+    pub fn with_memo<M: Write + Seek + Debug + 'static>(sink: W, memo_sink: M, fields: Vec<FieldDescriptor>) -> Result<DbfWriter<W>, DbaseError> {
+        let version = version_for(&fields)?;
+        DbfWriter::with_memo_and_version_byte(sink, memo_sink, version, fields)
+    }
+
+    /// Writes a `version` database to `sink`, and the content of its memo fields to
+    /// `memo_sink`, see `DbfWriter::create_as`.
+    pub fn with_memo_as<M: Write + Seek + Debug + 'static>(sink: W, memo_sink: M, version: Version, fields: Vec<FieldDescriptor>) -> Result<DbfWriter<W>, DbaseError> {
+        let version = version_byte(&version, &fields)?;
+        DbfWriter::with_memo_and_version_byte(sink, memo_sink, version, fields)
+    }
+
+    fn with_memo_and_version_byte<M: Write + Seek + Debug + 'static>(sink: W, memo_sink: M, version: u8, fields: Vec<FieldDescriptor>) -> Result<DbfWriter<W>, DbaseError> {
+        if !has_memo(&fields) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "There are no memo fields to write to the memo sink").into());
+        }
+        let memo = create_memo_writer(version, memo_sink)?;
+        DbfWriter::with_memo_writer(sink, Some(memo), version, fields)
+    }
+
+    fn with_memo_writer(sink: W, memo: Option<Box<dyn MemoWriter>>, version: u8, mut fields: Vec<FieldDescriptor>) -> Result<DbfWriter<W>, DbaseError> {
+        // Nullable and variable length fields are flagged in a hidden column after the others,
+        // which is rebuilt here if the fields were copied from another database
+        fields.retain(|field| field.type_code != NULL_FLAGS_TYPE);
+        let flag_count:usize = fields.iter().map(flag_count).sum();
+        if flag_count > 0 {
+            fields.push(FieldDescriptorBuilder::new(NULL_FLAGS_NAME, NULL_FLAGS_TYPE)
+                .length(flag_count.div_ceil(8) as u16)
+                .flags(0x05)
+                .build()?);
+        }
+        let record_size = 1 + fields.iter().map(|field| field.length as usize).sum::<usize>();
+        // Visual FoxPro tables leave room for a database container backlink after the fields
+        let trailer_size = match is_visual_foxpro(version) {
+            true => 1 + 263,
+            false => 1
+        };
+        let header_size = 32 + fields.len() * 32 + trailer_size;
+        if record_size > u16::MAX as usize || header_size > u16::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "There are too many fields to fit in a record").into());
+        }
+        let mut writer = DbfWriter {
+            descriptor: Some(sink),
+            memo,
+            version,
+            fields,
+            header_size: header_size as u16,
+            record_size: record_size as u16,
+            record_count: 0,
+            overflow: Overflow::Error,
+            padding: Padding::Right,
+            numeric_fill: NumericFill::Blank,
+            code_page: CodePage::Windows1252,
+            unmappable: Unmappable::Error
+        };
+        writer.write_header()?;
+        Ok(writer)
+    }
+
+    /// Sets what happens to values which don't fit in their field, `Overflow::Error` by default.
+    pub fn overflow(&mut self, overflow: Overflow) -> &mut Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Sets which side text is padded on in character fields, `Padding::Right` by default.
+    pub fn padding(&mut self, padding: Padding) -> &mut Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets what numbers are padded with, `NumericFill::Blank` by default.
+    pub fn numeric_fill(&mut self, numeric_fill: NumericFill) -> &mut Self {
+        self.numeric_fill = numeric_fill;
+        self
+    }
+
+    /// Sets the code page text is written in, `CodePage::Windows1252` by default. Its language
+    /// driver is stored in the header, so it should be set before writing any record.
+    pub fn code_page(&mut self, code_page: CodePage) -> &mut Self {
+        self.code_page = code_page;
+        self
+    }
+
+    /// Sets what happens to characters the code page cannot represent, `Unmappable::Error` by
+    /// default.
+    pub fn unmappable(&mut self, unmappable: Unmappable) -> &mut Self {
+        self.unmappable = unmappable;
+        self
+    }
+
+    fn sink(&mut self) -> &mut W {
+        self.descriptor.as_mut().expect("The writer is already finished")
+    }
+
+    fn write_header(&mut self) -> Result<(), io::Error> {
+        let today = Utc::now();
+        let mut header = Vec::with_capacity(self.header_size as usize);
+        header.push(self.version);
+        header.extend_from_slice(&[(today.year() - 1900) as u8, today.month() as u8, today.day() as u8]);
+        header.write_u32::<LittleEndian>(self.record_count)?;
+        header.write_u16::<LittleEndian>(self.header_size)?;
+        header.write_u16::<LittleEndian>(self.record_size)?;
+        let mut reserved = [0; 20];
+        // Byte 28 flags Visual FoxPro tables which have a memo file
+        if is_visual_foxpro(self.version) && self.memo.is_some() {
+            reserved[16] = 0x02;
+        }
+        reserved[17] = self.code_page.language_driver();
+        header.extend_from_slice(&reserved);
+        let mut data_address = 1;
+        for field in &self.fields {
+            let mut name = field.name.as_bytes().to_vec();
+            name.resize(11, 0);
+            header.extend_from_slice(&name);
+            header.push(field.type_code);
+            // Visual FoxPro stores the offset of the field in the record
+            header.write_u32::<LittleEndian>(match is_visual_foxpro(self.version) {
+                true => data_address,
+                false => 0
+            })?;
+            // Character fields wider than 255 bytes keep the high byte of their length in the
+            // decimal count
+            match field.type_code {
+                b'C' => header.write_u16::<LittleEndian>(field.length)?,
+                _ => header.extend_from_slice(&[field.length as u8, field.decimal_count])
+            }
+            header.push(field.flags);
+            header.write_u32::<LittleEndian>(field.autoincrement_next)?;
+            header.push(field.autoincrement_step);
+            header.extend_from_slice(&[0; 8]);
+            data_address += u32::from(field.length);
+        }
+        header.push(0x0d);
+        header.resize(self.header_size as usize, 0);
+        let sink = self.sink();
+        sink.seek(io::SeekFrom::Start(0))?;
+        sink.write_all(&header)
+    }
+
+    /// Appends a record made of `values`, keyed by field name. Missing and `Null` fields are left
+    /// blank.
+    ///
+    /// If the record cannot be written, the memos it added to the memo file are freed.
+    pub fn write_record(&mut self, values: &HashMap<String, FieldValue>) -> Result<(), DbaseError> {
+        with_memo_rollback(self, |writer| &mut writer.memo, |writer| {
+            let bytes = writer.encode_record(values)?;
+            writer.write_raw_record(&bytes)
+        })
+    }
+
+    /// Appends `record`, see `WritableRecord`.
+    pub fn write<T: WritableRecord>(&mut self, record: &T) -> Result<(), DbaseError> {
+        self.write_record(&record.values())
+    }
+
+    /// Encodes `values` as an active record, writing its memos to the memo file.
+    pub(crate) fn encode_record(&mut self, values: &HashMap<String, FieldValue>) -> Result<Vec<u8>, DbaseError> {
+        let mut bytes = Vec::with_capacity(self.record_size as usize);
+        // The record isn't deleted
+        bytes.push(b' ');
+        let options = EncodeOptions {
+            charset: self.code_page.charset(),
+            unmappable: self.unmappable,
+            overflow: self.overflow,
+            padding: self.padding,
+            numeric_fill: self.numeric_fill
+        };
+        bytes.append(&mut encode_fields(&self.fields, values, &mut self.memo, options)?);
+        Ok(bytes)
+    }
+
+    /// Appends a record already laid out for this database, deletion flag included.
+    pub(crate) fn write_raw_record(&mut self, bytes: &[u8]) -> Result<(), DbaseError> {
+        if bytes.len() != self.record_size as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("The record is {} bytes long instead of {}", bytes.len(), self.record_size)).into());
+        }
+        let offset = u64::from(self.header_size) + u64::from(self.record_count) * u64::from(self.record_size);
+        let sink = self.sink();
+        sink.seek(io::SeekFrom::Start(offset))?;
+        sink.write_all(bytes)?;
+        self.record_count += 1;
+        Ok(())
+    }
+
+    pub(crate) fn fields(&self) -> &[FieldDescriptor] {
+        &self.fields
+    }
+
+    /// The size of a single record, including its leading deletion flag.
+    pub(crate) fn record_size(&self) -> usize {
+        self.record_size as usize
+    }
+
+    /// Writes the end of file marker, then the record count and today's date to the header, and
+    /// flushes the database and memo sinks.
+    pub fn flush(&mut self) -> Result<(), DbaseError> {
+        let end = u64::from(self.header_size) + u64::from(self.record_count) * u64::from(self.record_size);
+        let sink = self.sink();
+        sink.seek(io::SeekFrom::Start(end))?;
+        sink.write_all(&[0x1a])?;
+        self.write_header()?;
+        self.sink().flush()?;
+        if let Some(memo) = self.memo.as_mut() {
+            memo.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Flushes the database like `flush`, giving back the sink.
+    pub fn finish(mut self) -> Result<W, DbaseError> {
+        self.flush()?;
+        Ok(self.descriptor.take().expect("The writer is already finished"))
+    }
+}
+
+impl<W: Write + Seek> Drop for DbfWriter<W> {
+    /// Flushes a writer which wasn't finished. Errors are ignored, call `flush` or `finish` to
+    /// handle them.
+    fn drop(&mut self) {
+        if self.descriptor.is_some() {
+            let _ = self.flush();
+        }
+    }
+}
+
+/// What to do with a value which doesn't fit in its field when writing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Refuse the value with a `DbaseError::InvalidFieldValue` naming the field
+    Error,
+    /// Cut text to the length of the field and round numbers to its decimal count. Numbers too
+    /// large for the field are still refused.
+    Truncate
+}
+
+/// Which side of a character field text shorter than the field is padded with spaces on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Padding {
+    /// Spaces after the text, as dBASE and FoxPro write it
+    Right,
+    /// Spaces before the text, right-aligning it
+    Left
+}
+
+/// What numbers narrower than their field are padded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumericFill {
+    /// Leading spaces, such as `"   12.50"`
+    Blank,
+    /// Leading zeros after the sign, such as `"00012.50"`
+    Zero
+}
+
+/// How `encode_field` turns values into bytes.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct EncodeOptions {
+    pub(crate) charset: Charset,
+    pub(crate) unmappable: Unmappable,
+    pub(crate) overflow: Overflow,
+    pub(crate) padding: Padding,
+    pub(crate) numeric_fill: NumericFill
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            charset: CodePage::Windows1252.charset(),
+            unmappable: Unmappable::Error,
+            overflow: Overflow::Error,
+            padding: Padding::Right,
+            numeric_fill: NumericFill::Blank
+        }
+    }
+}
+
+/// The Julian day number of the day before 1 January of year 1, from which chrono counts days.
+const JULIAN_DAY_OFFSET: i32 = 1_721_425;
+
+/// Whether rounding `value` to `decimal_count` decimals changes it by more than floating point
+/// noise, such as the 0.30000000000000004 of 0.1 + 0.2 rounded to 0.3.
+fn loses_decimals(value: f64, decimal_count: usize) -> bool {
+    let scale = 10_f64.powi(decimal_count as i32);
+    let rounded = (value * scale).round() / scale;
+    rounded.is_finite() && (rounded - value).abs() > value.abs() * 1e-12
+}
+
+/// The name and type of the Visual FoxPro column flagging null values.
+const NULL_FLAGS_NAME: &str = "_NullFlags";
+pub(crate) const NULL_FLAGS_TYPE: u8 = b'0';
+
+/// The number of bits `field` takes in the `_NullFlags` column: one for variable length fields,
+/// set when the value is shorter than the field, then one for nullable fields, set when the
+/// value is null.
+pub(crate) fn flag_count(field: &FieldDescriptor) -> usize {
+    is_varlength_type(field.type_code) as usize + field.is_nullable() as usize
+}
+
+pub(crate) fn flag_is_set(null_flags: &[u8], bit: usize) -> bool {
+    null_flags.get(bit / 8).is_some_and(|byte| byte & (1 << (bit % 8)) != 0)
+}
+
+/// The bits `field` takes in the `_NullFlags` column when it holds `value`, see `flag_count`.
+pub(crate) fn field_flags(field: &FieldDescriptor, value: Option<&FieldValue>, options: EncodeOptions) -> Result<Vec<bool>, DbaseError> {
+    let mut flags = vec![];
+    if is_varlength_type(field.type_code) {
+        flags.push(varlength_content(field, value, options)?.len() < field.length as usize);
+    }
+    if field.is_nullable() {
+        flags.push(matches!(value, None | Some(FieldValue::Null)));
+    }
+    Ok(flags)
+}
+
+/// Encodes `values` as the fields of a record, without its deletion flag.
+///
+/// Missing and `Null` values of nullable fields, as well as values shorter than their variable
+/// length field, are flagged in the `_NullFlags` column, see `flag_count`.
+pub(crate) fn encode_fields(fields: &[FieldDescriptor], values: &HashMap<String, FieldValue>, memo: &mut Option<Box<dyn MemoWriter>>, options: EncodeOptions) -> Result<Vec<u8>, DbaseError> {
+    let mut bytes = vec![];
+    let mut flags = vec![];
+    for field in fields {
+        let value = values.get(&field.name);
+        if field.type_code == NULL_FLAGS_TYPE {
+            let mut null_flags = vec![0; field.length as usize];
+            for (i, _) in flags.iter().enumerate().filter(|(_, flag)| **flag) {
+                if let Some(byte) = null_flags.get_mut(i / 8) {
+                    *byte |= 1 << (i % 8);
+                }
+            }
+            bytes.append(&mut null_flags);
+            continue;
+        }
+        flags.append(&mut field_flags(field, value, options)?);
+        bytes.append(&mut encode_field(field, value, memo, options)?);
+    }
+    Ok(bytes)
+}
+
+/// The bytes of `value` in the variable length `field`, before any padding.
+fn varlength_content(field: &FieldDescriptor, value: Option<&FieldValue>, options: EncodeOptions) -> Result<Vec<u8>, DbaseError> {
+    let length = field.length as usize;
+    let invalid = |message: String| DbaseError::InvalidFieldValue { field: field.name.clone(), message };
+    let mut bytes = match (field.type_code, value) {
+        (_, None) | (_, Some(FieldValue::Null)) => vec![],
+        (b'V', Some(FieldValue::Text(r))) => options.charset.encode(r, options.unmappable)
+            .map_err(|c| invalid(format!("{:?} in {:?} cannot be represented in {}", c, r, options.charset.name())))?,
+        (b'Q', Some(FieldValue::Binary(r))) | (b'Q', Some(FieldValue::Unknown(r))) => r.clone(),
+        (type_code, Some(value)) => return Err(invalid(format!("{:?} cannot be written to a field of type {}", value, type_code as char)))
+    };
+    if bytes.len() > length && options.overflow == Overflow::Error {
+        return Err(invalid(format!("the value is {} bytes long, more than the {} of the field", bytes.len(), length)));
+    }
+    bytes.truncate(length);
+    Ok(bytes)
+}
+
+/// Encodes `value` as the content of `field`, with memos written to `memo`.
+pub(crate) fn encode_field(field: &FieldDescriptor, value: Option<&FieldValue>, memo: &mut Option<Box<dyn MemoWriter>>, options: EncodeOptions) -> Result<Vec<u8>, DbaseError> {
+    let length = field.length as usize;
+    // Types registered by users encode their own values
+    if !is_builtin_type(field.type_code) {
+        return match value {
+            Some(value) => field.field_type.encode(field, value),
+            None => Ok(vec![b' '; length])
+        };
+    }
+    let decimal_count = field.decimal_count as usize;
+    let charset = options.charset;
+    let invalid = |message: String| DbaseError::InvalidFieldValue { field: field.name.clone(), message };
+    let encode = |text: &str| charset.encode(text, options.unmappable)
+        .map_err(|c| invalid(format!("{:?} in {:?} cannot be represented in {}", c, text, charset.name())));
+    let decimals = |lost: bool| match (lost, options.overflow) {
+        (true, Overflow::Error) => Err(invalid(format!("the value has more decimals than the {} of the field", decimal_count))),
+        _ => Ok(())
+    };
+    let numeric = |content: String| match (content.len() <= length, options.numeric_fill) {
+        (true, NumericFill::Blank) => Ok(format!("{:>width$}", content, width = length).into_bytes()),
+        (true, NumericFill::Zero) => {
+            let (sign, digits) = match content.strip_prefix('-') {
+                Some(digits) => ("-", digits),
+                None => ("", content.as_str())
+            };
+            Ok(format!("{}{:0>width$}", sign, digits, width = length - sign.len()).into_bytes())
+        },
+        (false, _) => Err(invalid(format!("{} does not fit in {} bytes", content, length)))
+    };
+    let blank_memo = || match length {
+        4 => vec![0; 4],
+        _ => vec![b' '; length]
+    };
+    let mut write_memo = |data: &[u8], is_text: bool| match (memo.as_mut(), data.is_empty()) {
+        (_, true) => Ok(blank_memo()),
+        (Some(memo), false) => Ok(memo_pointer(memo.write_memo(data, is_text)?, length)),
+        (None, false) => Err(invalid("the database has no memo file".to_string()))
+    };
+    // Autoincrement fields hold plain integers, and dBASE 7 timestamps are datetimes
+    let type_code = match field.type_code {
+        b'+' => b'I',
+        b'@' => b'T',
+        r => r
+    };
+    match (type_code, value) {
+        // Values shorter than a variable length field store their length in its last byte
+        (b'V', _) | (b'Q', _) => {
+            let mut bytes = varlength_content(field, value, options)?;
+            let content_length = bytes.len();
+            if content_length < length {
+                bytes.resize(length - 1, b' ');
+                bytes.push(content_length as u8);
+            }
+            Ok(bytes)
+        },
+        (b'M', None) | (b'G', None) | (b'P', None)
+        | (b'M', Some(FieldValue::Null)) | (b'G', Some(FieldValue::Null)) | (b'P', Some(FieldValue::Null)) => Ok(blank_memo()),
+        // Binary fields are left empty with zeros, as spaces would read as a value
+        (b'I', None) | (b'T', None) | (b'B', None) | (b'O', None) | (b'Y', None) => Ok(vec![0; length]),
+        (b'I', Some(FieldValue::Null)) | (b'T', Some(FieldValue::Null)) | (b'B', Some(FieldValue::Null)) | (b'O', Some(FieldValue::Null)) | (b'Y', Some(FieldValue::Null)) => Ok(vec![0; length]),
+        (_, None) | (_, Some(FieldValue::Null)) => Ok(vec![b' '; length]),
+        (b'C', Some(FieldValue::Text(r))) => {
+            let mut bytes = encode(r)?;
+            if bytes.len() > length && options.overflow == Overflow::Error {
+                return Err(invalid(format!("{:?} does not fit in {} bytes", r, length)));
+            }
+            bytes.truncate(length);
+            match options.padding {
+                Padding::Right => bytes.resize(length, b' '),
+                Padding::Left => {
+                    let mut padded = vec![b' '; length - bytes.len()];
+                    padded.append(&mut bytes);
+                    bytes = padded;
+                }
+            }
+            Ok(bytes)
+        },
+        // Binary character fields are read back whole, see `FieldDescriptor::is_binary`
+        (b'C', Some(FieldValue::Binary(r))) => {
+            if r.len() > length && options.overflow == Overflow::Error {
+                return Err(invalid(format!("the value is {} bytes long, more than the {} of the field", r.len(), length)));
+            }
+            let mut bytes = r.clone();
+            bytes.resize(length, b' ');
+            Ok(bytes)
+        },
+        (b'N', Some(FieldValue::Numeric(r))) | (b'F', Some(FieldValue::Numeric(r)))
+        | (b'N', Some(FieldValue::Float(r))) | (b'F', Some(FieldValue::Float(r)))
+        | (b'N', Some(FieldValue::Double(r))) | (b'F', Some(FieldValue::Double(r))) => {
+            decimals(loses_decimals(*r, decimal_count))?;
+            numeric(format!("{:.*}", decimal_count, r))
+        },
+        (b'N', Some(FieldValue::Overflow)) | (b'F', Some(FieldValue::Overflow)) => Ok(vec![b'*'; length]),
+        (b'N', Some(FieldValue::Integer(r))) | (b'F', Some(FieldValue::Integer(r))) => numeric(format!("{:.*}", decimal_count, f64::from(*r))),
+        #[cfg(feature = "rust_decimal")]
+        (b'N', Some(FieldValue::Decimal(r))) | (b'F', Some(FieldValue::Decimal(r))) => {
+            decimals(r.normalize().scale() as usize > decimal_count)?;
+            let mut r = *r;
+            r.rescale(field.decimal_count.into());
+            numeric(r.to_string())
+        },
+        (b'L', Some(FieldValue::Boolean(r))) => Ok(vec![match r {
+            Some(true) => b'Y',
+            Some(false) => b'N',
+            None => b'?'
+        }]),
+        (b'D', Some(FieldValue::Date(r))) => match r.year() {
+            0..=9999 => Ok(r.format("%Y%m%d").to_string().into_bytes()),
+            year => Err(invalid(format!("the year {} cannot be written in 4 digits", year)))
+        },
+        // The Julian day number, then the milliseconds since midnight
+        (b'T', Some(FieldValue::DateTime(r))) => {
+            let mut bytes = vec![];
+            bytes.write_i32::<LittleEndian>(r.num_days_from_ce() + JULIAN_DAY_OFFSET)?;
+            bytes.write_u32::<LittleEndian>(r.num_seconds_from_midnight() * 1000 + r.timestamp_subsec_millis())?;
+            Ok(bytes)
+        },
+        (b'T', Some(FieldValue::Date(r))) => {
+            let mut bytes = vec![];
+            bytes.write_i32::<LittleEndian>(r.num_days_from_ce() + JULIAN_DAY_OFFSET)?;
+            bytes.write_u32::<LittleEndian>(0)?;
+            Ok(bytes)
+        },
+        (b'I', Some(FieldValue::Integer(r))) => Ok(r.to_le_bytes().to_vec()),
+        (b'I', Some(FieldValue::Numeric(r))) => match r.fract() == 0.0 && *r >= f64::from(i32::MIN) && *r <= f64::from(i32::MAX) {
+            true => Ok((*r as i32).to_le_bytes().to_vec()),
+            false => Err(invalid(format!("{} is not a 32 bit integer", r)))
+        },
+        (b'B', Some(FieldValue::Numeric(r))) | (b'B', Some(FieldValue::Float(r))) | (b'B', Some(FieldValue::Double(r))) => Ok(r.to_le_bytes().to_vec()),
+        (b'B', Some(FieldValue::Integer(r))) => Ok(f64::from(*r).to_le_bytes().to_vec()),
+        (b'O', Some(FieldValue::Numeric(r))) | (b'O', Some(FieldValue::Float(r))) | (b'O', Some(FieldValue::Double(r))) => Ok(dbase7_double(*r).to_vec()),
+        (b'O', Some(FieldValue::Integer(r))) => Ok(dbase7_double(f64::from(*r)).to_vec()),
+        // Currencies are stored as a number of ten thousandths
+        (b'Y', Some(FieldValue::Numeric(r))) | (b'Y', Some(FieldValue::Float(r))) | (b'Y', Some(FieldValue::Double(r))) => match (r * 10000.0).round() {
+            units if units.abs() < i64::MAX as f64 => Ok((units as i64).to_le_bytes().to_vec()),
+            _ => Err(invalid(format!("{} does not fit in a currency", r)))
+        },
+        (b'Y', Some(FieldValue::Currency(r))) => Ok(r.to_le_bytes().to_vec()),
+        (b'Y', Some(FieldValue::Integer(r))) => Ok((i64::from(*r) * 10000).to_le_bytes().to_vec()),
+        (b'M', Some(FieldValue::Text(r))) => write_memo(&encode(r)?, true),
+        (b'M', Some(FieldValue::Unknown(r))) | (b'G', Some(FieldValue::Unknown(r)))
+        | (b'M', Some(FieldValue::Binary(r))) | (b'G', Some(FieldValue::Binary(r)))
+        | (b'P', Some(FieldValue::Unknown(r))) | (b'P', Some(FieldValue::Binary(r))) => write_memo(r, false),
+        (type_code, Some(value)) => Err(invalid(format!("{:?} cannot be written to a field of type {}", value, type_code as char)))
+    }
+}
+
+#[test]
+fn foxpro_memo_layout() {
+    use std::io::Read;
+
+    let path = std::env::temp_dir().join("dbase_parser_foxpro_memo_layout.fpt");
+    let mut writer = FoxProMemoWriter::create(File::create(&path).unwrap()).unwrap();
+    assert_eq!(writer.write_memo(b"A note", true).unwrap(), 8);
+    // 8 bytes of block header and 100 of data take two blocks
+    assert_eq!(writer.write_memo(&[0x55; 100], false).unwrap(), 9);
+    assert_eq!(writer.write_memo(b"", true).unwrap(), 11);
+
+    let mut bytes = vec![];
+    File::open(&path).unwrap().read_to_end(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 12 * 64);
+    // The next free block and the block size, both big-endian
+    assert_eq!(bytes[0..8], [0, 0, 0, 12, 0, 0, 0, 64]);
+    assert_eq!(bytes[512..526], [0, 0, 0, 1, 0, 0, 0, 6, b'A', b' ', b'n', b'o', b't', b'e']);
+    assert_eq!(bytes[576..584], [0, 0, 0, 0, 0, 0, 0, 100]);
+    assert_eq!(bytes[584..684], [0x55; 100][..]);
+    assert_eq!(bytes[704..712], [0, 0, 0, 1, 0, 0, 0, 0]);
+
+    // Reopening carries on after the last block
+    let mut writer = FoxProMemoWriter::open(&path, 0).unwrap();
+    assert_eq!(writer.write_memo(b"Another note", true).unwrap(), 12);
+}
+
+#[test]
+fn dbase_memo_layout() {
+    use std::io::Read;
+
+    let path = std::env::temp_dir().join("dbase_parser_dbase_memo_layout.dbt");
+    let mut writer = DBaseMemoWriter::create(File::create(&path).unwrap(), false).unwrap();
+    assert_eq!(writer.write_memo(b"A note", true).unwrap(), 1);
+    // 511 bytes of data and the two markers take two blocks
+    assert_eq!(writer.write_memo(&[b'x'; 511], true).unwrap(), 2);
+
+    let mut bytes = vec![];
+    File::open(&path).unwrap().read_to_end(&mut bytes).unwrap();
+    assert_eq!(bytes.len(), 4 * 512);
+    assert_eq!(bytes[0..4], [4, 0, 0, 0]);
+    assert_eq!(bytes[16], 0x03);
+    assert_eq!(bytes[512..520], *b"A note\x1a\x1a");
+    assert_eq!(bytes[1535..1537], [0x1a, 0x1a]);
+
+    // A header pointing before the end of the file does not overwrite the last memo
+    fs::OpenOptions::new().write(true).open(&path).unwrap().write_all(&[2, 0, 0, 0]).unwrap();
+    let mut writer = DBaseMemoWriter::open(&path, false, 0).unwrap();
+    assert_eq!(writer.write_memo(b"Another note", true).unwrap(), 4);
+}
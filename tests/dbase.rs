@@ -1,15 +1,15 @@
 #[cfg(test)]
 mod tests {
     use dbase_parser::fields::FieldValue;
-    use dbase_parser::open;
+    use dbase_parser::{open, DbaseError, OpenOptions};
     use chrono::Utc;
     use chrono::offset::TimeZone;
-    use dbase_parser::header::Record;
+    use dbase_parser::header::{FieldKind, Record, RecordStatus, RecordVisitor};
 
     #[test]
     fn parse_file_gis() {
         let db = open("tests/reference_gis.dbf").expect("Could not open file");
-        let record = db.into_iter().next().expect("No first record");
+        let record = db.into_iter().next().expect("No first record").unwrap();
         assert_eq!(record.get("NAME_4").unwrap(), &FieldValue::Text("Clervaux".to_string()));
         assert_eq!(record.get("NAME_3").unwrap(), &FieldValue::Text("Clervaux".to_string()));
         assert_eq!(record.get("NAME_2").unwrap(), &FieldValue::Text("Clervaux".to_string()));
@@ -28,15 +28,15 @@ mod tests {
         let db = r.unwrap();
 
         let mut record_iter = db.into_iter();
-        let record = record_iter.next().expect("Expected one record");
+        let record = record_iter.next().expect("Expected one record").unwrap();
         assert_eq!(record.get("ID").unwrap(), &FieldValue::Numeric(20.0));
         assert_eq!(record.get("Name").unwrap(), &FieldValue::Text("srenauld".to_string()));
         assert_eq!(record.get("created_at").unwrap(), &FieldValue::DateTime(Utc.ymd(2019, 09, 04).and_hms(11, 6, 0)));
         assert_eq!(record.get("join").unwrap(), &FieldValue::Date(Utc.ymd(1999, 09, 03)));
         assert_eq!(record.get("active").unwrap(), &FieldValue::Boolean(Some(true)));
         assert_eq!(record.get("transfers").expect("No transfers"), &FieldValue::Integer(5));
-        // assert_eq!(record.get("notes").expect("No notes"), &FieldValue::Text("This is a note.".to_string()));
-        let record2 = record_iter.next().expect("Expected two records");
+        assert_eq!(record.get("notes").expect("No notes"), &FieldValue::Text("This is a note.".to_string()));
+        let record2 = record_iter.next().expect("Expected two records").unwrap();
         assert_eq!(record2.get("ID").unwrap(), &FieldValue::Numeric(34.0));
         assert_eq!(record2.get("Name").unwrap(), &FieldValue::Text("Another".to_string()));
         assert_eq!(record2.get("created_at").unwrap(), &FieldValue::DateTime(Utc.ymd(2019, 09, 04).and_hms(11, 40, 0)));
@@ -51,7 +51,7 @@ mod tests {
         let db = r.unwrap();
 
         let mut record_iter = db.into_iter();
-        let record = record_iter.next().expect("Expected one record in dbase III");
+        let record = record_iter.next().expect("Expected one record in dbase III").unwrap();
         assert_eq!(record.get("ID").unwrap(), &FieldValue::Numeric(87.0));
         assert_eq!(record.get("DESC").expect("No notes"), &FieldValue::Text("Our Original assortment...a little taste of heaven for everyone.  Let us
 select a special assortment of our chocolate and pastel favorites for you.
@@ -69,14 +69,2709 @@ Grand Orange, Plum Squares, Milk chocolate squares, and Raspberry Blanc.".to_str
         let db = r.unwrap();
 
         let mut record_iter = db.into_iter();
-        let rec_1 = record_iter.next().expect("Expected one records");
+        let rec_1 = record_iter.next().expect("Expected one records").unwrap();
         assert_eq!(rec_1.get("id").unwrap(), &FieldValue::Integer(1));
-        assert_eq!(rec_1.get("name").unwrap(), &FieldValue::Text("".to_string()));
-        assert_eq!(rec_1.get("reg_date").unwrap(), &FieldValue::Date(Utc.ymd(1900, 01, 01)));
+        assert_eq!(rec_1.get("name").unwrap(), &FieldValue::Null);
+        assert_eq!(rec_1.get("reg_date").unwrap(), &FieldValue::Null);
+        assert_eq!(rec_1.get("is_active").unwrap(), &FieldValue::Null);
+        assert_eq!(rec_1.get("salary").unwrap(), &FieldValue::Null);
+        // The memo pointer is 0: there is no memo
+        assert_eq!(rec_1.get("desc").unwrap(), &FieldValue::Text("".to_string()));
 
-        let rec_2 = record_iter.next().expect("Expected two records");
+        let rec_2 = record_iter.next().expect("Expected two records").unwrap();
         assert_eq!(rec_2.get("id").unwrap(), &FieldValue::Integer(2));
         assert_eq!(rec_2.get("name").unwrap(), &FieldValue::Text("jhon".to_string()));
 
+        let rec_6 = record_iter.nth(3).expect("Expected six records").unwrap();
+        assert_eq!(rec_6.get("reg_date").unwrap(), &FieldValue::Date(Utc.ymd(2019, 9, 18)));
+        assert_eq!(rec_6.get("salary").unwrap(), &FieldValue::Numeric(23222.0));
+
+    }
+
+    #[test]
+    fn visit_sums_numeric_column() {
+        struct Sum {
+            total: f64,
+            records: usize
+        }
+        impl RecordVisitor for Sum {
+            fn field(&mut self, name: &str, value: FieldValue) {
+                if let ("ID_0", FieldValue::Numeric(r)) = (name, value) {
+                    self.total += r;
+                }
+            }
+            fn end_record(&mut self) {
+                self.records += 1;
+            }
+        }
+
+        let db = open("tests/reference_gis.dbf").unwrap();
+        let sum = db.visit(Sum { total: 0.0, records: 0 }).unwrap();
+        assert_eq!(sum.records, 139);
+        assert_eq!(sum.total, 18209.0);
+
+        // Deleted records are skipped, as when iterating
+        let sum = open("tests/deleted_records.dbf").unwrap().visit(Sum { total: 0.0, records: 0 }).unwrap();
+        assert_eq!(sum.records, 3);
+        let mut db = open("tests/deleted_records.dbf").unwrap();
+        db.include_deleted(true);
+        assert_eq!(db.visit(Sum { total: 0.0, records: 0 }).unwrap().records, 5);
+    }
+
+    #[test]
+    fn encrypted_file_is_refused() {
+        assert!(!open("tests/reference_fpt.dbf").unwrap().header.is_encrypted());
+        match open("tests/encrypted.dbf") {
+            Err(DbaseError::Encrypted) => (),
+            r => panic!("Expected an encryption error, got {:?}", r.map(|db| db.header))
+        }
+    }
+
+    #[test]
+    fn transaction_incomplete_flag() {
+        assert!(!open("tests/reference_fpt.dbf").unwrap().header.transaction_incomplete());
+        assert!(open("tests/transaction_incomplete.dbf").unwrap().header.transaction_incomplete());
+    }
+
+    #[test]
+    fn read_records_range() {
+        let mut db = open("tests/five_records.dbf").unwrap();
+        let names:Vec<FieldValue> = db.records_range(2, 4)
+            .map(|r| r.unwrap().fields.remove("NAME").unwrap())
+            .collect();
+        assert_eq!(names, vec![FieldValue::Text("three".to_string()), FieldValue::Text("four".to_string())]);
+
+        match db.records_range(4, 6).next() {
+            Some(Err(DbaseError::InvalidRange { start: 4, end: 6, record_count: 5 })) => (),
+            r => panic!("Expected an invalid range error, got {:?}", r)
+        };
+    }
+
+    #[test]
+    fn registered_field_type_handles_unknown_byte() {
+        use dbase_parser::fields::FieldType;
+        use dbase_parser::header::{Database, FieldDescriptorBuilder};
+        use std::io;
+
+        // Two little-endian u16s, summed
+        #[derive(Debug)]
+        struct FieldTypeX;
+        impl FieldType for FieldTypeX {
+            fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+                Ok(FieldValue::Integer((data[0] as i32 | (data[1] as i32) << 8) + (data[2] as i32 | (data[3] as i32) << 8)))
+            }
+        }
+
+        assert!(open("tests/custom_field_type.dbf").is_err());
+
+        let db = OpenOptions::new().allow_unknown_field_types(true).open("tests/custom_field_type.dbf").unwrap();
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("CODE").unwrap(), &FieldValue::Unknown(vec![1, 0, 2, 0]));
+
+        let db = OpenOptions::new()
+            .allow_unknown_field_types(true)
+            .register_field_type(b'X', Box::new(FieldTypeX))
+            .open("tests/custom_field_type.dbf").unwrap();
+        let mut records = db.into_iter();
+        assert_eq!(records.next().unwrap().unwrap().get("CODE").unwrap(), &FieldValue::Integer(3));
+        assert_eq!(records.next().unwrap().unwrap().get("CODE").unwrap(), &FieldValue::Integer(7));
+
+        // Without an encoder of its own the type cannot be written
+        let code = FieldDescriptorBuilder::new("CODE", b'X').field_type(Box::new(FieldTypeX)).length(4).build().unwrap();
+        let path = std::env::temp_dir().join("dbase_parser_custom_no_encode.dbf");
+        let mut writer = dbase_parser::create(path.to_str().unwrap(), vec![code]).unwrap();
+        let mut values = std::collections::HashMap::new();
+        values.insert("CODE".to_string(), FieldValue::Integer(3));
+        match writer.write_record(&values) {
+            Err(DbaseError::InvalidFieldValue { field, message }) => {
+                assert_eq!(field, "CODE");
+                assert_eq!(message, "no encoder for type X");
+            },
+            other => panic!("expected an invalid field value, got {:?}", other)
+        }
+    }
+
+    #[test]
+    fn raw_header_bytes_match_file() {
+        let mut db = open("tests/reference_gis.dbf").unwrap();
+        let bytes = db.raw_header_bytes().unwrap();
+        assert_eq!(bytes.len(), db.header.header_size() as usize);
+        assert_eq!(bytes[..], std::fs::read("tests/reference_gis.dbf").unwrap()[..bytes.len()]);
+        assert_eq!(bytes.last(), Some(&0x0d));
+        // Reading records afterwards is unaffected
+        assert_eq!(db.into_iter().next().unwrap().unwrap().get("ISO").unwrap(), &FieldValue::Text("LUX".to_string()));
+    }
+
+    #[test]
+    fn open_missing_file_or_directory() {
+        use std::path::PathBuf;
+
+        match open("tests/does_not_exist.dbf") {
+            Err(DbaseError::NotFound(path)) => assert_eq!(path, PathBuf::from("tests/does_not_exist.dbf")),
+            r => panic!("Expected a not found error, got {:?}", r.map(|db| db.header))
+        }
+        match open("tests") {
+            Err(DbaseError::IsADirectory(path)) => assert_eq!(path, PathBuf::from("tests")),
+            r => panic!("Expected a directory error, got {:?}", r.map(|db| db.header))
+        }
+    }
+
+    #[test]
+    fn header_year_pivot() {
+        let db = open("tests/two_digit_year.dbf").unwrap();
+        assert_eq!(db.header.last_update, Utc.ymd(1919, 10, 1));
+        let db = OpenOptions::new().year_pivot(19).open("tests/two_digit_year.dbf").unwrap();
+        assert_eq!(db.header.last_update, Utc.ymd(1919, 10, 1));
+        let db = OpenOptions::new().year_pivot(20).open("tests/two_digit_year.dbf").unwrap();
+        assert_eq!(db.header.last_update, Utc.ymd(2019, 10, 1));
+        // Years stored from 1900 are past any pivot
+        let db = OpenOptions::new().year_pivot(50).open("tests/five_records.dbf").unwrap();
+        assert_eq!(db.header.last_update, Utc.ymd(2019, 10, 1));
+    }
+
+    #[test]
+    fn invalid_header_dates() {
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_header_date.dbf");
+        let mut bytes = fs::read("tests/five_records.dbf").unwrap();
+        for (date, year) in [([0, 0, 0], 1900), ([119, 13, 32], 2019), ([120, 2, 30], 2020)] {
+            bytes[1..4].copy_from_slice(&date);
+            fs::write(&path, &bytes).unwrap();
+            let db = open(path.to_str().unwrap()).unwrap();
+            assert_eq!(db.header.last_update.naive_utc(), chrono::NaiveDate::from_ymd_opt(year, 1, 1).unwrap());
+            assert_eq!(db.into_iter().count(), 5);
+        }
+    }
+
+    #[test]
+    fn records_with_status() {
+        let db = open("tests/deleted_records.dbf").unwrap();
+        let records:Vec<(RecordStatus, Record)> = db.records_with_status().map(|r| r.unwrap()).collect();
+        let statuses:Vec<RecordStatus> = records.iter().map(|(status, _)| *status).collect();
+        assert_eq!(statuses, vec![
+            RecordStatus::Active,
+            RecordStatus::Deleted,
+            RecordStatus::Active,
+            RecordStatus::Deleted,
+            RecordStatus::Active
+        ]);
+        assert_eq!(records[1].1.get("NAME").unwrap(), &FieldValue::Text("two".to_string()));
+        assert_eq!(records[2].1.get("ID").unwrap(), &FieldValue::Numeric(3.0));
+    }
+
+    #[test]
+    fn database_container_backlink() {
+        let db = open("tests/dbc_bound.dbf").unwrap();
+        assert_eq!(db.header.database_container(), Some("..\\data\\sales.dbc"));
+        assert_eq!(db.into_iter().next().unwrap().unwrap().get("ID").unwrap(), &FieldValue::Integer(1));
+        // A free table has an empty backlink
+        assert_eq!(open("tests/reference_fpt.dbf").unwrap().header.database_container(), None);
+    }
+
+    #[test]
+    fn from_open_file() {
+        use dbase_parser::header::Database;
+        use std::fs::File;
+        use std::path::Path;
+
+        let file = File::open("tests/reference_fpt.dbf").unwrap();
+        let db = Database::from_file(file, Some(Path::new("tests/reference_fpt.dbf"))).unwrap();
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("Name").unwrap(), &FieldValue::Text("srenauld".to_string()));
+        assert_eq!(record.get("notes").unwrap(), &FieldValue::Text("This is a note.".to_string()));
+
+        let file = File::open("tests/five_records.dbf").unwrap();
+        let db = Database::from_file(file, None).unwrap();
+        assert_eq!(db.into_iter().next().unwrap().unwrap().get("NAME").unwrap(), &FieldValue::Text("one".to_string()));
+    }
+
+    #[test]
+    fn header_limits() {
+        use dbase_parser::error::Limit;
+        use dbase_parser::header::Database;
+        use std::io::Cursor;
+
+        // A header declaring the largest possible header size, with nothing behind it
+        let mut bytes = vec![0x03, 119, 10, 1, 0, 0, 0, 0, 0xff, 0xff, 0x10, 0x00];
+        bytes.resize(32, 0);
+
+        let mut options = OpenOptions::new();
+        options.max_fields(16);
+        match Database::parse_with_options("limits.dbf", Cursor::new(bytes.clone()), options) {
+            Err(DbaseError::LimitExceeded { limit: Limit::FieldCount, declared: 2046, max: 16 }) => (),
+            r => panic!("Expected a field count limit error, got {:?}", r.map(|db| db.header))
+        }
+        let mut options = OpenOptions::new();
+        options.max_record_size(8);
+        match Database::parse_with_options("limits.dbf", Cursor::new(bytes), options) {
+            Err(DbaseError::LimitExceeded { limit: Limit::RecordSize, declared: 16, max: 8 }) => (),
+            r => panic!("Expected a record size limit error, got {:?}", r.map(|db| db.header))
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn record_to_json_map() {
+        use serde_json::json;
+
+        let record = open("tests/reference_fpt.dbf").unwrap().into_iter().next().unwrap().unwrap();
+        let map = record.to_json_map();
+        assert_eq!(map.len(), 7);
+        assert_eq!(map["ID"], json!(20.0));
+        assert_eq!(map["Name"], json!("srenauld"));
+        assert_eq!(map["created_at"], json!("2019-09-04T11:06:00+00:00"));
+        assert_eq!(map["join"], json!("1999-09-03"));
+        assert_eq!(map["active"], json!(true));
+        assert_eq!(map["transfers"], json!(5));
+        assert_eq!(map["notes"], json!("This is a note."));
+
+        let db = OpenOptions::new().allow_unknown_field_types(true).open("tests/custom_field_type.dbf").unwrap();
+        let map = db.into_iter().next().unwrap().unwrap().to_json_map();
+        assert_eq!(map["CODE"], json!("AQACAA=="));
+    }
+
+    #[test]
+    fn mdx_index_flag_and_file() {
+        use std::path::Path;
+
+        let db = open("tests/with_mdx.dbf").unwrap();
+        assert!(db.header.has_mdx_index());
+        assert_eq!(db.mdx_index_path(), Some(Path::new("tests/with_mdx.mdx")));
+
+        let db = open("tests/five_records.dbf").unwrap();
+        assert!(!db.header.has_mdx_index());
+        assert_eq!(db.mdx_index_path(), None);
+    }
+
+    #[test]
+    fn get_by_index_matches_get() {
+        let db = open("tests/reference_fpt.dbf").unwrap();
+        let index = db.header.field_index("created_at").unwrap();
+        assert_eq!(index, 2);
+        assert_eq!(db.header.field_index("missing"), None);
+
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get_by_index(index), record.get("created_at"));
+        assert_eq!(record.get_by_index(0), record.get("ID"));
+        assert_eq!(record.get_by_index(6), record.get("notes"));
+        assert_eq!(record.get_by_index(7), None);
+    }
+
+    #[test]
+    fn implied_decimal_numeric() {
+        let amounts = |db: dbase_parser::header::Database| -> Vec<FieldValue> {
+            db.into_iter().map(|r| r.unwrap().fields.remove("AMOUNT").unwrap()).collect()
+        };
+        assert_eq!(amounts(open("tests/implied_decimal.dbf").unwrap()), vec![
+            FieldValue::Numeric(12345.0),
+            FieldValue::Numeric(-5.0),
+            FieldValue::Numeric(123.45)
+        ]);
+        assert_eq!(amounts(OpenOptions::new().implied_decimal(true).open("tests/implied_decimal.dbf").unwrap()), vec![
+            FieldValue::Numeric(123.45),
+            FieldValue::Numeric(-0.05),
+            FieldValue::Numeric(123.45)
+        ]);
+    }
+
+    #[test]
+    fn reserved_header_bytes() {
+        let db = open("tests/reference_fpt.dbf").unwrap();
+        let file = std::fs::read("tests/reference_fpt.dbf").unwrap();
+        assert_eq!(db.header.reserved_bytes()[..], file[12..32]);
+        // Visual FoxPro table flags (0x02: has a memo) and code page (0x03: Windows-1252)
+        assert_eq!(db.header.reserved_bytes()[16..18], [0x02, 0x03]);
+        assert!(!db.header.has_mdx_index());
+    }
+
+    #[test]
+    fn exclusive_lock_excludes_other_handles() {
+        let first = open("tests/deleted_records.dbf").unwrap();
+        let second = open("tests/deleted_records.dbf").unwrap();
+        first.lock_exclusive().unwrap();
+        match second.try_lock_exclusive() {
+            Err(DbaseError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::WouldBlock),
+            r => panic!("Expected the lock to be held, got {:?}", r)
+        }
+        first.unlock().unwrap();
+        second.try_lock_exclusive().unwrap();
+        second.unlock().unwrap();
+
+        let locked = OpenOptions::new().locking(true).open("tests/deleted_records.dbf").unwrap();
+        assert!(first.try_lock_exclusive().is_err());
+        drop(locked);
+        first.try_lock_exclusive().unwrap();
+    }
+
+    #[test]
+    fn index_by_field() {
+        let mut db = open("tests/reference_gis.dbf").unwrap();
+        let index = db.index_by("ISO").unwrap();
+        assert_eq!(index.len(), 1);
+        let last = db.records_range(138, 139).next().unwrap().unwrap();
+        let lux = &index[&FieldValue::Text("LUX".to_string())];
+        assert_eq!(lux.get("ISO"), Some(&FieldValue::Text("LUX".to_string())));
+        assert_eq!(lux.get("NAME_4"), last.get("NAME_4"));
+
+        let index = db.index_by("ID_4").unwrap();
+        assert_eq!(index[&FieldValue::Numeric(1.0)].get("NAME_4"), Some(&FieldValue::Text("Clervaux".to_string())));
+
+        match db.index_by("MISSING") {
+            Err(DbaseError::FieldNotFound(name)) => assert_eq!(name, "MISSING"),
+            r => panic!("Expected a missing field error, got {:?}", r.map(|r| r.len()))
+        }
+    }
+
+    #[test]
+    fn deleted_count() {
+        let mut db = open("tests/deleted_records.dbf").unwrap();
+        assert_eq!(db.deleted_count().unwrap(), 2);
+        assert_eq!(db.deleted_count().unwrap(), 2);
+        assert_eq!(db.header.record_count, 5);
+
+        let mut db = open("tests/reference_gis.dbf").unwrap();
+        assert_eq!(db.deleted_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn write_memo_fields() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+
+        let note = "A note long enough to span several memo blocks. ".repeat(40);
+        for &(extension, memo_length) in &[("dbt", 10), ("fpt", 4)] {
+            let path = std::env::temp_dir().join(format!("dbase_parser_write_memo_{}.dbf", extension));
+            let fields = vec![
+                FieldDescriptor::new("ID", b'N', 3, 0).unwrap(),
+                FieldDescriptor::new("NOTES", b'M', memo_length, 0).unwrap()
+            ];
+            let mut writer = DbfWriter::create(path.to_str().unwrap(), fields).unwrap();
+            let mut values = HashMap::new();
+            values.insert("ID".to_string(), FieldValue::Numeric(1.0));
+            values.insert("NOTES".to_string(), FieldValue::Text(note.clone()));
+            writer.write_record(&values).unwrap();
+            values.insert("ID".to_string(), FieldValue::Numeric(2.0));
+            values.insert("NOTES".to_string(), FieldValue::Text("A short note".to_string()));
+            writer.write_record(&values).unwrap();
+            values.insert("ID".to_string(), FieldValue::Numeric(3.0));
+            values.remove("NOTES");
+            writer.write_record(&values).unwrap();
+            writer.finish().unwrap();
+            assert!(path.with_extension(extension).is_file());
+
+            let records:Vec<Record> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+            assert_eq!(records.len(), 3);
+            assert_eq!(records[0].get("NOTES"), Some(&FieldValue::Text(note.clone())));
+            assert_eq!(records[1].get("NOTES"), Some(&FieldValue::Text("A short note".to_string())));
+            assert_eq!(records[2].get("NOTES"), Some(&FieldValue::Text("".to_string())));
+            assert_eq!(records[2].get("ID"), Some(&FieldValue::Numeric(3.0)));
+        }
+    }
+
+    #[test]
+    fn copy_records_between_databases() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::FieldDescriptor;
+        use std::fs;
+
+        // Same layout: the records are copied as is
+        let path = std::env::temp_dir().join("dbase_parser_copy_records.dbf");
+        let fields = vec![
+            FieldDescriptor::new("ID", b'N', 3, 0).unwrap(),
+            FieldDescriptor::new("NAME", b'C', 10, 0).unwrap()
+        ];
+        let mut writer = DbfWriter::create(path.to_str().unwrap(), fields).unwrap();
+        let source = open("tests/five_records.dbf").unwrap();
+        let header_size = source.header.header_size() as usize;
+        source.copy_records_to(&mut writer).unwrap();
+        writer.finish().unwrap();
+
+        let rows = 5 * 14;
+        let expected = fs::read("tests/five_records.dbf").unwrap();
+        let copied = fs::read(&path).unwrap();
+        let copied_header_size = open(path.to_str().unwrap()).unwrap().header.header_size() as usize;
+        assert_eq!(copied[copied_header_size..copied_header_size + rows], expected[header_size..header_size + rows]);
+
+        // Another layout: the records are parsed and written again
+        let path = std::env::temp_dir().join("dbase_parser_copy_records_reordered.dbf");
+        let fields = vec![
+            FieldDescriptor::new("NAME", b'C', 12, 0).unwrap(),
+            FieldDescriptor::new("ID", b'N', 5, 1).unwrap()
+        ];
+        let mut writer = DbfWriter::create(path.to_str().unwrap(), fields).unwrap();
+        open("tests/deleted_records.dbf").unwrap().copy_records_to(&mut writer).unwrap();
+        writer.finish().unwrap();
+
+        let source:Vec<(RecordStatus, Record)> = open("tests/deleted_records.dbf").unwrap().records_with_status().map(|r| r.unwrap()).collect();
+        let copied:Vec<(RecordStatus, Record)> = open(path.to_str().unwrap()).unwrap().records_with_status().map(|r| r.unwrap()).collect();
+        assert_eq!(copied.len(), source.len());
+        for ((source_status, source), (copied_status, copied)) in source.iter().zip(copied.iter()) {
+            assert_eq!(source_status, copied_status);
+            assert_eq!(source.get("ID"), copied.get("ID"));
+            assert_eq!(source.get("NAME"), copied.get("NAME"));
+        }
+    }
+
+    #[test]
+    fn memo_without_memo_file() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_memo_without_file.dbf");
+        let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = DbfWriter::create(path.to_str().unwrap(), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("NOTES".to_string(), FieldValue::Text("A note".to_string()));
+        writer.write_record(&values).unwrap();
+        writer.write_record(&HashMap::new()).unwrap();
+        writer.finish().unwrap();
+        fs::remove_file(path.with_extension("dbt")).unwrap();
+
+        let records:Vec<Record> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("NOTES"), Some(&FieldValue::Unknown(b"         1".to_vec())));
+        assert_eq!(records[1].get("NOTES"), Some(&FieldValue::Text("".to_string())));
+    }
+
+    #[test]
+    fn deleted_records_are_skipped() {
+        let db = open("tests/deleted_records.dbf").unwrap();
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 3);
+        assert!(records.iter().all(|r| !r.deleted));
+        let ids:Vec<&FieldValue> = records.iter().map(|r| r.get("ID").unwrap()).collect();
+        assert_eq!(ids, vec![&FieldValue::Numeric(1.0), &FieldValue::Numeric(3.0), &FieldValue::Numeric(5.0)]);
+        assert_eq!(records[1].get("NAME"), Some(&FieldValue::Text("three".to_string())));
+
+        let mut db = open("tests/deleted_records.dbf").unwrap();
+        db.include_deleted(true);
+        let deleted:Vec<bool> = db.into_iter().map(|r| r.unwrap().deleted).collect();
+        assert_eq!(deleted, vec![false, true, false, true, false]);
+    }
+
+    #[test]
+    fn iterator_reports_errors() {
+        // The second record holds an invalid date
+        let db = open("tests/invalid_date.dbf").unwrap();
+        let records:Vec<Result<Record, DbaseError>> = db.into_iter().collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].as_ref().unwrap().get("NAME"), Some(&FieldValue::Text("first".to_string())));
+        assert!(records[1].is_err());
+        assert_eq!(records[2].as_ref().unwrap().get("NAME"), Some(&FieldValue::Text("third".to_string())));
+
+        let db = open("tests/invalid_date.dbf").unwrap();
+        assert_eq!(db.into_iter().filter_map(Result::ok).count(), 2);
+    }
+
+    #[test]
+    fn code_page_from_language_driver() {
+        let db = open("tests/code_page_1251.dbf").unwrap();
+        assert_eq!(db.header.encoding(), Some(encoding_rs::WINDOWS_1251));
+        let cities:Vec<FieldValue> = db.into_iter().map(|r| r.unwrap().fields.remove("CITY").unwrap()).collect();
+        assert_eq!(cities, vec![FieldValue::Text("Москва".to_string()), FieldValue::Text("Caf?".to_string())]);
+
+        let db = open("tests/code_page_1252.dbf").unwrap();
+        assert_eq!(db.into_iter().next().unwrap().unwrap().get("CITY"), Some(&FieldValue::Text("Pétange".to_string())));
+
+        // The language driver is blank, so this UTF-8 file is read as Windows-1252 unless told otherwise
+        let mut db = open("tests/reference_gis.dbf").unwrap();
+        assert_eq!(db.header.encoding(), Some(encoding_rs::WINDOWS_1252));
+        let record = db.records_range(88, 89).next().unwrap().unwrap();
+        assert_eq!(record.get("NAME_4"), Some(&FieldValue::Text("PÃ©tange".to_string())));
+        db.with_encoding(encoding_rs::UTF_8);
+        let record = db.records_range(88, 89).next().unwrap().unwrap();
+        assert_eq!(record.get("NAME_4"), Some(&FieldValue::Text("Pétange".to_string())));
+    }
+
+    #[test]
+    fn random_access_by_index() {
+        let mut db = open("tests/deleted_records.dbf").unwrap();
+        let record = db.record(4).unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("five".to_string())));
+        let record = db.record(1).unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("two".to_string())));
+        assert!(record.deleted);
+        assert!(db.record(5).unwrap().is_none());
+        assert!(db.record(u32::MAX).unwrap().is_none());
+    }
+
+    #[test]
+    fn foxpro_double_and_currency() {
+        let db = open("tests/foxpro_numeric_types.dbf").unwrap();
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("RATIO"), Some(&FieldValue::Double(6.02214)));
+        assert_eq!(records[0].get("PRICE"), Some(&FieldValue::Currency(123456)));
+        assert_eq!(records[1].get("RATIO"), Some(&FieldValue::Double(-0.25)));
+        assert_eq!(records[1].get("PRICE"), Some(&FieldValue::Currency(-5000)));
+        assert_eq!(records[2].get("RATIO"), Some(&FieldValue::Double(1e10)));
+        assert_eq!(records[2].get("PRICE"), Some(&FieldValue::Currency(922337203685477)));
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn currency_as_decimal() {
+        use rust_decimal::Decimal;
+
+        let db = OpenOptions::new().numeric_as_decimal(true).open("tests/foxpro_numeric_types.dbf").unwrap();
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("PRICE"), Some(&FieldValue::Decimal(Decimal::new(123456, 4))));
+        assert_eq!(records[2].get("PRICE"), Some(&FieldValue::Decimal(Decimal::new(922337203685477, 4))));
+    }
+
+    #[test]
+    fn general_fields_round_trip() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+
+        let path = std::env::temp_dir().join("dbase_parser_general_fields.dbf");
+        let fields = vec![FieldDescriptor::new("OBJECT", b'G', 4, 0).unwrap()];
+        let mut writer = DbfWriter::create(path.to_str().unwrap(), fields).unwrap();
+        let object:Vec<u8> = (0..=255).collect();
+        let mut values = HashMap::new();
+        values.insert("OBJECT".to_string(), FieldValue::Binary(object.clone()));
+        writer.write_record(&values).unwrap();
+        writer.write_record(&HashMap::new()).unwrap();
+        writer.finish().unwrap();
+
+        let records:Vec<Record> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("OBJECT"), Some(&FieldValue::Binary(object)));
+        assert_eq!(records[1].get("OBJECT"), Some(&FieldValue::Binary(vec![])));
+    }
+
+    #[test]
+    fn picture_fields_round_trip() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+
+        let path = std::env::temp_dir().join("dbase_parser_picture_fields.dbf");
+        let fields = vec![
+            FieldDescriptor::new("NAME", b'C', 10, 0).unwrap(),
+            FieldDescriptor::new("PHOTO", b'P', 4, 0).unwrap()
+        ];
+        let mut writer = DbfWriter::create(path.to_str().unwrap(), fields).unwrap();
+        let image = b"\x89PNG\r\n\x1a\n not quite an image".to_vec();
+        let mut values = HashMap::new();
+        values.insert("NAME".to_string(), FieldValue::Text("Portrait".to_string()));
+        values.insert("PHOTO".to_string(), FieldValue::Binary(image.clone()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.fields()[1].field_kind(), FieldKind::Picture);
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("PHOTO"), Some(&FieldValue::Binary(image)));
+    }
+
+    #[test]
+    fn schema_introspection() {
+        let db = open("tests/reference_fpt.dbf").unwrap();
+        let schema:Vec<(&str, FieldKind, u16, u8)> = db.header.fields().iter()
+            .map(|field| (field.name(), field.field_kind(), field.length(), field.decimal_count()))
+            .collect();
+        assert_eq!(schema, vec![
+            ("ID", FieldKind::Numeric, 8, 0),
+            ("Name", FieldKind::Character, 10, 0),
+            ("created_at", FieldKind::DateTime, 8, 0),
+            ("join", FieldKind::Date, 8, 0),
+            ("active", FieldKind::Logical, 1, 0),
+            ("transfers", FieldKind::Integer, 4, 0),
+            ("notes", FieldKind::Memo, 4, 0)
+        ]);
+        assert_eq!(db.header.fields()[6].type_code(), b'M');
+
+        let db = OpenOptions::new().allow_unknown_field_types(true).open("tests/custom_field_type.dbf").unwrap();
+        assert_eq!(db.header.fields()[1].field_kind(), FieldKind::Other(b'X'));
+    }
+
+    #[test]
+    fn create_from_schema() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_create_from_schema.dbf");
+        let writer = create(path.to_str().unwrap(), vec![
+            FieldDescriptor::new("NAME", b'C', 20, 0).unwrap(),
+            FieldDescriptor::new("AMOUNT", b'N', 10, 2).unwrap()
+        ]).unwrap();
+        writer.finish().unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        // Header, two field descriptors, the terminator and the end of file marker
+        assert_eq!(bytes.len(), 32 + 2 * 32 + 1 + 1);
+        assert_eq!(bytes[0], 0x03);
+        assert_eq!(bytes[32 + 2 * 32], 0x0d);
+        assert_eq!(bytes[bytes.len() - 1], 0x1a);
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.record_count, 0);
+        assert_eq!(db.header.physical_record_size(), 31);
+        let schema:Vec<(&str, FieldKind, u16, u8)> = db.header.fields().iter()
+            .map(|field| (field.name(), field.field_kind(), field.length(), field.decimal_count()))
+            .collect();
+        assert_eq!(schema, vec![("NAME", FieldKind::Character, 20, 0), ("AMOUNT", FieldKind::Numeric, 10, 2)]);
+        assert_eq!(db.into_iter().count(), 0);
+
+        let mut writer = create(path.to_str().unwrap(), vec![
+            FieldDescriptor::new("NAME", b'C', 20, 0).unwrap(),
+            FieldDescriptor::new("AMOUNT", b'N', 10, 2).unwrap()
+        ]).unwrap();
+        let mut values = HashMap::new();
+        values.insert("NAME".to_string(), FieldValue::Text("Widget".to_string()));
+        values.insert("AMOUNT".to_string(), FieldValue::Numeric(12.5));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("Widget".to_string())));
+        assert_eq!(record.get("AMOUNT"), Some(&FieldValue::Numeric(12.5)));
+
+        assert!(FieldDescriptor::new("A_VERY_LONG_NAME", b'C', 20, 0).is_err());
+    }
+
+    #[test]
+    fn append_records() {
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_append.dbf");
+        fs::copy("tests/five_records.dbf", &path).unwrap();
+
+        let mut values = HashMap::new();
+        values.insert("ID".to_string(), FieldValue::Numeric(6.0));
+        values.insert("NAME".to_string(), FieldValue::Text("six".to_string()));
+        match open(path.to_str().unwrap()).unwrap().append(&values) {
+            Err(DbaseError::ReadOnly) => (),
+            r => panic!("Expected a read-only error, got {:?}", r)
+        }
+
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        db.append(&values).unwrap();
+        assert_eq!(db.header.record_count, 6);
+        assert_eq!(db.record(5).unwrap().unwrap().get("NAME"), Some(&FieldValue::Text("six".to_string())));
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.record_count, 6);
+        assert_ne!(db.header.last_update, Utc.ymd(2019, 10, 1));
+        let names:Vec<FieldValue> = db.into_iter().map(|r| r.unwrap().fields.remove("NAME").unwrap()).collect();
+        assert_eq!(names.len(), 6);
+        assert_eq!(names[5], FieldValue::Text("six".to_string()));
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), fs::metadata("tests/five_records.dbf").unwrap().len() as usize + 14);
+        assert_eq!(bytes[bytes.len() - 1], 0x1a);
+    }
+
+    #[test]
+    fn append_records_with_memo() {
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_append_memo.dbf");
+        fs::copy("tests/reference_fpt.dbf", &path).unwrap();
+        fs::copy("tests/reference_fpt.fpt", path.with_extension("fpt")).unwrap();
+
+        let note = "A note appended to an existing memo file. ".repeat(10);
+        let mut values = HashMap::new();
+        values.insert("ID".to_string(), FieldValue::Numeric(40.0));
+        values.insert("Name".to_string(), FieldValue::Text("Appended".to_string()));
+        values.insert("notes".to_string(), FieldValue::Text(note.clone()));
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        db.append(&values).unwrap();
+
+        let records:Vec<Record> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].get("notes"), Some(&FieldValue::Text("This is a note.".to_string())));
+        assert_eq!(records[1].get("notes"), Some(&FieldValue::Text("This is a note.".to_string())));
+        assert_eq!(records[2].get("notes"), Some(&FieldValue::Text(note)));
+        assert_eq!(records[2].get("Name"), Some(&FieldValue::Text("Appended".to_string())));
+        assert_eq!(records[2].get("created_at"), Some(&FieldValue::Null));
+    }
+
+    #[test]
+    fn update_records_in_place() {
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_update.dbf");
+        fs::copy("tests/deleted_records.dbf", &path).unwrap();
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+
+        let mut values = HashMap::new();
+        values.insert("ID".to_string(), FieldValue::Numeric(33.0));
+        values.insert("NAME".to_string(), FieldValue::Text("THREE".to_string()));
+        db.update_record(2, &values).unwrap();
+        values.insert("NAME".to_string(), FieldValue::Text("TWO".to_string()));
+        db.update_record(1, &values).unwrap();
+
+        values.insert("NAME".to_string(), FieldValue::Text("much too long".to_string()));
+        match db.update_record(0, &values) {
+            Err(DbaseError::InvalidFieldValue { field, .. }) => assert_eq!(field, "NAME"),
+            r => panic!("Expected an invalid value error, got {:?}", r)
+        }
+        match db.update_record(5, &values) {
+            Err(DbaseError::InvalidRange { start: 5, end: 6, record_count: 5 }) => (),
+            r => panic!("Expected an invalid range error, got {:?}", r)
+        }
+
+        let mut db = open(path.to_str().unwrap()).unwrap();
+        let record = db.record(2).unwrap().unwrap();
+        assert_eq!(record.get("ID"), Some(&FieldValue::Numeric(33.0)));
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("THREE".to_string())));
+        let record = db.record(1).unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("TWO".to_string())));
+        assert!(record.deleted);
+        let record = db.record(0).unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("one".to_string())));
+        assert_eq!(fs::metadata(&path).unwrap().len(), fs::metadata("tests/deleted_records.dbf").unwrap().len());
+    }
+
+    #[test]
+    fn mark_deleted_and_undelete() {
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_mark_deleted.dbf");
+        fs::copy("tests/deleted_records.dbf", &path).unwrap();
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.deleted_count().unwrap(), 2);
+        db.mark_deleted(0).unwrap();
+        db.undelete(1).unwrap();
+        db.undelete(2).unwrap();
+        assert_eq!(db.deleted_count().unwrap(), 2);
+        assert!(db.mark_deleted(5).is_err());
+
+        let statuses:Vec<RecordStatus> = open(path.to_str().unwrap()).unwrap().records_with_status().map(|r| r.unwrap().0).collect();
+        assert_eq!(statuses, vec![RecordStatus::Deleted, RecordStatus::Active, RecordStatus::Active, RecordStatus::Deleted, RecordStatus::Active]);
+        let record = open(path.to_str().unwrap()).unwrap().record(0).unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("one".to_string())));
+
+        match open("tests/deleted_records.dbf").unwrap().mark_deleted(0) {
+            Err(DbaseError::ReadOnly) => (),
+            r => panic!("Expected a read-only error, got {:?}", r)
+        }
+    }
+
+    #[test]
+    fn pack_removes_deleted_records() {
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_pack.dbf");
+        fs::copy("tests/deleted_records.dbf", &path).unwrap();
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.pack().unwrap(), 2);
+        assert_eq!(db.header.record_count, 3);
+        assert_eq!(db.pack().unwrap(), 0);
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.record_count, 3);
+        let names:Vec<FieldValue> = db.into_iter().map(|r| r.unwrap().fields.remove("NAME").unwrap()).collect();
+        assert_eq!(names, vec![
+            FieldValue::Text("one".to_string()),
+            FieldValue::Text("three".to_string()),
+            FieldValue::Text("five".to_string())
+        ]);
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), fs::metadata("tests/deleted_records.dbf").unwrap().len() as usize - 2 * 14);
+        assert_eq!(bytes[bytes.len() - 1], 0x1a);
+        // The records are packed into a copy, moved over the database once complete
+        assert!(!std::env::temp_dir().join("dbase_parser_pack.tmp.dbf").exists());
+    }
+
+    #[test]
+    fn field_descriptor_builder() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptorBuilder;
+
+        let born = FieldDescriptorBuilder::new("BORN", b'D').build().unwrap();
+        assert_eq!((born.field_kind(), born.length()), (FieldKind::Date, 8));
+        let notes = FieldDescriptorBuilder::new("NOTES", b'M').build().unwrap();
+        assert_eq!(notes.length(), 10);
+        let price = FieldDescriptorBuilder::new("PRICE", b'N').length(8).decimal_count(2).flags(0x02).build().unwrap();
+        assert_eq!((price.length(), price.decimal_count(), price.flags()), (8, 2, 0x02));
+
+        let invalid = vec![
+            FieldDescriptorBuilder::new("", b'C').length(10).build(),
+            FieldDescriptorBuilder::new("FIRST NAME", b'C').length(10).build(),
+            FieldDescriptorBuilder::new("NAME", b'C').build(),
+            FieldDescriptorBuilder::new("NAME", b'C').length(255).build(),
+            FieldDescriptorBuilder::new("BORN", b'D').length(10).build(),
+            FieldDescriptorBuilder::new("PRICE", b'N').length(21).build(),
+            FieldDescriptorBuilder::new("PRICE", b'N').length(4).decimal_count(3).build(),
+            FieldDescriptorBuilder::new("NAME", b'C').length(10).decimal_count(1).build(),
+            FieldDescriptorBuilder::new("NOTES", b'M').length(8).build(),
+            FieldDescriptorBuilder::new("ODD", b'Z').length(1).build()
+        ];
+        for result in invalid {
+            assert!(result.is_err(), "Expected an error, got {:?}", result);
+        }
+        match FieldDescriptorBuilder::new("PRICE", b'N').length(21).build() {
+            Err(DbaseError::InvalidFieldDescriptor { field, .. }) => assert_eq!(field, "PRICE"),
+            r => panic!("Expected an invalid field descriptor, got {:?}", r)
+        }
+
+        // The flags are kept in the header
+        let path = std::env::temp_dir().join("dbase_parser_field_descriptor_builder.dbf");
+        create(path.to_str().unwrap(), vec![price]).unwrap().finish().unwrap();
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.fields()[0].flags(), 0x02);
+    }
+
+    #[test]
+    fn write_to_any_sink() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+        use std::io::Cursor;
+
+        let fields = || vec![
+            FieldDescriptor::new("NAME", b'C', 10, 0).unwrap(),
+            FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()
+        ];
+        let mut values = HashMap::new();
+        values.insert("NAME".to_string(), FieldValue::Text("Widget".to_string()));
+        values.insert("NOTES".to_string(), FieldValue::Text("Sold by the dozen".to_string()));
+
+        let mut writer = DbfWriter::with_memo(Cursor::new(vec![]), Cursor::new(vec![]), fields()).unwrap();
+        writer.write_record(&values).unwrap();
+        let bytes = writer.finish().unwrap().into_inner();
+
+        // The same table as one written to a file
+        let path = std::env::temp_dir().join("dbase_parser_write_to_any_sink.dbf");
+        let mut writer = DbfWriter::create(path.to_str().unwrap(), fields()).unwrap();
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(bytes, fs::read(&path).unwrap());
+
+        assert!(DbfWriter::new(Cursor::new(vec![]), fields()).is_err());
+        assert!(DbfWriter::with_memo(Cursor::new(vec![]), Cursor::new(vec![]), vec![FieldDescriptor::new("NAME", b'C', 10, 0).unwrap()]).is_err());
+    }
+
+    #[test]
+    fn writer_keeps_header_up_to_date() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_writer_header.dbf");
+        let mut values = HashMap::new();
+        values.insert("NAME".to_string(), FieldValue::Text("Widget".to_string()));
+
+        let mut writer = create(path.to_str().unwrap(), vec![FieldDescriptor::new("NAME", b'C', 10, 0).unwrap()]).unwrap();
+        writer.write_record(&values).unwrap();
+        writer.flush().unwrap();
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.record_count, 1);
+        assert_eq!(fs::read(&path).unwrap().last(), Some(&0x1a));
+
+        // Dropping the writer does the same
+        writer.write_record(&values).unwrap();
+        drop(writer);
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes.len(), 32 + 32 + 1 + 2 * 11 + 1);
+        assert_eq!(bytes.last(), Some(&0x1a));
+        assert_eq!(open(path.to_str().unwrap()).unwrap().header.record_count, 2);
+    }
+
+    #[test]
+    fn copy_with_schema_transform() {
+        use dbase_parser::header::{FieldDescriptor, FieldTransform};
+
+        let path = std::env::temp_dir().join("dbase_parser_copy_transform.dbf");
+        let db = open("tests/five_records.dbf").unwrap();
+        let count = db.copy_to(path.to_str().unwrap(), |field| match field.name() {
+            "ID" => FieldTransform::Convert(FieldDescriptor::new("CODE", b'C', 5, 0).unwrap(), Box::new(|value| match value {
+                FieldValue::Numeric(r) => Ok(FieldValue::Text(format!("#{}", r))),
+                r => Ok(r)
+            })),
+            "NAME" => FieldTransform::rename(field, "FULLNAME").unwrap(),
+            _ => FieldTransform::Keep
+        }).unwrap();
+        assert_eq!(count, 5);
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        let schema:Vec<(&str, FieldKind, u16)> = db.header.fields().iter()
+            .map(|field| (field.name(), field.field_kind(), field.length()))
+            .collect();
+        assert_eq!(schema, vec![("CODE", FieldKind::Character, 5), ("FULLNAME", FieldKind::Character, 10)]);
+        let record = db.into_iter().nth(2).unwrap().unwrap();
+        assert_eq!(record.get("CODE"), Some(&FieldValue::Text("#3".to_string())));
+        assert_eq!(record.get("FULLNAME"), Some(&FieldValue::Text("three".to_string())));
+
+        // Dropped fields are left out
+        let db = open("tests/five_records.dbf").unwrap();
+        db.copy_to(path.to_str().unwrap(), |field| match field.name() {
+            "ID" => FieldTransform::Drop,
+            _ => FieldTransform::Keep
+        }).unwrap();
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.fields().len(), 1);
+        assert_eq!(db.into_iter().count(), 5);
+
+        // Errors from a conversion stop the copy
+        let db = open("tests/five_records.dbf").unwrap();
+        let result = db.copy_to(path.to_str().unwrap(), |field| match field.name() {
+            "ID" => FieldTransform::Convert(field.clone(), Box::new(|_| Err(DbaseError::FieldNotFound("ID".to_string())))),
+            _ => FieldTransform::Keep
+        });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn byte_exact_round_trip() {
+        use std::fs;
+
+        let paths = ["tests/reference_dbase.dbf", "tests/reference_fpt.dbf", "tests/five_records.dbf", "tests/deleted_records.dbf", "tests/dbc_bound.dbf"];
+        for path in paths.iter() {
+            let mut bytes = vec![];
+            open(path).unwrap().write_to(&mut bytes).unwrap();
+            assert!(bytes == fs::read(path).unwrap(), "{} was not written back identically", path);
+        }
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn derive_writable_record() {
+        use dbase_parser::{create, WritableRecord};
+        use chrono::NaiveDate;
+        use std::convert::TryFrom;
+
+        #[derive(WritableRecord)]
+        struct Product {
+            #[dbase(length = 20)]
+            name: String,
+            #[dbase(name = "PRICE", length = 10, decimal_count = 2)]
+            unit_price: f64,
+            stock: Option<u16>,
+            added: NaiveDate
+        }
+
+        let path = std::env::temp_dir().join("dbase_parser_derive.dbf");
+        let mut writer = create(path.to_str().unwrap(), Product::fields().unwrap()).unwrap();
+        let added = NaiveDate::from_ymd_opt(2019, 9, 1).unwrap();
+        writer.write(&Product { name: "Widget".to_string(), unit_price: 12.5, stock: Some(40), added }).unwrap();
+        writer.write(&Product { name: "Gadget".to_string(), unit_price: 3.0, stock: None, added }).unwrap();
+        writer.finish().unwrap();
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        let schema:Vec<(&str, FieldKind, u16, u8)> = db.header.fields().iter()
+            .map(|field| (field.name(), field.field_kind(), field.length(), field.decimal_count()))
+            .collect();
+        assert_eq!(schema, vec![
+            ("NAME", FieldKind::Character, 20, 0),
+            ("PRICE", FieldKind::Numeric, 10, 2),
+            ("STOCK", FieldKind::Numeric, 5, 0),
+            ("ADDED", FieldKind::Date, 8, 0)
+        ]);
+        let records:Vec<Record> = db.into_iter().map(Result::unwrap).collect();
+        assert_eq!(records[0].get("PRICE"), Some(&FieldValue::Numeric(12.5)));
+        assert_eq!(records[0].get("STOCK"), Some(&FieldValue::Numeric(40.0)));
+        assert_eq!(records[1].get("STOCK"), Some(&FieldValue::Null));
+        assert_eq!(NaiveDate::try_from(records[0].get("ADDED").unwrap().clone()).unwrap(), added);
+    }
+
+    #[test]
+    fn overflow_policy() {
+        use dbase_parser::{create, Overflow};
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_overflow.dbf");
+        let fields = || vec![
+            FieldDescriptor::new("NAME", b'C', 5, 0).unwrap(),
+            FieldDescriptor::new("AMOUNT", b'N', 6, 2).unwrap()
+        ];
+        let record = |name: &str, amount: f64| {
+            let mut values = HashMap::new();
+            values.insert("NAME".to_string(), FieldValue::Text(name.to_string()));
+            values.insert("AMOUNT".to_string(), FieldValue::Numeric(amount));
+            values
+        };
+
+        let mut writer = create(path.to_str().unwrap(), fields()).unwrap();
+        writer.write_record(&record("Bolt", 1.25)).unwrap();
+        // Floating point noise is not a decimal of its own
+        writer.write_record(&record("Nut", 0.1 + 0.2)).unwrap();
+        for (values, field) in [(record("Widget", 1.25), "NAME"), (record("Bolt", 1.255), "AMOUNT"), (record("Bolt", 1000.0), "AMOUNT")] {
+            match writer.write_record(&values) {
+                Err(DbaseError::InvalidFieldValue { field: name, .. }) => assert_eq!(name, field),
+                r => panic!("Expected an invalid value for {}, got {:?}", field, r)
+            }
+        }
+
+        writer.overflow(Overflow::Truncate);
+        writer.write_record(&record("Widget", 1.255)).unwrap();
+        assert!(writer.write_record(&record("Bolt", 1000.0)).is_err());
+        writer.finish().unwrap();
+        let records:Vec<Record> = open(path.to_str().unwrap()).unwrap().into_iter().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].get("AMOUNT"), Some(&FieldValue::Numeric(0.3)));
+        assert_eq!(records[2].get("NAME"), Some(&FieldValue::Text("Widge".to_string())));
+        assert_eq!(records[2].get("AMOUNT"), Some(&FieldValue::Numeric(1.25)));
+
+        // Databases opened for writing follow the same policy
+        let copy = std::env::temp_dir().join("dbase_parser_overflow_append.dbf");
+        fs::copy(&path, &copy).unwrap();
+        let mut db = OpenOptions::new().write(true).open(copy.to_str().unwrap()).unwrap();
+        assert!(db.append(&record("Gadget", 2.0)).is_err());
+        let mut db = OpenOptions::new().write(true).overflow(Overflow::Truncate).open(copy.to_str().unwrap()).unwrap();
+        db.append(&record("Gadget", 2.0)).unwrap();
+        assert_eq!(db.record(3).unwrap().unwrap().get("NAME"), Some(&FieldValue::Text("Gadge".to_string())));
+    }
+
+    #[test]
+    fn padding_policies() {
+        use dbase_parser::{DbfWriter, NumericFill, Padding};
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::io::Cursor;
+
+        let fields = vec![
+            FieldDescriptor::new("NAME", b'C', 6, 0).unwrap(),
+            FieldDescriptor::new("AMOUNT", b'N', 7, 2).unwrap()
+        ];
+        let mut writer = DbfWriter::new(Cursor::new(vec![]), fields).unwrap();
+        writer.padding(Padding::Left).numeric_fill(NumericFill::Zero);
+        for (name, amount) in [("Bolt", 12.5), ("Nut", -3.0)] {
+            let mut values = HashMap::new();
+            values.insert("NAME".to_string(), FieldValue::Text(name.to_string()));
+            values.insert("AMOUNT".to_string(), FieldValue::Numeric(amount));
+            writer.write_record(&values).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+        let records = &bytes[32 + 2 * 32 + 1..];
+        assert_eq!(&records[..14], b"   Bolt0012.50");
+        assert_eq!(&records[14..28], b"    Nut-003.00");
+    }
+
+    #[test]
+    fn write_nullable_fields() {
+        use dbase_parser::create;
+        use dbase_parser::header::{FieldDescriptor, FieldDescriptorBuilder, Version};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_nullable.dbf");
+        let fields = vec![
+            FieldDescriptorBuilder::new("NAME", b'C').length(10).nullable(true).build().unwrap(),
+            FieldDescriptor::new("ID", b'N', 3, 0).unwrap(),
+            FieldDescriptorBuilder::new("AMOUNT", b'N').length(6).decimal_count(2).nullable(true).build().unwrap()
+        ];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("NAME".to_string(), FieldValue::Text("Widget".to_string()));
+        values.insert("ID".to_string(), FieldValue::Null);
+        values.insert("AMOUNT".to_string(), FieldValue::Null);
+        writer.write_record(&values).unwrap();
+        values.insert("NAME".to_string(), FieldValue::Null);
+        values.insert("AMOUNT".to_string(), FieldValue::Numeric(1.5));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+        assert!(!std::env::temp_dir().join("dbase_parser_nullable.fpt").exists());
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert!(matches!(db.header.version, Version::VisualFoxPro(false, false)));
+        let null_flags = &db.header.fields()[3];
+        assert_eq!((null_flags.name(), null_flags.type_code(), null_flags.length(), null_flags.flags()), ("_NullFlags", b'0', 1, 0x05));
+
+        // The second nullable field is null in the first record, the first one in the second
+        let bytes = fs::read(&path).unwrap();
+        let header_size = 32 + 4 * 32 + 1 + 263;
+        assert_eq!(bytes[header_size + 20], 0b10);
+        assert_eq!(bytes[header_size + 21 + 20], 0b01);
+
+        // Flagged fields read as null, and the system column stays hidden
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert!(db.header.fields()[3].is_system() && !db.header.fields()[0].is_system());
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("NAME"), Some(&FieldValue::Text("Widget".to_string())));
+        assert_eq!(records[0].get("AMOUNT"), Some(&FieldValue::Null));
+        assert_eq!(records[1].get("NAME"), Some(&FieldValue::Null));
+        assert_eq!(records[1].get("AMOUNT"), Some(&FieldValue::Numeric(1.5)));
+        assert_eq!(records[1].get("_NullFlags"), None);
+    }
+
+    #[test]
+    fn append_all_records() {
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_append_all.dbf");
+        fs::copy("tests/five_records.dbf", &path).unwrap();
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        let records = (6..100006).map(|i| {
+            let mut values = HashMap::new();
+            values.insert("ID".to_string(), FieldValue::Numeric(f64::from(i % 1000)));
+            values.insert("NAME".to_string(), FieldValue::Text(format!("row {}", i)));
+            let mut record = Record::new(values);
+            record.deleted = i == 7;
+            record
+        });
+        assert_eq!(db.append_all(records).unwrap(), 100000);
+        assert_eq!(db.header.record_count, 100005);
+        assert_eq!(db.deleted_count().unwrap(), 1);
+        assert_eq!(db.record(100004).unwrap().unwrap().get("NAME"), Some(&FieldValue::Text("row 100005".to_string())));
+        let length = fs::metadata(&path).unwrap().len();
+        assert_eq!(fs::read(&path).unwrap().last(), Some(&0x1a));
+
+        // A record which can't be written leaves the database as it was
+        let mut values = HashMap::new();
+        values.insert("NAME".to_string(), FieldValue::Text("far too long for the field".to_string()));
+        assert!(db.append_all(vec![Record::new(HashMap::new()), Record::new(values)]).is_err());
+        assert_eq!(db.header.record_count, 100005);
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.record_count, 100005);
+        assert_eq!(fs::metadata(&path).unwrap().len(), length);
+    }
+
+    #[test]
+    fn atomic_writes() {
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_atomic.dbf");
+        let memo_path = path.with_extension("fpt");
+        let temp_path = std::env::temp_dir().join("dbase_parser_atomic.tmp.dbf");
+        let temp_memo_path = temp_path.with_extension("fpt");
+        fs::copy("tests/reference_fpt.dbf", &path).unwrap();
+        fs::copy("tests/reference_fpt.fpt", &memo_path).unwrap();
+        let original = fs::read(&path).unwrap();
+
+        let mut values = HashMap::new();
+        values.insert("Name".to_string(), FieldValue::Text("Appended".to_string()));
+        values.insert("notes".to_string(), FieldValue::Text("A note appended atomically.".to_string()));
+
+        // Changes only reach the original once committed
+        let mut db = OpenOptions::new().atomic(true).open(path.to_str().unwrap()).unwrap();
+        db.append(&values).unwrap();
+        assert!(temp_path.exists() && temp_memo_path.exists());
+        assert_eq!(fs::read(&path).unwrap(), original);
+        db.commit().unwrap();
+        assert!(!temp_path.exists() && !temp_memo_path.exists());
+        let records:Vec<Record> = open(path.to_str().unwrap()).unwrap().into_iter().map(Result::unwrap).collect();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[2].get("notes"), Some(&FieldValue::Text("A note appended atomically.".to_string())));
+
+        // Dropping the database discards them
+        let committed = fs::read(&path).unwrap();
+        let committed_memo = fs::read(&memo_path).unwrap();
+        let mut db = OpenOptions::new().atomic(true).open(path.to_str().unwrap()).unwrap();
+        db.append(&values).unwrap();
+        drop(db);
+        assert!(!temp_path.exists() && !temp_memo_path.exists());
+        assert_eq!(fs::read(&path).unwrap(), committed);
+        assert_eq!(fs::read(&memo_path).unwrap(), committed_memo);
+    }
+
+    #[test]
+    fn create_with_target_version() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::{FieldDescriptor, FieldDescriptorBuilder, Version};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_target_version.dbf");
+        let name = || FieldDescriptor::new("NAME", b'C', 10, 0).unwrap();
+
+        // dBASE IV tables keep their memos in dBASE IV blocks
+        let fields = vec![name(), FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = DbfWriter::create_as(path.to_str().unwrap(), Version::dBASE4Table(false), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("NAME".to_string(), FieldValue::Text("Widget".to_string()));
+        values.insert("NOTES".to_string(), FieldValue::Text("Sold by the dozen".to_string()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(fs::read(&path).unwrap()[0], 0xcb);
+        let memo = fs::read(path.with_extension("dbt")).unwrap();
+        assert_eq!(memo[512..520], [0xff, 0xff, 0x08, 0x00, 25, 0, 0, 0]);
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("NOTES"), Some(&FieldValue::Text("Sold by the dozen".to_string())));
+
+        DbfWriter::create_as(path.to_str().unwrap(), Version::VisualFoxPro(false, false), vec![name()]).unwrap().finish().unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes[0], 0x30);
+        assert_eq!(bytes.len(), 32 + 32 + 1 + 263 + 1);
+
+        let refused = vec![
+            (Version::dBASE3(false), FieldDescriptor::new("STAMP", b'T', 8, 0).unwrap()),
+            (Version::dBASE3(false), FieldDescriptor::new("RATIO", b'F', 10, 2).unwrap()),
+            (Version::dBASE3(false), FieldDescriptorBuilder::new("PRICE", b'N').length(8).nullable(true).build().unwrap()),
+            (Version::VisualFoxPro(false, false), FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()),
+            (Version::FoxPro2(false), name())
+        ];
+        for (version, field) in refused {
+            assert!(DbfWriter::create_as(path.to_str().unwrap(), version, vec![field]).is_err());
+        }
+    }
+
+    #[test]
+    fn binary_fields_round_trip() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::{FieldDescriptor, FieldTransform};
+        use std::collections::HashMap;
+        use std::fs;
+
+        // Logical, date, datetime, integer, double and currency fields read back as they were
+        for source in ["tests/reference_fpt.dbf", "tests/foxpro_numeric_types.dbf"].iter() {
+            let path = std::env::temp_dir().join("dbase_parser_binary_round_trip.dbf");
+            let db = open(source).unwrap();
+            let names:Vec<String> = db.header.fields().iter().map(|field| field.name().to_string()).collect();
+            let expected:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+            open(source).unwrap().copy_to(path.to_str().unwrap(), |_| FieldTransform::Keep).unwrap();
+            let written:Vec<Record> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+            assert_eq!(written.len(), expected.len());
+            for (written, expected) in written.iter().zip(expected.iter()) {
+                for name in names.iter() {
+                    assert_eq!(written.get(name), expected.get(name));
+                }
+            }
+        }
+
+        let path = std::env::temp_dir().join("dbase_parser_binary_fields.dbf");
+        let fields = vec![
+            FieldDescriptor::new("ACTIVE", b'L', 1, 0).unwrap(),
+            FieldDescriptor::new("COUNT", b'I', 4, 0).unwrap(),
+            FieldDescriptor::new("PRICE", b'Y', 8, 0).unwrap()
+        ];
+        let mut writer = DbfWriter::create(path.to_str().unwrap(), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("ACTIVE".to_string(), FieldValue::Boolean(None));
+        values.insert("COUNT".to_string(), FieldValue::Integer(-2));
+        values.insert("PRICE".to_string(), FieldValue::Numeric(12.5));
+        writer.write_record(&values).unwrap();
+        writer.write_record(&HashMap::new()).unwrap();
+        values.insert("COUNT".to_string(), FieldValue::Numeric(1.5));
+        assert!(writer.write_record(&values).is_err());
+        writer.finish().unwrap();
+
+        // Binary types make a Visual FoxPro table
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes[0], 0x30);
+        let header_size = 32 + 32 * 3 + 1 + 263;
+        assert_eq!(bytes[header_size..header_size + 14], [b' ', b'?', 0xfe, 0xff, 0xff, 0xff, 0x48, 0xe8, 0x01, 0, 0, 0, 0, 0]);
+        assert_eq!(bytes[header_size + 14..header_size + 28], [b' ', b' ', 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("ACTIVE"), Some(&FieldValue::Boolean(None)));
+        assert_eq!(record.get("COUNT"), Some(&FieldValue::Integer(-2)));
+        assert_eq!(record.get("PRICE"), Some(&FieldValue::Currency(125000)));
+    }
+
+    #[test]
+    fn failed_appends_free_their_memos() {
+        use dbase_parser::{DbfWriter, OpenOptions};
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_memo_rollback.dbf");
+        let memo_path = path.with_extension("fpt");
+        let fields = vec![
+            FieldDescriptor::new("NOTES", b'M', 4, 0).unwrap(),
+            FieldDescriptor::new("NAME", b'C', 5, 0).unwrap()
+        ];
+        let record = |notes: &str, name: &str| {
+            let mut values = HashMap::new();
+            values.insert("NOTES".to_string(), FieldValue::Text(notes.to_string()));
+            values.insert("NAME".to_string(), FieldValue::Text(name.to_string()));
+            values
+        };
+
+        // The memo of a record too long for its name is written first, then freed
+        let mut writer = DbfWriter::create(path.to_str().unwrap(), fields).unwrap();
+        writer.write_record(&record("First", "one")).unwrap();
+        assert!(writer.write_record(&record("Orphan", "too long")).is_err());
+        writer.write_record(&record("Second", "two")).unwrap();
+        writer.finish().unwrap();
+        let memo = fs::read(&memo_path).unwrap();
+        assert_eq!(memo[0..4], [0, 0, 0, 10]);
+        assert_eq!(memo.len(), 10 * 64);
+
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        assert!(db.append(&record("Orphan", "too long")).is_err());
+        assert_eq!(fs::read(&memo_path).unwrap(), memo);
+        let records = vec![Record::new(record("Third", "three")), Record::new(record("Orphan", "too long"))];
+        assert!(db.append_all(records).is_err());
+        assert_eq!(fs::read(&memo_path).unwrap(), memo);
+        assert!(db.update_record(0, &record("Orphan", "too long")).is_err());
+        assert_eq!(fs::read(&memo_path).unwrap(), memo);
+        db.append(&record("Third", "three")).unwrap();
+        drop(db);
+
+        let notes:Vec<FieldValue> = open(path.to_str().unwrap()).unwrap().into_iter()
+            .map(|r| r.unwrap().get("NOTES").unwrap().clone())
+            .collect();
+        assert_eq!(notes, vec![
+            FieldValue::Text("First".to_string()),
+            FieldValue::Text("Second".to_string()),
+            FieldValue::Text("Third".to_string())
+        ]);
+        assert_eq!(fs::read(&memo_path).unwrap().len(), 11 * 64);
+    }
+
+    #[test]
+    fn create_from_csv() {
+        use dbase_parser::{from_csv, CsvOptions};
+        use dbase_parser::header::FieldDescriptor;
+
+        let path = std::env::temp_dir().join("dbase_parser_from_csv.dbf");
+        let long = "x".repeat(300);
+        let csv = format!("\u{feff}Name,Unit price,Active,Since,Zip code,Notes\r\n\
+            \"Widget, large\",12.5,Y,2019-09-03,01234,\"Says \"\"hi\"\"\ntwice\"\r\n\
+            Gadget,-3,no,,90210,{}\r\n\
+            \r\n", long);
+        let count = from_csv(csv.as_bytes(), path.to_str().unwrap(), &CsvOptions::new()).unwrap();
+        assert_eq!(count, 2);
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        let schema:Vec<(&str, FieldKind, u16, u8)> = db.header.fields().iter()
+            .map(|field| (field.name(), field.field_kind(), field.length(), field.decimal_count()))
+            .collect();
+        assert_eq!(schema, vec![
+            ("NAME", FieldKind::Character, 13, 0),
+            ("UNIT_PRICE", FieldKind::Numeric, 4, 1),
+            ("ACTIVE", FieldKind::Logical, 1, 0),
+            ("SINCE", FieldKind::Date, 8, 0),
+            ("ZIP_CODE", FieldKind::Character, 5, 0),
+            ("NOTES", FieldKind::Memo, 10, 0)
+        ]);
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("NAME"), Some(&FieldValue::Text("Widget, large".to_string())));
+        assert_eq!(records[0].get("NOTES"), Some(&FieldValue::Text("Says \"hi\"\ntwice".to_string())));
+        assert_eq!(records[1].get("UNIT_PRICE"), Some(&FieldValue::Numeric(-3.0)));
+        assert_eq!(records[1].get("ACTIVE"), Some(&FieldValue::Boolean(Some(false))));
+        assert_eq!(records[1].get("SINCE"), Some(&FieldValue::Null));
+        assert_eq!(records[1].get("ZIP_CODE"), Some(&FieldValue::Text("90210".to_string())));
+        assert_eq!(records[1].get("NOTES"), Some(&FieldValue::Text(long)));
+
+        // An explicit schema, without a header row
+        let mut options = CsvOptions::new();
+        options.delimiter(b';').has_headers(false).fields(vec![
+            FieldDescriptor::new("CODE", b'C', 4, 0).unwrap(),
+            FieldDescriptor::new("SOLD", b'D', 8, 0).unwrap()
+        ]);
+        assert_eq!(from_csv("A1;20190903\nB2;\n".as_bytes(), path.to_str().unwrap(), &options).unwrap(), 2);
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("CODE"), Some(&FieldValue::Text("A1".to_string())));
+        assert!(matches!(record.get("SOLD"), Some(FieldValue::Date(_))));
+
+        assert!(from_csv("A1;someday\n".as_bytes(), path.to_str().unwrap(), &options).is_err());
+        assert!(from_csv("A1;20190903;extra\n".as_bytes(), path.to_str().unwrap(), &options).is_err());
+        assert!(from_csv("a,b\n\"open,1\n".as_bytes(), path.to_str().unwrap(), &CsvOptions::new()).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn create_from_serialize() {
+        use dbase_parser::from_serialize;
+        use serde_crate::Serialize;
+        use std::collections::BTreeMap;
+
+        #[derive(Serialize)]
+        #[serde(crate = "serde_crate")]
+        struct Sale {
+            product: String,
+            quantity: u32,
+            price: Option<f64>,
+            paid: bool,
+            sold_on: String,
+            tags: Vec<String>
+        }
+
+        let path = std::env::temp_dir().join("dbase_parser_from_serialize.dbf");
+        let sales = vec![
+            Sale { product: "Widget".to_string(), quantity: 12, price: Some(2.5), paid: true, sold_on: "2019-09-03".to_string(), tags: vec!["new".to_string()] },
+            Sale { product: "Gadget".to_string(), quantity: 3, price: None, paid: false, sold_on: "2019-09-04".to_string(), tags: vec![] }
+        ];
+        assert_eq!(from_serialize(path.to_str().unwrap(), sales).unwrap(), 2);
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        let schema:Vec<(&str, FieldKind, u16, u8)> = db.header.fields().iter()
+            .map(|field| (field.name(), field.field_kind(), field.length(), field.decimal_count()))
+            .collect();
+        assert_eq!(schema, vec![
+            ("PRODUCT", FieldKind::Character, 6, 0),
+            ("QUANTITY", FieldKind::Numeric, 2, 0),
+            ("PRICE", FieldKind::Numeric, 3, 1),
+            ("PAID", FieldKind::Logical, 1, 0),
+            ("SOLD_ON", FieldKind::Character, 10, 0),
+            ("TAGS", FieldKind::Character, 7, 0)
+        ]);
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("PRICE"), Some(&FieldValue::Numeric(2.5)));
+        assert_eq!(records[0].get("TAGS"), Some(&FieldValue::Text("[\"new\"]".to_string())));
+        assert_eq!(records[1].get("PRICE"), Some(&FieldValue::Null));
+        assert_eq!(records[1].get("PAID"), Some(&FieldValue::Boolean(Some(false))));
+
+        // Maps work too, but not plain values
+        let mut map = BTreeMap::new();
+        map.insert("code", "A1");
+        assert_eq!(from_serialize(path.to_str().unwrap(), [map]).unwrap(), 1);
+        assert!(from_serialize(path.to_str().unwrap(), [1, 2]).is_err());
+
+        // The type of a field follows the serde type, not what the text looks like
+        #[derive(Serialize)]
+        #[serde(crate = "serde_crate")]
+        struct Account {
+            code: String,
+            flag: String,
+            total: f64
+        }
+        let accounts = [Account { code: "42".to_string(), flag: "Y".to_string(), total: 1.5e15 }];
+        from_serialize(path.to_str().unwrap(), accounts).unwrap();
+        let db = open(path.to_str().unwrap()).unwrap();
+        let kinds:Vec<FieldKind> = db.header.fields().iter().map(|field| field.field_kind()).collect();
+        assert_eq!(kinds, vec![FieldKind::Character, FieldKind::Character, FieldKind::Numeric]);
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("CODE"), Some(&FieldValue::Text("42".to_string())));
+        assert_eq!(record.get("TOTAL"), Some(&FieldValue::Numeric(1.5e15)));
+    }
+
+    #[test]
+    fn write_in_code_page() {
+        use dbase_parser::{create, CodePage, Unmappable};
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_code_page.dbf");
+        let write = |code_page: CodePage, unmappable: Unmappable, text: &str| {
+            let mut writer = create(path.to_str().unwrap(), vec![FieldDescriptor::new("NAME", b'C', 10, 0).unwrap()]).unwrap();
+            writer.code_page(code_page).unmappable(unmappable);
+            let mut values = HashMap::new();
+            values.insert("NAME".to_string(), FieldValue::Text(text.to_string()));
+            let result = writer.write_record(&values);
+            writer.finish().unwrap();
+            result
+        };
+        let header_size = 32 + 32 + 1;
+
+        // The language driver lets the reader pick the code page back
+        write(CodePage::Dos866, Unmappable::Error, "Привет").unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes[29], 0x65);
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("Привет".to_string())));
+
+        write(CodePage::Dos850, Unmappable::Error, "Café ÿ").unwrap();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes[29], 0x02);
+        assert_eq!(bytes[header_size + 1..header_size + 7], [b'C', b'a', b'f', 0x82, b' ', 0x98]);
+
+        assert!(write(CodePage::Windows1252, Unmappable::Error, "Привет").is_err());
+        assert!(write(CodePage::Dos437, Unmappable::Error, "Ø").is_err());
+        write(CodePage::Windows1252, Unmappable::Substitute, "Café Ж").unwrap();
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("Café ?".to_string())));
+    }
+
+    #[test]
+    fn patch_single_field() {
+        use dbase_parser::create;
+        use dbase_parser::header::{FieldDescriptor, FieldDescriptorBuilder};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_patch_field.dbf");
+        fs::copy("tests/five_records.dbf", &path).unwrap();
+        let before = fs::read(&path).unwrap();
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        db.patch_field(2, "NAME", &FieldValue::Text("patched".to_string())).unwrap();
+        assert!(matches!(db.patch_field(2, "NOPE", &FieldValue::Null), Err(DbaseError::FieldNotFound(_))));
+        assert!(db.patch_field(5, "NAME", &FieldValue::Null).is_err());
+        drop(db);
+
+        // Only the bytes of the field changed
+        let after = fs::read(&path).unwrap();
+        let changed:Vec<usize> = (0..after.len()).filter(|i| before[*i] != after[*i]).collect();
+        assert!(!changed.is_empty());
+        let db = open(path.to_str().unwrap()).unwrap();
+        let name_offset = usize::from(db.header.header_size()) + 2 * db.header.physical_record_size() + 1 + usize::from(db.header.fields()[0].length());
+        assert!(changed.iter().all(|i| *i >= name_offset && *i < name_offset + 10));
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[2].get("NAME"), Some(&FieldValue::Text("patched".to_string())));
+        assert_eq!(records[2].get("ID"), Some(&FieldValue::Numeric(3.0)));
+        assert_eq!(records[1].get("NAME"), Some(&FieldValue::Text("two".to_string())));
+
+        // Nullable fields keep their null flag in step
+        let fields = vec![
+            FieldDescriptor::new("ID", b'N', 3, 0).unwrap(),
+            FieldDescriptorBuilder::new("AMOUNT", b'N').length(6).decimal_count(2).nullable(true).build().unwrap()
+        ];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("AMOUNT".to_string(), FieldValue::Numeric(1.5));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+        let null_flags = 32 + 3 * 32 + 1 + 263 + 1 + 3 + 6;
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        db.patch_field(0, "AMOUNT", &FieldValue::Null).unwrap();
+        assert_eq!(fs::read(&path).unwrap()[null_flags], 0x01);
+        db.patch_field(0, "AMOUNT", &FieldValue::Numeric(2.5)).unwrap();
+        assert_eq!(fs::read(&path).unwrap()[null_flags], 0x00);
+    }
+
+    #[test]
+    fn float_fields_are_distinct() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::{FieldDescriptor, Version};
+        use std::collections::HashMap;
+
+        let path = std::env::temp_dir().join("dbase_parser_float_fields.dbf");
+        let fields = vec![
+            FieldDescriptor::new("AMOUNT", b'N', 8, 2).unwrap(),
+            FieldDescriptor::new("RATIO", b'F', 8, 2).unwrap()
+        ];
+        let mut writer = DbfWriter::create_as(path.to_str().unwrap(), Version::dBASE4Table(false), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("AMOUNT".to_string(), FieldValue::Float(1.25));
+        values.insert("RATIO".to_string(), FieldValue::Numeric(0.5));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        let kinds:Vec<FieldKind> = db.header.fields().iter().map(|field| field.field_kind()).collect();
+        assert_eq!(kinds, vec![FieldKind::Numeric, FieldKind::Float]);
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("AMOUNT"), Some(&FieldValue::Numeric(1.25)));
+        assert_eq!(record.get("RATIO"), Some(&FieldValue::Float(0.5)));
+    }
+
+    #[test]
+    fn varchar_fields_round_trip() {
+        use dbase_parser::create;
+        use dbase_parser::header::{FieldDescriptor, FieldDescriptorBuilder, FieldKind, Version};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_varchar.dbf");
+        let fields = vec![
+            FieldDescriptor::new("NOTE", b'V', 10, 0).unwrap(),
+            FieldDescriptorBuilder::new("AMOUNT", b'N').length(6).decimal_count(2).nullable(true).build().unwrap()
+        ];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        for note in ["short", "0123456789", "pad  ", ""].iter() {
+            let mut values = HashMap::new();
+            values.insert("NOTE".to_string(), FieldValue::Text(note.to_string()));
+            writer.write_record(&values).unwrap();
+        }
+        writer.finish().unwrap();
+
+        // Short values store their length in the last byte and set their bit of _NullFlags,
+        // before the null bit of AMOUNT
+        let bytes = fs::read(&path).unwrap();
+        let header_size = 32 + 3 * 32 + 1 + 263;
+        assert_eq!(bytes[header_size + 1..header_size + 11], b"short    \x05"[..]);
+        assert_eq!(bytes[header_size + 17], 0b11);
+        assert_eq!(bytes[header_size + 18 + 17], 0b10);
+
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        assert!(matches!(db.header.version, Version::VisualFoxPro(false, true)));
+        assert_eq!(db.header.fields()[0].field_kind(), FieldKind::Varchar);
+        db.patch_field(0, "NOTE", &FieldValue::Text("abcdefghij".to_string())).unwrap();
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        let notes:Vec<&FieldValue> = records.iter().map(|r| r.get("NOTE").unwrap()).collect();
+        assert_eq!(notes, [
+            &FieldValue::Text("abcdefghij".to_string()),
+            &FieldValue::Text("0123456789".to_string()),
+            &FieldValue::Text("pad  ".to_string()),
+            &FieldValue::Text("".to_string())
+        ]);
+        assert_eq!(fs::read(&path).unwrap()[header_size + 17], 0b10);
+    }
+
+    #[test]
+    fn varbinary_fields_round_trip() {
+        use dbase_parser::create;
+        use dbase_parser::header::{FieldDescriptor, FieldKind};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_varbinary.dbf");
+        let fields = vec![
+            FieldDescriptor::new("ID", b'I', 4, 0).unwrap(),
+            FieldDescriptor::new("TOKEN", b'Q', 6, 0).unwrap()
+        ];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        let tokens = [vec![0xde, 0xad], vec![0, 1, 2, 3, 4, 5], vec![0x20, 0]];
+        for token in tokens.iter() {
+            let mut values = HashMap::new();
+            values.insert("TOKEN".to_string(), FieldValue::Binary(token.clone()));
+            writer.write_record(&values).unwrap();
+        }
+        let mut values = HashMap::new();
+        values.insert("TOKEN".to_string(), FieldValue::Binary(vec![0; 7]));
+        assert!(writer.write_record(&values).is_err());
+        writer.finish().unwrap();
+
+        let bytes = fs::read(&path).unwrap();
+        let header_size = 32 + 3 * 32 + 1 + 263;
+        assert_eq!(bytes[header_size + 5..header_size + 11], [0xde, 0xad, 0x20, 0x20, 0x20, 0x02]);
+        assert_eq!(bytes[header_size + 11], 0b1);
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.fields()[1].field_kind(), FieldKind::Varbinary);
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        let read:Vec<FieldValue> = records.iter().map(|r| r.get("TOKEN").unwrap().clone()).collect();
+        assert_eq!(read, tokens.iter().cloned().map(FieldValue::Binary).collect::<Vec<FieldValue>>());
+    }
+
+    #[test]
+    fn autoincrement_fields() {
+        use dbase_parser::create;
+        use dbase_parser::header::{FieldDescriptor, FieldDescriptorBuilder, FieldKind};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_autoincrement.dbf");
+        let fields = vec![
+            FieldDescriptorBuilder::new("ID", b'+').autoincrement(3, 2).build().unwrap(),
+            FieldDescriptor::new("NAME", b'C', 10, 0).unwrap()
+        ];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        for id in [1, -1].iter() {
+            let mut values = HashMap::new();
+            values.insert("ID".to_string(), FieldValue::Integer(*id));
+            writer.write_record(&values).unwrap();
+        }
+        writer.finish().unwrap();
+
+        // The counter sits in bytes 19 to 23 of the field descriptor
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes[32 + 11], b'+');
+        assert_eq!(bytes[32 + 19..32 + 24], [3, 0, 0, 0, 2]);
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        let id = &db.header.fields()[0];
+        assert_eq!(id.field_kind(), FieldKind::Autoincrement);
+        assert_eq!((id.length(), id.is_autoincrement(), id.next_value(), id.step()), (4, true, Some(3), Some(2)));
+        assert_eq!((db.header.fields()[1].next_value(), db.header.fields()[1].step()), (None, None));
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("ID"), Some(&FieldValue::Integer(1)));
+        assert_eq!(records[1].get("ID"), Some(&FieldValue::Integer(-1)));
+    }
+
+    #[test]
+    fn timestamp_fields() {
+        use dbase_parser::create;
+        use dbase_parser::header::{FieldDescriptor, FieldDescriptorBuilder, FieldKind};
+        use std::collections::HashMap;
+        use std::fs;
+
+        // dBASE 7 tables cannot be created, so the field is patched into a dBASE III one
+        let path = std::env::temp_dir().join("dbase_parser_timestamp.dbf");
+        let fields = vec![FieldDescriptorBuilder::new("STAMP", b'@').build().unwrap()];
+        assert!(create(path.to_str().unwrap(), fields).is_err());
+        create(path.to_str().unwrap(), vec![FieldDescriptor::new("STAMP", b'C', 8, 0).unwrap()]).unwrap().finish().unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[32 + 11] = b'@';
+        fs::write(&path, bytes).unwrap();
+        let stamp = Utc.with_ymd_and_hms(1999, 12, 31, 23, 59, 58).unwrap();
+        let mut values = HashMap::new();
+        values.insert("STAMP".to_string(), FieldValue::DateTime(stamp));
+        OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap().append(&values).unwrap();
+
+        // Two little endian longs: the julian day, then milliseconds since midnight
+        let bytes = fs::read(&path).unwrap();
+        let header_size = 32 + 32 + 1;
+        assert_eq!(bytes[header_size + 1..header_size + 9], [0x58, 0x68, 0x25, 0x00, 0x30, 0x54, 0x26, 0x05]);
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.fields()[0].field_kind(), FieldKind::Timestamp);
+        assert_eq!(db.header.fields()[0].length(), 8);
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("STAMP"), Some(&FieldValue::DateTime(stamp)));
+    }
+
+    #[test]
+    fn dbase7_double_fields() {
+        use dbase_parser::create;
+        use dbase_parser::header::{FieldDescriptor, FieldDescriptorBuilder, FieldKind};
+        use std::collections::HashMap;
+        use std::fs;
+
+        // dBASE 7 tables cannot be created, so the field is patched into a dBASE III one
+        let path = std::env::temp_dir().join("dbase_parser_dbase7_double.dbf");
+        let fields = vec![FieldDescriptorBuilder::new("RATIO", b'O').build().unwrap()];
+        assert!(create(path.to_str().unwrap(), fields).is_err());
+        create(path.to_str().unwrap(), vec![FieldDescriptor::new("RATIO", b'C', 8, 0).unwrap()]).unwrap().finish().unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[32 + 11] = b'O';
+        fs::write(&path, bytes).unwrap();
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        let ratios = [FieldValue::Double(1.5), FieldValue::Double(-2.25), FieldValue::Double(0.0), FieldValue::Null];
+        for ratio in ratios.iter() {
+            let mut values = HashMap::new();
+            values.insert("RATIO".to_string(), ratio.clone());
+            db.append(&values).unwrap();
+        }
+        drop(db);
+
+        // Big endian, with the sign bit flipped for positive numbers and every bit for negative ones
+        let bytes = fs::read(&path).unwrap();
+        let header_size = 32 + 32 + 1;
+        assert_eq!(bytes[header_size + 1..header_size + 9], [0xbf, 0xf8, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(bytes[header_size + 10..header_size + 18], [0x3f, 0xfd, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.fields()[0].field_kind(), FieldKind::Dbase7Double);
+        let records:Vec<Record> = db.into_iter().map(|r| r.unwrap()).collect();
+        let read:Vec<FieldValue> = records.iter().map(|r| r.get("RATIO").unwrap().clone()).collect();
+        assert_eq!(read, ratios);
+    }
+
+    #[test]
+    fn binary_memos_of_dbase4() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::{FieldDescriptor, Version};
+        use std::collections::HashMap;
+        use std::fs;
+
+        // B fields of dBASE IV tables point to the memo file
+        let path = std::env::temp_dir().join("dbase_parser_binary_memo.dbf");
+        let fields = vec![FieldDescriptor::new("DATA", b'M', 10, 0).unwrap()];
+        let mut writer = DbfWriter::create_as(path.to_str().unwrap(), Version::dBASE4Table(false), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("DATA".to_string(), FieldValue::Text("\u{1}binary".to_string()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[32 + 11] = b'B';
+        fs::write(&path, bytes).unwrap();
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("DATA"), Some(&FieldValue::Binary(b"\x01binary".to_vec())));
+
+        // The same byte is a double in Visual FoxPro tables
+        let db = open("tests/foxpro_numeric_types.dbf").unwrap();
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert!(matches!(record.get("RATIO"), Some(FieldValue::Double(_))));
+    }
+
+    #[test]
+    fn blank_fields_are_null() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_blank_fields.dbf");
+        let fields = vec![
+            FieldDescriptor::new("ACTIVE", b'L', 1, 0).unwrap(),
+            FieldDescriptor::new("BORN", b'D', 8, 0).unwrap(),
+            FieldDescriptor::new("AMOUNT", b'N', 6, 2).unwrap()
+        ];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        writer.write_record(&HashMap::new()).unwrap();
+        writer.write_record(&HashMap::new()).unwrap();
+        writer.finish().unwrap();
+
+        // The second record is filled with zeros, as some exporters do
+        let mut bytes = fs::read(&path).unwrap();
+        let record = 32 + 3 * 32 + 1 + 16;
+        bytes[record + 1] = 0;
+        bytes[record + 2..record + 10].copy_from_slice(b"00000000");
+        bytes[record + 10..record + 16].copy_from_slice(&[0; 6]);
+        fs::write(&path, bytes).unwrap();
+        for record in open(path.to_str().unwrap()).unwrap() {
+            let record = record.unwrap();
+            for name in ["ACTIVE", "BORN", "AMOUNT"].iter() {
+                assert_eq!(record.get(name), Some(&FieldValue::Null));
+            }
+        }
+    }
+
+    #[test]
+    fn binary_character_fields() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptorBuilder;
+        use std::collections::HashMap;
+
+        let path = std::env::temp_dir().join("dbase_parser_binary_character.dbf");
+        let fields = vec![
+            FieldDescriptorBuilder::new("KEY", b'C').length(4).flags(0x04).build().unwrap(),
+            FieldDescriptorBuilder::new("BLOB", b'M').length(4).flags(0x04).build().unwrap(),
+            FieldDescriptorBuilder::new("NAME", b'C').length(4).build().unwrap()
+        ];
+        assert!(fields[0].is_binary() && fields[1].is_binary() && !fields[2].is_binary());
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("KEY".to_string(), FieldValue::Binary(vec![0xff, 0xfe, 0, 0x20]));
+        values.insert("BLOB".to_string(), FieldValue::Binary(vec![0xc3, 0x28, 0x20, 0x20]));
+        values.insert("NAME".to_string(), FieldValue::Text("ab".to_string()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+
+        // Neither decoded nor trimmed
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("KEY"), Some(&FieldValue::Binary(vec![0xff, 0xfe, 0, 0x20])));
+        assert_eq!(record.get("BLOB"), Some(&FieldValue::Binary(vec![0xc3, 0x28, 0x20, 0x20])));
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text("ab".to_string())));
+    }
+
+    #[test]
+    fn empty_dates_and_datetimes() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_empty_dates.dbf");
+        let fields = vec![
+            FieldDescriptor::new("BORN", b'D', 8, 0).unwrap(),
+            FieldDescriptor::new("STAMP", b'T', 8, 0).unwrap()
+        ];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        for _ in 0..3 {
+            writer.write_record(&HashMap::new()).unwrap();
+        }
+        writer.finish().unwrap();
+
+        // A time without a day is still empty, while days which don't exist and times past
+        // midnight are errors for their record only
+        let mut bytes = fs::read(&path).unwrap();
+        let header_size = 32 + 2 * 32 + 1 + 263;
+        bytes[header_size + 13..header_size + 17].copy_from_slice(&[0x10, 0, 0, 0]);
+        bytes[header_size + 18..header_size + 26].copy_from_slice(b"20190231");
+        bytes[header_size + 34 + 9..header_size + 34 + 17].copy_from_slice(&[0x58, 0x68, 0x25, 0, 0, 0x5c, 0x26, 0x05]);
+        fs::write(&path, bytes).unwrap();
+        let records:Vec<Result<Record, DbaseError>> = open(path.to_str().unwrap()).unwrap().into_iter().collect();
+        let first = records[0].as_ref().unwrap();
+        assert_eq!((first.get("BORN"), first.get("STAMP")), (Some(&FieldValue::Null), Some(&FieldValue::Null)));
+        assert!(records[1].is_err());
+        assert!(records[2].is_err());
+    }
+
+    #[test]
+    fn numeric_overflow_markers() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_numeric_overflow.dbf");
+        let fields = vec![
+            FieldDescriptor::new("AMOUNT", b'N', 6, 2).unwrap(),
+            FieldDescriptor::new("RATIO", b'F', 5, 0).unwrap()
+        ];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("AMOUNT".to_string(), FieldValue::Overflow);
+        values.insert("RATIO".to_string(), FieldValue::Float(12.0));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+
+        let mut bytes = fs::read(&path).unwrap();
+        let header_size = 32 + 2 * 32 + 1;
+        assert_eq!(bytes[header_size + 1..header_size + 7], b"******"[..]);
+        // Some exporters keep the decimal point
+        bytes[header_size + 7..header_size + 12].copy_from_slice(b"**.**");
+        fs::write(&path, bytes).unwrap();
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("AMOUNT"), Some(&FieldValue::Overflow));
+        assert_eq!(record.get("RATIO"), Some(&FieldValue::Overflow));
+    }
+
+    #[test]
+    fn logical_letters() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_logical_letters.dbf");
+        let mut writer = create(path.to_str().unwrap(), vec![FieldDescriptor::new("ACTIVE", b'L', 1, 0).unwrap()]).unwrap();
+        let mut values = HashMap::new();
+        values.insert("ACTIVE".to_string(), FieldValue::Boolean(None));
+        for _ in 0..5 {
+            writer.write_record(&values).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let mut bytes = fs::read(&path).unwrap();
+        let header_size = 32 + 32 + 1;
+        assert_eq!(bytes[header_size + 1], b'?');
+        for (i, letter) in b"TtFf".iter().enumerate() {
+            bytes[header_size + 2 * i + 3] = *letter;
+        }
+        fs::write(&path, bytes).unwrap();
+        let values:Vec<FieldValue> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap().get("ACTIVE").unwrap().clone()).collect();
+        assert_eq!(values, [
+            FieldValue::Boolean(None),
+            FieldValue::Boolean(Some(true)),
+            FieldValue::Boolean(Some(true)),
+            FieldValue::Boolean(Some(false)),
+            FieldValue::Boolean(Some(false))
+        ]);
+    }
+
+    #[test]
+    fn wide_character_fields() {
+        use dbase_parser::create;
+        use std::collections::HashMap;
+        use std::fs;
+
+        // A 300 byte character field, whose decimal count holds the high byte of its length
+        let mut bytes = vec![0x03, 124, 1, 1, 1, 0, 0, 0, 97, 0, 48, 1];
+        bytes.resize(32, 0);
+        for (name, type_code, length) in [("NAME", b'C', [0x2c, 0x01]), ("ID", b'N', [3, 0])].iter() {
+            let mut descriptor = name.as_bytes().to_vec();
+            descriptor.resize(11, 0);
+            descriptor.push(*type_code);
+            descriptor.extend_from_slice(&[0; 4]);
+            descriptor.extend_from_slice(length);
+            descriptor.resize(32, 0);
+            bytes.append(&mut descriptor);
+        }
+        bytes.push(0x0d);
+        let name = format!("{}y", "x".repeat(299));
+        bytes.push(b' ');
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(b"  7\x1a");
+        let path = std::env::temp_dir().join("dbase_parser_wide_character.dbf");
+        fs::write(&path, bytes).unwrap();
+
+        let db = open(path.to_str().unwrap()).unwrap();
+        assert_eq!((db.header.fields()[0].length(), db.header.fields()[0].decimal_count()), (300, 0));
+        let fields = db.header.fields().to_vec();
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text(name.clone())));
+        assert_eq!(record.get("ID"), Some(&FieldValue::Numeric(7.0)));
+
+        // And it is written back the same way
+        let copy = std::env::temp_dir().join("dbase_parser_wide_character_copy.dbf");
+        let mut writer = create(copy.to_str().unwrap(), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("NAME".to_string(), FieldValue::Text(name.clone()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+        assert_eq!(fs::read(&copy).unwrap()[32 + 16..32 + 18], [0x2c, 0x01]);
+        let record = open(copy.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("NAME"), Some(&FieldValue::Text(name)));
+    }
+
+    #[test]
+    fn convert_field_values() {
+        use chrono::{NaiveDate, NaiveDateTime};
+        use std::convert::TryFrom;
+
+        assert_eq!(String::try_from(FieldValue::Text("abc".to_string())).unwrap(), "abc");
+        assert_eq!(String::try_from(&FieldValue::Text("abc".to_string())).unwrap(), "abc");
+        assert_eq!(f64::try_from(&FieldValue::Integer(3)).unwrap(), 3.0);
+        assert_eq!(f64::try_from(FieldValue::Currency(12_500)).unwrap(), 1.25);
+        assert_eq!(i32::try_from(&FieldValue::Numeric(42.0)).unwrap(), 42);
+        assert_eq!(i64::try_from(FieldValue::Numeric(5e12)).unwrap(), 5_000_000_000_000);
+        assert!(bool::try_from(&FieldValue::Boolean(Some(true))).unwrap());
+        let date = NaiveDate::from_ymd_opt(2019, 9, 1).unwrap();
+        assert_eq!(NaiveDate::try_from(FieldValue::Date(Utc.ymd(2019, 9, 1))).unwrap(), date);
+        assert_eq!(NaiveDateTime::try_from(FieldValue::Date(Utc.ymd(2019, 9, 1))).unwrap(), date.and_hms_opt(0, 0, 0).unwrap());
+
+        let refused = [
+            i32::try_from(&FieldValue::Numeric(1.5)).is_err(),
+            i32::try_from(&FieldValue::Numeric(5e12)).is_err(),
+            bool::try_from(&FieldValue::Boolean(None)).is_err(),
+            f64::try_from(&FieldValue::Null).is_err(),
+            String::try_from(&FieldValue::Integer(1)).is_err()
+        ];
+        assert!(refused.iter().all(|r| *r));
+        match String::try_from(FieldValue::Null) {
+            Err(DbaseError::InvalidConversion { target, .. }) => assert_eq!(target, "String"),
+            r => panic!("Expected an invalid conversion, got {:?}", r)
+        }
+    }
+
+    #[test]
+    fn display_field_values() {
+        let rendered:Vec<String> = [
+            FieldValue::Text("abc".to_string()),
+            FieldValue::Numeric(1.5),
+            FieldValue::Integer(-7),
+            FieldValue::Currency(-12_500),
+            FieldValue::Boolean(Some(true)),
+            FieldValue::Boolean(Some(false)),
+            FieldValue::Boolean(None),
+            FieldValue::Date(Utc.ymd(2019, 9, 1)),
+            FieldValue::DateTime(Utc.with_ymd_and_hms(2019, 9, 1, 13, 5, 9).unwrap()),
+            FieldValue::Binary(vec![0xca, 0xfe]),
+            FieldValue::Null
+        ].iter().map(|r| r.to_string()).collect();
+        assert_eq!(rendered, ["abc", "1.5", "-7", "-1.2500", "Y", "N", "?", "20190901", "20190901130509", "cafe", ""]);
+        assert_eq!(format!("{:.2}", FieldValue::Numeric(1.5)), "1.50");
+    }
+
+    #[test]
+    fn lazy_memos() {
+        let mut db = OpenOptions::new().lazy_memos(true).open("tests/reference_fpt.dbf").unwrap();
+        let records:Vec<Record> = db.records_range(0, 2).map(|r| r.unwrap()).collect();
+        let handle = match records[1].get("notes") {
+            Some(FieldValue::MemoRef(handle)) => handle.clone(),
+            r => panic!("Expected an unread memo, got {:?}", r)
+        };
+        assert!(handle.is_text());
+        assert_eq!(records[1].get("Name"), Some(&FieldValue::Text("Another".to_string())));
+        assert_eq!(db.resolve_memo(&handle).unwrap(), FieldValue::Text("This is a note.".to_string()));
+        assert_eq!(handle.read(&mut db).unwrap(), FieldValue::Text("This is a note.".to_string()));
+
+        // A single memo can be read straight from its record, whether memos are lazy or not
+        assert_eq!(db.memo_for(1, "notes").unwrap(), FieldValue::Text("This is a note.".to_string()));
+        assert_eq!(db.memo_for(1, "Name").unwrap(), FieldValue::Text("Another".to_string()));
+        assert!(matches!(db.memo_for(1, "missing"), Err(DbaseError::FieldNotFound(_))));
+        assert!(matches!(db.memo_for(100, "notes"), Err(DbaseError::InvalidRange { .. })));
+        let mut db = open("tests/reference_fpt.dbf").unwrap();
+        assert_eq!(db.memo_for(1, "notes").unwrap(), FieldValue::Text("This is a note.".to_string()));
+    }
+
+    #[test]
+    fn numeric_as_integer() {
+        let db = OpenOptions::new().numeric_as_integer(true).open("tests/reference_gis.dbf").unwrap();
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("ID_0"), Some(&FieldValue::Integer(131)));
+        assert_eq!(record.get("ID_4"), Some(&FieldValue::Integer(1)));
+
+        let db = OpenOptions::new().numeric_as_integer(true).open("tests/five_records.dbf").unwrap();
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("ID"), Some(&FieldValue::Integer(1)));
+
+        // Identifiers beyond the range of an i32 stay numbers
+        let path = std::env::temp_dir().join("dbase_parser_numeric_as_integer.dbf");
+        let mut writer = dbase_parser::create(path.to_str().unwrap(), vec![
+            dbase_parser::header::FieldDescriptor::new("ID", b'N', 11, 0).unwrap()
+        ]).unwrap();
+        for id in [2147483647.0, 12345678901.0] {
+            let mut values = std::collections::HashMap::new();
+            values.insert("ID".to_string(), FieldValue::Numeric(id));
+            writer.write_record(&values).unwrap();
+        }
+        writer.finish().unwrap();
+        let db = OpenOptions::new().numeric_as_integer(true).open(path.to_str().unwrap()).unwrap();
+        let records:Vec<Record> = db.into_iter().map(Result::unwrap).collect();
+        assert_eq!(records[0].get("ID"), Some(&FieldValue::Integer(2147483647)));
+        assert_eq!(records[1].get("ID"), Some(&FieldValue::Numeric(12345678901.0)));
+    }
+
+    #[test]
+    fn field_types_describe_and_encode() {
+        use dbase_parser::create;
+        use dbase_parser::fields::{FieldType, FieldTypeOldNumeric};
+        use dbase_parser::header::{Database, FieldDescriptor, FieldDescriptorBuilder};
+        use std::collections::HashMap;
+        use std::io;
+        use std::ops::RangeInclusive;
+
+        let numeric = FieldTypeOldNumeric { decimal_count: 2 };
+        assert_eq!((numeric.type_code(), numeric.length_range()), (Some(b'N'), 1..=20));
+        let amount = FieldDescriptor::new("AMOUNT", b'N', 6, 2).unwrap();
+        assert_eq!(numeric.encode(&amount, &FieldValue::Numeric(1.5)).unwrap(), b"  1.50");
+
+        // A hexadecimal byte
+        #[derive(Debug)]
+        struct FieldTypeH;
+        impl FieldType for FieldTypeH {
+            fn parse(&self, _database: &mut Database, data: Vec<u8>) -> Result<FieldValue, io::Error> {
+                let text = String::from_utf8_lossy(&data).to_string();
+                i32::from_str_radix(&text, 16).map(FieldValue::Integer).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            fn length_range(&self) -> RangeInclusive<u16> {
+                2..=2
+            }
+            fn encode(&self, field: &FieldDescriptor, value: &FieldValue) -> Result<Vec<u8>, DbaseError> {
+                match value {
+                    FieldValue::Integer(r) => Ok(format!("{:02x}", r).into_bytes()),
+                    r => Err(DbaseError::InvalidFieldValue { field: field.name().to_string(), message: format!("{:?} is not a byte", r) })
+                }
+            }
+        }
+
+        assert!(FieldDescriptorBuilder::new("CODE", b'H').field_type(Box::new(FieldTypeH)).length(3).build().is_err());
+        let code = FieldDescriptorBuilder::new("CODE", b'H').field_type(Box::new(FieldTypeH)).build().unwrap();
+        assert_eq!(code.length(), 2);
+        let path = std::env::temp_dir().join("dbase_parser_custom_encode.dbf");
+        let mut writer = create(path.to_str().unwrap(), vec![code]).unwrap();
+        let mut values = HashMap::new();
+        values.insert("CODE".to_string(), FieldValue::Integer(0xab));
+        writer.write_record(&values).unwrap();
+        values.insert("CODE".to_string(), FieldValue::Null);
+        assert!(writer.write_record(&values).is_err());
+        writer.finish().unwrap();
+        let db = OpenOptions::new().register_field_type(b'H', Box::new(FieldTypeH)).open(path.to_str().unwrap()).unwrap();
+        let record = db.into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("CODE"), Some(&FieldValue::Integer(0xab)));
+    }
+
+    #[test]
+    fn dbase4_memo_blocks() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::{FieldDescriptor, Version};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_dbase4_memo_blocks.dbf");
+        let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = DbfWriter::create_as(path.to_str().unwrap(), Version::dBASE4Table(false), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("NOTES".to_string(), FieldValue::Text("placeholder".to_string()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+
+        // A dBASE IV memo file with 64 byte blocks, declared only in bytes 20 and 21, holding a
+        // memo longer than a block which contains 0x1A
+        let text = format!("{}\x1a{}", "a".repeat(60), "b".repeat(20));
+        let mut memo = vec![0; 64];
+        memo[0] = 4;
+        memo[20] = 64;
+        memo.extend_from_slice(&[0xff, 0xff, 0x08, 0x00]);
+        memo.extend_from_slice(&(text.len() as u32 + 8).to_le_bytes());
+        memo.extend_from_slice(text.as_bytes());
+        memo.resize(4 * 64, 0);
+        fs::write(path.with_extension("dbt"), memo).unwrap();
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("NOTES"), Some(&FieldValue::Text(text)));
+
+        // Appended memos follow the blocks of the file
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        values.insert("NOTES".to_string(), FieldValue::Text("Appended".to_string()));
+        db.append(&values).unwrap();
+        assert_eq!(fs::read(path.with_extension("dbt")).unwrap()[4 * 64..4 * 64 + 4], [0xff, 0xff, 0x08, 0x00]);
+        let records:Vec<Record> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[1].get("NOTES"), Some(&FieldValue::Text("Appended".to_string())));
+    }
+
+    #[test]
+    fn memo_block_size_override() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_memo_block_size.dbf");
+        let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("NOTES".to_string(), FieldValue::Text("Sold by the dozen".to_string()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+
+        // A bogus block size in the header of the .dbt file
+        let memo_path = path.with_extension("dbt");
+        let mut memo = fs::read(&memo_path).unwrap();
+        memo[4] = 7;
+        fs::write(&memo_path, memo).unwrap();
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_ne!(record.get("NOTES"), Some(&FieldValue::Text("Sold by the dozen".to_string())));
+
+        let mut options = OpenOptions::new();
+        options.memo_block_size(512).write(true);
+        let mut db = options.open(path.to_str().unwrap()).unwrap();
+        values.insert("NOTES".to_string(), FieldValue::Text("Appended".to_string()));
+        db.append(&values).unwrap();
+        assert_eq!(fs::read(&memo_path).unwrap().len(), 3 * 512);
+        let records:Vec<Record> = options.open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("NOTES"), Some(&FieldValue::Text("Sold by the dozen".to_string())));
+        assert_eq!(records[1].get("NOTES"), Some(&FieldValue::Text("Appended".to_string())));
+    }
+
+    #[test]
+    fn memo_cache() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_memo_cache.dbf");
+        let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        for note in ["first", "second"].iter() {
+            let mut values = HashMap::new();
+            values.insert("NOTES".to_string(), FieldValue::Text(note.to_string()));
+            writer.write_record(&values).unwrap();
+        }
+        writer.finish().unwrap();
+
+        // Cached memos are not read again from the memo file, which changes under them here
+        let memo_path = path.with_extension("dbt");
+        let memo = fs::read(&memo_path).unwrap();
+        let mut changed = memo.clone();
+        changed[512..517].copy_from_slice(b"FIRST");
+        changed[1024..1030].copy_from_slice(b"SECOND");
+        let text = |value: &str| FieldValue::Text(value.to_string());
+
+        let mut db = OpenOptions::new().memo_cache(1).open(path.to_str().unwrap()).unwrap();
+        let mut uncached = open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.memo_for(0, "NOTES").unwrap(), text("first"));
+        assert_eq!(uncached.memo_for(0, "NOTES").unwrap(), text("first"));
+        fs::write(&memo_path, &changed).unwrap();
+        assert_eq!(db.memo_for(0, "NOTES").unwrap(), text("first"));
+        assert_eq!(uncached.memo_for(0, "NOTES").unwrap(), text("FIRST"));
+
+        // Reading another memo evicts the least recently read one
+        assert_eq!(db.memo_for(1, "NOTES").unwrap(), text("SECOND"));
+        fs::write(&memo_path, &memo).unwrap();
+        assert_eq!(db.memo_for(0, "NOTES").unwrap(), text("first"));
+        assert_eq!(db.memo_for(0, "NOTES").unwrap(), text("first"));
+        assert_eq!(db.memo_for(1, "NOTES").unwrap(), text("second"));
+    }
+
+    #[test]
+    fn stream_memos() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::{FieldDescriptor, Version};
+        use std::collections::HashMap;
+        use std::io::Read;
+
+        let mut db = OpenOptions::new().lazy_memos(true).open("tests/reference_fpt.dbf").unwrap();
+        let record = db.record(1).unwrap().unwrap();
+        let handle = match record.get("notes") {
+            Some(FieldValue::MemoRef(handle)) => handle.clone(),
+            r => panic!("Expected an unread memo, got {:?}", r)
+        };
+        let mut memo = String::new();
+        db.memo_reader(&handle).unwrap().read_to_string(&mut memo).unwrap();
+        assert_eq!(memo, "This is a note.");
+
+        // Memos longer than a block, in both .dbt layouts
+        let long_note = "x".repeat(2000);
+        for version in [Version::dBASE3(false), Version::dBASE4Table(false)] {
+            let path = std::env::temp_dir().join("dbase_parser_stream_memos.dbf");
+            let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+            let mut writer = DbfWriter::create_as(path.to_str().unwrap(), version, fields).unwrap();
+            let mut values = HashMap::new();
+            values.insert("NOTES".to_string(), FieldValue::Text(long_note.clone()));
+            writer.write_record(&values).unwrap();
+            writer.finish().unwrap();
+
+            let mut db = OpenOptions::new().lazy_memos(true).open(path.to_str().unwrap()).unwrap();
+            let record = db.record(0).unwrap().unwrap();
+            let handle = match record.get("NOTES") {
+                Some(FieldValue::MemoRef(handle)) => handle.clone(),
+                r => panic!("Expected an unread memo, got {:?}", r)
+            };
+            let mut memo = vec![];
+            db.memo_reader(&handle).unwrap().read_to_end(&mut memo).unwrap();
+            assert_eq!(memo, long_note.as_bytes());
+        }
+    }
+
+    #[test]
+    fn custom_memo_container() {
+        use dbase_parser::header::MemoContainer;
+        use std::io;
+
+        // Memos kept in memory, keyed by their block number
+        #[derive(Debug)]
+        struct StubMemos;
+        impl MemoContainer for StubMemos {
+            fn memo(&mut self, id: Vec<u8>) -> Result<Vec<u8>, io::Error> {
+                let mut block = [0; 4];
+                block.copy_from_slice(&id);
+                Ok(format!("memo {}", u32::from_le_bytes(block)).into_bytes())
+            }
+        }
+
+        let mut db = open("tests/reference_fpt.dbf").unwrap();
+        db.with_memo_container(Box::new(StubMemos));
+        let record = db.record(1).unwrap().unwrap();
+        assert!(matches!(record.get("notes"), Some(FieldValue::Text(memo)) if memo.starts_with("memo ")));
+
+        // The default reader reads the whole memo
+        let mut db = OpenOptions::new().lazy_memos(true).open("tests/reference_fpt.dbf").unwrap();
+        db.with_memo_container(Box::new(StubMemos));
+        let handle = match db.record(1).unwrap().unwrap().get("notes") {
+            Some(FieldValue::MemoRef(handle)) => handle.clone(),
+            r => panic!("Expected an unread memo, got {:?}", r)
+        };
+        let mut memo = String::new();
+        io::Read::read_to_string(&mut db.memo_reader(&handle).unwrap(), &mut memo).unwrap();
+        assert_eq!(FieldValue::Text(memo), db.resolve_memo(&handle).unwrap());
+    }
+
+    #[test]
+    fn explicit_memo_path() {
+        use std::collections::HashMap;
+        use std::fs;
+
+        let dir = std::env::temp_dir().join("dbase_parser_explicit_memo_path");
+        fs::create_dir_all(dir.join("memos")).unwrap();
+        let path = dir.join("table.dbf");
+        let memo_path = dir.join("memos").join("notes_of_table.fpt");
+        fs::copy("tests/reference_fpt.dbf", &path).unwrap();
+        fs::copy("tests/reference_fpt.fpt", &memo_path).unwrap();
+
+        let record = open(path.to_str().unwrap()).unwrap().record(1).unwrap().unwrap();
+        assert_ne!(record.get("notes"), Some(&FieldValue::Text("This is a note.".to_string())));
+        let record = OpenOptions::new().memo_path(&memo_path).open(path.to_str().unwrap()).unwrap().record(1).unwrap().unwrap();
+        assert_eq!(record.get("notes"), Some(&FieldValue::Text("This is a note.".to_string())));
+
+        // Atomic writes move the memo file back where it was found
+        let mut db = OpenOptions::new().memo_path(&memo_path).atomic(true).open(path.to_str().unwrap()).unwrap();
+        let mut values = HashMap::new();
+        values.insert("notes".to_string(), FieldValue::Text("Kept elsewhere.".to_string()));
+        db.append(&values).unwrap();
+        db.commit().unwrap();
+        let mut db = OpenOptions::new().memo_path(&memo_path).open(path.to_str().unwrap()).unwrap();
+        let record_count = db.header.record_count;
+        let record = db.record(record_count - 1).unwrap().unwrap();
+        assert_eq!(record.get("notes"), Some(&FieldValue::Text("Kept elsewhere.".to_string())));
+        assert!(!dir.join("table.fpt").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn upper_case_memo_extensions() {
+        use std::fs;
+
+        let dir = std::env::temp_dir().join("dbase_parser_upper_case_memo");
+        fs::create_dir_all(&dir).unwrap();
+        for table in ["table.dbf", "TABLE.DBF"].iter() {
+            let path = dir.join(table);
+            fs::copy("tests/reference_fpt.dbf", &path).unwrap();
+            fs::copy("tests/reference_fpt.fpt", path.with_extension("FPT")).unwrap();
+            let record = open(path.to_str().unwrap()).unwrap().record(1).unwrap().unwrap();
+            assert_eq!(record.get("notes"), Some(&FieldValue::Text("This is a note.".to_string())));
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn memo_containers_over_readers() {
+        use dbase_parser::header::{DBaseMemoContainer, Database, FoxProMemoContainer};
+        use std::fs::{self, File};
+        use std::io::Cursor;
+
+        let memo = Cursor::new(fs::read("tests/reference_fpt.fpt").unwrap());
+        let mut db = Database::parse("reference_fpt.dbf", File::open("tests/reference_fpt.dbf").unwrap()).unwrap();
+        db.with_memo_container(Box::new(FoxProMemoContainer::from_reader(memo).unwrap()));
+        let record = db.record(1).unwrap().unwrap();
+        assert_eq!(record.get("notes"), Some(&FieldValue::Text("This is a note.".to_string())));
+
+        let expected = open("tests/reference_dbase.dbf").unwrap().record(0).unwrap().unwrap();
+        let memo = Cursor::new(fs::read("tests/reference_dbase.dbt").unwrap());
+        let mut db = Database::parse("reference_dbase.dbf", File::open("tests/reference_dbase.dbf").unwrap()).unwrap();
+        db.with_memo_container(Box::new(DBaseMemoContainer::from_reader(memo).unwrap()));
+        assert_eq!(db.record(0).unwrap().unwrap().fields, expected.fields);
+    }
+
+    #[test]
+    fn fpt_memo_types() {
+        use dbase_parser::header::MemoType;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_fpt_memo_types.dbf");
+        fs::copy("tests/reference_fpt.dbf", &path).unwrap();
+        fs::copy("tests/reference_fpt.fpt", path.with_extension("fpt")).unwrap();
+        let mut db = open(path.to_str().unwrap()).unwrap();
+        let pointer = match OpenOptions::new().lazy_memos(true).open(path.to_str().unwrap()).unwrap().record(1).unwrap().unwrap().get("notes") {
+            Some(FieldValue::MemoRef(handle)) => handle.pointer().to_vec(),
+            r => panic!("Expected an unread memo, got {:?}", r)
+        };
+        let container = db.memo.as_mut().unwrap();
+        assert_eq!(container.typed_memo(pointer.clone()).unwrap(), (b"This is a note.".to_vec(), Some(MemoType::Text)));
+
+        // A memo field holding a picture reads as bytes
+        let mut memo = fs::read("tests/reference_fpt.fpt").unwrap();
+        let start = 64 * u32::from_le_bytes([pointer[0], pointer[1], pointer[2], pointer[3]]) as usize;
+        memo[start + 3] = 0;
+        fs::write(path.with_extension("fpt"), memo).unwrap();
+        let record = open(path.to_str().unwrap()).unwrap().record(1).unwrap().unwrap();
+        assert_eq!(record.get("notes"), Some(&FieldValue::Binary(b"This is a note.".to_vec())));
+    }
+
+    #[test]
+    fn memo_lengths_are_bounded() {
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_memo_lengths.dbf");
+        fs::copy("tests/reference_fpt.dbf", &path).unwrap();
+        let mut memo = fs::read("tests/reference_fpt.fpt").unwrap();
+        assert!(OpenOptions::new().max_memo_size(4).open("tests/reference_fpt.dbf").unwrap().record(1).is_err());
+        assert!(OpenOptions::new().max_memo_size(4).open("tests/reference_dbase.dbf").unwrap().into_iter().any(|r| r.is_err()));
+        assert!(OpenOptions::new().max_memo_size(15).open("tests/reference_fpt.dbf").unwrap().record(1).is_ok());
+
+        // A corrupt length past the end of the file
+        memo[580..584].copy_from_slice(&[0xff, 0xff, 0xff, 0xf0]);
+        fs::write(path.with_extension("fpt"), memo).unwrap();
+        let error = open(path.to_str().unwrap()).unwrap().record(1).unwrap_err();
+        assert!(error.to_string().contains("past the end of the memo file"));
+    }
+
+    #[test]
+    fn memo_pointers_follow_field_length() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_memo_pointers.dbf");
+        let fields = vec![
+            FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap(),
+            FieldDescriptor::new("CODE", b'C', 1, 0).unwrap()
+        ];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("NOTES".to_string(), FieldValue::Text("A note".to_string()));
+        values.insert("CODE".to_string(), FieldValue::Text("x".to_string()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+
+        // The same .dbt memo file behind a 4 byte pointer, as some FoxPro era tools write them
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[32 + 16] = 4;
+        bytes[64 + 16] = 7;
+        let record = 32 + 2 * 32 + 1;
+        bytes[record + 1..record + 12].copy_from_slice(b"\x01\x00\x00\x00      x");
+        fs::write(&path, bytes).unwrap();
+        let record = open(path.to_str().unwrap()).unwrap().into_iter().next().unwrap().unwrap();
+        assert_eq!(record.get("NOTES"), Some(&FieldValue::Text("A note".to_string())));
+        assert_eq!(record.get("CODE"), Some(&FieldValue::Text("x".to_string())));
+    }
+
+    #[test]
+    fn memo_end_markers() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::{FieldDescriptor, MemoEnd, Version};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_memo_end.dbf");
+        let note = format!("{}\u{1a}{}", "a".repeat(100), "b".repeat(600));
+        let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = DbfWriter::create_as(path.to_str().unwrap(), Version::dBASE3(true), fields).unwrap();
+        let mut values = HashMap::new();
+        values.insert("NOTES".to_string(), FieldValue::Text(note.clone()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+
+        let record = open(path.to_str().unwrap()).unwrap().record(0).unwrap().unwrap();
+        assert_eq!(record.get("NOTES"), Some(&FieldValue::Text("a".repeat(100))));
+        let mut options = OpenOptions::new();
+        options.memo_end(MemoEnd::DoubleMarker);
+        let record = options.open(path.to_str().unwrap()).unwrap().record(0).unwrap().unwrap();
+        assert_eq!(record.get("NOTES"), Some(&FieldValue::Text(note.clone())));
+
+        // dBASE IV memos in a memo file without the dBASE IV header
+        let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = DbfWriter::create_as(path.to_str().unwrap(), Version::dBASE4Table(false), fields).unwrap();
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+        let mut memo = fs::read(path.with_extension("dbt")).unwrap();
+        memo[16] = 0x03;
+        memo[20] = 0;
+        memo[21] = 0;
+        fs::write(path.with_extension("dbt"), memo).unwrap();
+        let record = options.open(path.to_str().unwrap()).unwrap().record(0).unwrap().unwrap();
+        assert_eq!(record.get("NOTES"), Some(&FieldValue::Text(note)));
+    }
+
+    #[test]
+    fn verify_memos() {
+        use dbase_parser::create;
+        use dbase_parser::header::{FieldDescriptor, MemoProblem};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let path = std::env::temp_dir().join("dbase_parser_verify_memos.dbf");
+        let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        for note in &["A note", "Another note"] {
+            let mut values = HashMap::new();
+            values.insert("NOTES".to_string(), FieldValue::Text(note.to_string()));
+            writer.write_record(&values).unwrap();
+        }
+        writer.finish().unwrap();
+        assert_eq!(open(path.to_str().unwrap()).unwrap().verify_memos().unwrap(), vec![]);
+        assert_eq!(open("tests/reference_fpt.dbf").unwrap().verify_memos().unwrap(), vec![]);
+        assert_eq!(open("tests/reference_dbase.dbf").unwrap().verify_memos().unwrap(), vec![]);
+
+        // Both records point to the first memo, leaving the second one behind
+        let record = 32 + 32 + 1;
+        let mut bytes = fs::read(&path).unwrap();
+        let first = bytes[record + 1..record + 11].to_vec();
+        bytes[record + 12..record + 22].copy_from_slice(&first);
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(open(path.to_str().unwrap()).unwrap().verify_memos().unwrap(), vec![MemoProblem::Orphan { block: 2, count: 1 }]);
+
+        bytes[record + 12..record + 22].copy_from_slice(b"      9999");
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(open(path.to_str().unwrap()).unwrap().verify_memos().unwrap(), vec![
+            MemoProblem::OutOfBounds { block: 9999 },
+            MemoProblem::Orphan { block: 2, count: 1 }
+        ]);
+        bytes[record + 12..record + 22].copy_from_slice(b"  note 12 ");
+        fs::write(&path, &bytes).unwrap();
+        assert_eq!(open(path.to_str().unwrap()).unwrap().verify_memos().unwrap()[0], MemoProblem::InvalidPointer(b"  note 12 ".to_vec()));
+
+        // A corrupt length past the end of the memo file
+        let path = std::env::temp_dir().join("dbase_parser_verify_fpt.dbf");
+        fs::copy("tests/reference_fpt.dbf", &path).unwrap();
+        let mut memo = fs::read("tests/reference_fpt.fpt").unwrap();
+        memo[580..584].copy_from_slice(&[0xff, 0xff, 0xff, 0xf0]);
+        fs::write(path.with_extension("fpt"), memo).unwrap();
+        let problems = open(path.to_str().unwrap()).unwrap().verify_memos().unwrap();
+        assert!(matches!(problems[0], MemoProblem::InvalidMemo { block: 9, .. }));
+    }
+
+    #[test]
+    fn pack_rebuilds_memos() {
+        use dbase_parser::DbfWriter;
+        use dbase_parser::header::{FieldDescriptor, Version};
+        use std::collections::HashMap;
+        use std::fs;
+
+        let layouts = [
+            (Version::dBASE3(true), FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap(), "dbt"),
+            (Version::VisualFoxPro(false, false), FieldDescriptor::new("NOTES", b'M', 4, 0).unwrap(), "fpt")
+        ];
+        for (version, notes, extension) in layouts {
+            let path = std::env::temp_dir().join(format!("dbase_parser_pack_memos_{}.dbf", extension));
+            let fields = vec![notes, FieldDescriptor::new("NAME", b'C', 10, 0).unwrap()];
+            let mut writer = DbfWriter::create_as(path.to_str().unwrap(), version, fields).unwrap();
+            for (name, note) in &[("one", "a".repeat(600)), ("two", "b".repeat(600)), ("three", String::new())] {
+                let mut values = HashMap::new();
+                values.insert("NAME".to_string(), FieldValue::Text(name.to_string()));
+                values.insert("NOTES".to_string(), FieldValue::Text(note.clone()));
+                writer.write_record(&values).unwrap();
+            }
+            writer.finish().unwrap();
+            let memo_path = path.with_extension(extension);
+            let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+            db.mark_deleted(0).unwrap();
+            // Leaves the first memo of the second record behind
+            db.patch_field(1, "NOTES", &FieldValue::Text("c".repeat(10))).unwrap();
+            let memo_size = fs::metadata(&memo_path).unwrap().len();
+            assert_eq!(db.pack().unwrap(), 1);
+            assert!(fs::metadata(&memo_path).unwrap().len() < memo_size);
+            assert_eq!(db.verify_memos().unwrap(), vec![]);
+            assert_eq!(db.memo_for(0, "NOTES").unwrap(), FieldValue::Text("c".repeat(10)));
+
+            let records:Vec<_> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+            assert_eq!(records.len(), 2);
+            assert_eq!(records[0].get("NOTES"), Some(&FieldValue::Text("c".repeat(10))));
+            assert_eq!(records[1].get("NAME"), Some(&FieldValue::Text("three".to_string())));
+            assert_eq!(records[1].get("NOTES"), Some(&FieldValue::Text(String::new())));
+            assert!(fs::read_dir(std::env::temp_dir()).unwrap().all(|entry| !entry.unwrap().file_name().to_string_lossy().starts_with(&format!("dbase_parser_pack_memos_{}.tmp", extension))));
+        }
+    }
+
+    #[test]
+    fn smt_memos() {
+        use dbase_parser::create;
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+        use std::fs;
+        use std::io::Read;
+
+        let path = std::env::temp_dir().join("dbase_parser_smt.dbf");
+        let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        for _ in 0..3 {
+            writer.write_record(&HashMap::new()).unwrap();
+        }
+        writer.finish().unwrap();
+        fs::remove_file(path.with_extension("dbt")).unwrap();
+
+        // A string in block 16 and a serialized number in block 17 of 32 byte blocks, the records
+        // holding the type, length and block of their memo
+        let mut memo = vec![];
+        memo.extend_from_slice(&18u32.to_le_bytes());
+        memo.extend_from_slice(&32u32.to_le_bytes());
+        memo.resize(512, 0);
+        memo.extend_from_slice(b"A HiPer-Six note");
+        memo.resize(544, 0);
+        memo.extend_from_slice(&[1, 2, 3, 4]);
+        memo.resize(576, 0);
+        fs::write(path.with_extension("smt"), &memo).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[0] = 0xe5;
+        let record = 32 + 32 + 1;
+        bytes[record + 1..record + 11].copy_from_slice(&[1, 0, 16, 0, 0, 0, 16, 0, 0, 0]);
+        bytes[record + 12..record + 22].copy_from_slice(&[2, 0, 4, 0, 0, 0, 17, 0, 0, 0]);
+        fs::write(&path, &bytes).unwrap();
+
+        let records:Vec<_> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records[0].get("NOTES"), Some(&FieldValue::Text("A HiPer-Six note".to_string())));
+        assert_eq!(records[1].get("NOTES"), Some(&FieldValue::Binary(vec![1, 2, 3, 4])));
+        assert_eq!(records[2].get("NOTES"), Some(&FieldValue::Text(String::new())));
+        assert_eq!(open(path.to_str().unwrap()).unwrap().verify_memos().unwrap(), vec![]);
+
+        let mut db = OpenOptions::new().lazy_memos(true).open(path.to_str().unwrap()).unwrap();
+        let handle = match db.record(0).unwrap().unwrap().fields.remove("NOTES") {
+            Some(FieldValue::MemoRef(handle)) => handle,
+            value => panic!("{:?}", value)
+        };
+        let mut note = String::new();
+        db.memo_reader(&handle).unwrap().read_to_string(&mut note).unwrap();
+        assert_eq!(note, "A HiPer-Six note");
+
+        // SMT memos cannot be written
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        assert!(db.patch_field(0, "NOTES", &FieldValue::Text("Another note".to_string())).is_err());
+
+        // Packing keeps the memo file and the pointers to it as they are
+        let memo_size = fs::metadata(path.with_extension("smt")).unwrap().len();
+        db.mark_deleted(0).unwrap();
+        assert_eq!(db.pack().unwrap(), 1);
+        assert_eq!(fs::metadata(path.with_extension("smt")).unwrap().len(), memo_size);
+        let records:Vec<_> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].get("NOTES"), Some(&FieldValue::Binary(vec![1, 2, 3, 4])));
+    }
+
+    #[test]
+    fn memo_stats() {
+        use dbase_parser::create;
+        use dbase_parser::header::{FieldDescriptor, MemoStats};
+        use std::collections::HashMap;
+
+        let path = std::env::temp_dir().join("dbase_parser_memo_stats.dbf");
+        let fields = vec![FieldDescriptor::new("NOTES", b'M', 10, 0).unwrap()];
+        let mut writer = create(path.to_str().unwrap(), fields).unwrap();
+        for note in &["a".repeat(600), "b".repeat(600)] {
+            let mut values = HashMap::new();
+            values.insert("NOTES".to_string(), FieldValue::Text(note.clone()));
+            writer.write_record(&values).unwrap();
+        }
+        writer.finish().unwrap();
+        assert_eq!(open("tests/five_records.dbf").unwrap().memo_stats().unwrap(), None);
+
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        let stats = MemoStats { block_size: 512, block_count: 5, next_available: 5, used_blocks: None, free_blocks: None };
+        assert_eq!(db.memo_stats().unwrap(), Some(stats));
+        // The second memo is left behind, two blocks which packing reclaims
+        db.patch_field(1, "NOTES", &FieldValue::Text("c".to_string())).unwrap();
+        db.verify_memos().unwrap();
+        let stats = MemoStats { block_size: 512, block_count: 6, next_available: 6, used_blocks: Some(3), free_blocks: Some(2) };
+        assert_eq!(db.memo_stats().unwrap(), Some(stats));
+        db.pack().unwrap();
+        db.verify_memos().unwrap();
+        let stats = MemoStats { block_size: 512, block_count: 4, next_available: 4, used_blocks: Some(3), free_blocks: Some(0) };
+        assert_eq!(db.memo_stats().unwrap(), Some(stats));
+    }
+
+    #[test]
+    fn dos_code_page_round_trip() {
+        use dbase_parser::{create, CodePage};
+        use dbase_parser::header::FieldDescriptor;
+        use std::collections::HashMap;
+
+        let path = std::env::temp_dir().join("dbase_parser_dos_850.dbf");
+        let mut writer = create(path.to_str().unwrap(), vec![FieldDescriptor::new("NAME", b'C', 10, 0).unwrap()]).unwrap();
+        writer.code_page(CodePage::Dos850);
+        let mut values = HashMap::new();
+        values.insert("NAME".to_string(), FieldValue::Text("Café Ø".to_string()));
+        writer.write_record(&values).unwrap();
+        writer.finish().unwrap();
+
+        let mut db = OpenOptions::new().write(true).open(path.to_str().unwrap()).unwrap();
+        assert_eq!(db.header.encoding(), None);
+        assert_eq!(db.record(0).unwrap().unwrap().get("NAME"), Some(&FieldValue::Text("Café Ø".to_string())));
+        // New records are written in the code page of the table too
+        values.insert("NAME".to_string(), FieldValue::Text("Ærø".to_string()));
+        db.append(&values).unwrap();
+        db.patch_field(0, "NAME", &FieldValue::Text("Noël".to_string())).unwrap();
+        let names:Vec<_> = open(path.to_str().unwrap()).unwrap().into_iter().map(|r| r.unwrap().fields.remove("NAME").unwrap()).collect();
+        assert_eq!(names, vec![FieldValue::Text("Noël".to_string()), FieldValue::Text("Ærø".to_string())]);
     }
-}
\ No newline at end of file
+}